@@ -24,6 +24,9 @@ pub enum CommandType {
 
     /// Test a Helix project
     Test(TestCommand),
+
+    /// Manage a Helix database
+    Db(DbCommand),
 }
 
 #[derive(Debug, Args)]
@@ -59,6 +62,38 @@ pub struct TestCommand {
     pub test: Option<String>,
 }
 
+#[derive(Debug, Args)]
+#[clap(name = "db", about = "Manage a Helix database")]
+pub struct DbCommand {
+    #[clap(subcommand)]
+    pub command: DbCommandType,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum DbCommandType {
+    /// Upgrade a database to the latest on-disk format
+    Migrate(MigrateCommand),
+}
+
+#[derive(Debug, Args)]
+#[clap(
+    name = "migrate",
+    about = "Upgrade a database to the latest on-disk format"
+)]
+pub struct MigrateCommand {
+    #[clap(short, long, help = "The path to the database")]
+    pub path: String,
+
+    #[clap(
+        long,
+        help = "Environment variable holding the hex encoded encryption key, if the database is encrypted"
+    )]
+    pub key_env: Option<String>,
+
+    #[clap(long, default_value_t = 0, help = "The id of the encryption key")]
+    pub key_id: u32,
+}
+
 #[derive(Debug)]
 pub enum CliError {
     Io(std::io::Error),
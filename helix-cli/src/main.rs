@@ -1,5 +1,10 @@
-use args::{CliError, HelixCLI};
+use args::{CliError, HelixCLI, MigrateCommand};
 use clap::Parser;
+use helix_engine::storage_core::{
+    encryption::{EncryptionKey, StorageEncryption},
+    migrations::CURRENT_FORMAT_VERSION,
+    storage_core::HelixGraphStorage,
+};
 use helixc::{
     // generator, 
     parser::helix_parser::{HelixParser, Source},
@@ -177,6 +182,12 @@ fn main() {
                 }
             };
         }
+        args::CommandType::Db(command) => match command.command {
+            args::DbCommandType::Migrate(command) => match migrate_db(&command) {
+                Ok(()) => {}
+                Err(e) => println!("{}", e),
+            },
+        },
         args::CommandType::Test(command) => {
             match command.path {
                 Some(path) => {
@@ -210,6 +221,47 @@ fn main() {
     }
 }
 
+fn migrate_db(command: &MigrateCommand) -> Result<(), CliError> {
+    if !check_is_dir(&command.path) {
+        return Err(CliError::from(format!(
+            "No database found at {}",
+            command.path
+        )));
+    }
+
+    let encryption = match &command.key_env {
+        Some(var) => Some(StorageEncryption::new(
+            EncryptionKey::from_env(command.key_id, var)
+                .map_err(|e| CliError::from(e.to_string()))?,
+        )),
+        None => None,
+    };
+    let storage = HelixGraphStorage::open_without_migrating(&command.path, encryption)
+        .map_err(|e| CliError::from(e.to_string()))?;
+
+    let reports = storage
+        .migrate()
+        .map_err(|e| CliError::from(e.to_string()))?;
+    if reports.is_empty() {
+        println!(
+            "Database is already at the latest format version {}",
+            CURRENT_FORMAT_VERSION
+        );
+        return Ok(());
+    }
+    for report in reports {
+        println!(
+            "\t✅ {} -> {}: \t{} ({} records rewritten)",
+            report.from, report.to, report.description, report.records_rewritten
+        );
+    }
+    println!(
+        "\nMigrated database to format version {}\n",
+        CURRENT_FORMAT_VERSION
+    );
+    Ok(())
+}
+
 fn check_and_read_files(path: &str) -> Result<Vec<DirEntry>, CliError> {
     // check there is schema and at least one query
     if !fs::read_dir(&path)
@@ -0,0 +1,370 @@
+use std::collections::VecDeque;
+
+use rand::{rngs::StdRng, seq::index, SeedableRng};
+use rayon::prelude::*;
+
+use crate::graph_algorithms::projection::{GraphProjection, ProjectionFilter};
+use crate::storage_core::storage_core::HelixGraphStorage;
+use crate::storage_core::storage_methods::EdgeDirection;
+use crate::types::GraphError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CentralityMeasure {
+    /// Number of edges in the configured direction
+    Degree,
+    /// How close a node is to every node it can reach, see `closeness_centrality`
+    Closeness,
+    /// How many shortest paths between other nodes pass through a node, see `betweenness_centrality`
+    Betweenness,
+}
+
+/// Settings for the centrality measures
+#[derive(Debug, Clone)]
+pub struct CentralityConfig {
+    pub filter: ProjectionFilter,
+    /// Direction of the edges counted for degree and followed by paths for closeness and betweenness
+    pub direction: EdgeDirection,
+    /// Number of source nodes sampled to estimate betweenness, every node if `None`
+    pub samples: Option<usize>,
+    /// Seed for picking the sampled source nodes
+    pub seed: u64,
+    /// Only the `top_k` highest scoring nodes are returned if set
+    pub top_k: Option<usize>,
+}
+
+impl Default for CentralityConfig {
+    fn default() -> Self {
+        CentralityConfig {
+            filter: ProjectionFilter::default(),
+            direction: EdgeDirection::Out,
+            samples: None,
+            seed: 0,
+            top_k: None,
+        }
+    }
+}
+
+/// Computes the given centrality measure for the filtered graph.
+/// Returns node scores highest first.
+pub fn centrality(
+    storage: &HelixGraphStorage,
+    measure: CentralityMeasure,
+    config: &CentralityConfig,
+) -> Result<Vec<(String, f64)>, GraphError> {
+    let graph = GraphProjection::load(storage, &config.filter)?;
+    let scores = centrality_of(&graph, measure, config)?;
+    Ok(ranked(&graph, scores, config.top_k))
+}
+
+/// Number of edges of each node in the configured direction
+pub fn degree_centrality(
+    storage: &HelixGraphStorage,
+    config: &CentralityConfig,
+) -> Result<Vec<(String, f64)>, GraphError> {
+    centrality(storage, CentralityMeasure::Degree, config)
+}
+
+/// Closeness of each node to the nodes it can reach in the configured direction.
+///
+/// Uses the Wasserman-Faust variant, `(r / (n - 1)) * (r / total distance)` where `r` is the number
+/// of reachable nodes, so scores stay comparable on graphs that are not connected.
+pub fn closeness_centrality(
+    storage: &HelixGraphStorage,
+    config: &CentralityConfig,
+) -> Result<Vec<(String, f64)>, GraphError> {
+    centrality(storage, CentralityMeasure::Closeness, config)
+}
+
+/// Number of shortest paths between pairs of other nodes passing through each node, using Brandes' algorithm.
+///
+/// With `EdgeDirection::Both` each unordered pair is counted once. If `samples` is set, only that many
+/// randomly chosen source nodes are used and the scores are scaled up to estimate the exact ones.
+pub fn betweenness_centrality(
+    storage: &HelixGraphStorage,
+    config: &CentralityConfig,
+) -> Result<Vec<(String, f64)>, GraphError> {
+    centrality(storage, CentralityMeasure::Betweenness, config)
+}
+
+/// Computes the given centrality measure for each node of a projection, in node order
+pub fn centrality_of(
+    graph: &GraphProjection,
+    measure: CentralityMeasure,
+    config: &CentralityConfig,
+) -> Result<Vec<f64>, GraphError> {
+    match measure {
+        CentralityMeasure::Degree => Ok((0..graph.node_count())
+            .into_par_iter()
+            .map(|node| match config.direction {
+                EdgeDirection::Out => graph.out_degree(node),
+                EdgeDirection::In => graph.in_degree(node),
+                EdgeDirection::Both => graph.out_degree(node) + graph.in_degree(node),
+            } as f64)
+            .collect()),
+        CentralityMeasure::Closeness => Ok(closeness(&graph.simple_adjacency(config.direction))),
+        CentralityMeasure::Betweenness => {
+            let adjacency = graph.simple_adjacency(config.direction);
+            let n = adjacency.len();
+            let sources: Vec<usize> = match config.samples {
+                Some(0) => {
+                    return Err(GraphError::AlgorithmError(
+                        "Betweenness needs at least one sampled node".to_string(),
+                    ))
+                }
+                Some(samples) if samples < n => {
+                    let mut rng = StdRng::seed_from_u64(config.seed);
+                    index::sample(&mut rng, n, samples).into_vec()
+                }
+                _ => (0..n).collect(),
+            };
+            let mut scale = n as f64 / sources.len().max(1) as f64;
+            if config.direction == EdgeDirection::Both {
+                scale /= 2.0;
+            }
+            Ok(betweenness(&adjacency, &sources)
+                .into_iter()
+                .map(|score| score * scale)
+                .collect())
+        }
+    }
+}
+
+/// Pairs scores with node ids, highest first, keeping the `top_k` highest if set
+pub fn ranked(
+    graph: &GraphProjection,
+    scores: Vec<f64>,
+    top_k: Option<usize>,
+) -> Vec<(String, f64)> {
+    let mut results = graph.with_ids(scores);
+    results.par_sort_by(|a, b| b.1.total_cmp(&a.1));
+    if let Some(k) = top_k {
+        results.truncate(k);
+    }
+    results
+}
+
+fn closeness(adjacency: &[Vec<usize>]) -> Vec<f64> {
+    let n = adjacency.len();
+    (0..n)
+        .into_par_iter()
+        .map(|source| {
+            let mut distance = vec![usize::MAX; n];
+            distance[source] = 0;
+            let mut queue = VecDeque::from([source]);
+            let (mut reached, mut total) = (0usize, 0usize);
+            while let Some(node) = queue.pop_front() {
+                for &neighbour in &adjacency[node] {
+                    if distance[neighbour] == usize::MAX {
+                        distance[neighbour] = distance[node] + 1;
+                        reached += 1;
+                        total += distance[neighbour];
+                        queue.push_back(neighbour);
+                    }
+                }
+            }
+            if total == 0 {
+                return 0.0;
+            }
+            let reached = reached as f64;
+            (reached / (n - 1) as f64) * (reached / total as f64)
+        })
+        .collect()
+}
+
+/// Brandes' algorithm from each source, run in parallel with a score vector per thread
+fn betweenness(adjacency: &[Vec<usize>], sources: &[usize]) -> Vec<f64> {
+    let n = adjacency.len();
+    sources
+        .par_iter()
+        .fold(
+            || (vec![0.0; n], BrandesState::new(n)),
+            |(mut scores, mut state), &source| {
+                state.accumulate(adjacency, source, &mut scores);
+                (scores, state)
+            },
+        )
+        .map(|(scores, _)| scores)
+        .reduce(
+            || vec![0.0; n],
+            |mut a, b| {
+                a.iter_mut().zip(b).for_each(|(x, y)| *x += y);
+                a
+            },
+        )
+}
+
+/// Buffers reused between sources handled by the same thread
+struct BrandesState {
+    order: Vec<usize>,
+    queue: VecDeque<usize>,
+    distance: Vec<usize>,
+    paths: Vec<f64>,
+    dependency: Vec<f64>,
+}
+
+impl BrandesState {
+    fn new(n: usize) -> Self {
+        BrandesState {
+            order: Vec::with_capacity(n),
+            queue: VecDeque::new(),
+            distance: vec![usize::MAX; n],
+            paths: vec![0.0; n],
+            dependency: vec![0.0; n],
+        }
+    }
+
+    fn accumulate(&mut self, adjacency: &[Vec<usize>], source: usize, scores: &mut [f64]) {
+        // BFS counting the shortest paths to every node
+        self.distance[source] = 0;
+        self.paths[source] = 1.0;
+        self.queue.push_back(source);
+        while let Some(node) = self.queue.pop_front() {
+            self.order.push(node);
+            for &neighbour in &adjacency[node] {
+                if self.distance[neighbour] == usize::MAX {
+                    self.distance[neighbour] = self.distance[node] + 1;
+                    self.queue.push_back(neighbour);
+                }
+                if self.distance[neighbour] == self.distance[node] + 1 {
+                    self.paths[neighbour] += self.paths[node];
+                }
+            }
+        }
+
+        // walk back from the furthest nodes, pulling dependencies from successors on shortest paths
+        for &node in self.order.iter().rev() {
+            for &neighbour in &adjacency[node] {
+                if self.distance[neighbour] == self.distance[node] + 1 {
+                    self.dependency[node] += self.paths[node] / self.paths[neighbour]
+                        * (1.0 + self.dependency[neighbour]);
+                }
+            }
+            if node != source {
+                scores[node] += self.dependency[node];
+            }
+        }
+
+        for &node in &self.order {
+            self.distance[node] = usize::MAX;
+            self.paths[node] = 0.0;
+            self.dependency[node] = 0.0;
+        }
+        self.order.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph_algorithms::setup_temp_db;
+    use crate::props;
+    use crate::storage_core::storage_methods::StorageMethods;
+    use protocol::Node;
+
+    /// An account followed by four others, one of which is followed by a fifth
+    fn setup_star(storage: &HelixGraphStorage) -> Vec<Node> {
+        let accounts: Vec<Node> = (0..6)
+            .map(|_| storage.create_node("account", props!()).unwrap())
+            .collect();
+        for (from, to) in [(1, 0), (2, 0), (3, 0), (4, 0), (5, 4)] {
+            storage
+                .create_edge("follows", &accounts[from].id, &accounts[to].id, props!())
+                .unwrap();
+        }
+        accounts
+    }
+
+    fn score_of(results: &[(String, f64)], id: &str) -> f64 {
+        results.iter().find(|(node_id, _)| node_id == id).unwrap().1
+    }
+
+    #[test]
+    fn test_degree_centrality() {
+        let (storage, _temp_dir) = setup_temp_db();
+        let accounts = setup_star(&storage);
+
+        let config = CentralityConfig {
+            direction: EdgeDirection::In,
+            top_k: Some(2),
+            ..CentralityConfig::default()
+        };
+        let results = degree_centrality(&storage, &config).unwrap();
+        assert_eq!(
+            results,
+            vec![(accounts[0].id.clone(), 4.0), (accounts[4].id.clone(), 1.0)]
+        );
+
+        let config = CentralityConfig {
+            direction: EdgeDirection::Both,
+            ..CentralityConfig::default()
+        };
+        let results = degree_centrality(&storage, &config).unwrap();
+        assert_eq!(results.len(), 6);
+        assert_eq!(score_of(&results, &accounts[4].id), 2.0);
+    }
+
+    #[test]
+    fn test_closeness_centrality() {
+        let (storage, _temp_dir) = setup_temp_db();
+        let accounts = setup_star(&storage);
+
+        let config = CentralityConfig {
+            direction: EdgeDirection::Both,
+            ..CentralityConfig::default()
+        };
+        let results = closeness_centrality(&storage, &config).unwrap();
+        assert_eq!(results[0].0, accounts[0].id);
+        // the center reaches four accounts at distance 1 and one at distance 2
+        assert!((results[0].1 - 5.0 / 6.0).abs() < 1e-9);
+
+        // following edges forward, account 5 reaches 4 and 0 but nothing else
+        let results = closeness_centrality(&storage, &CentralityConfig::default()).unwrap();
+        assert!((score_of(&results, &accounts[5].id) - (2.0 / 5.0) * (2.0 / 3.0)).abs() < 1e-9);
+        assert_eq!(score_of(&results, &accounts[0].id), 0.0);
+    }
+
+    #[test]
+    fn test_betweenness_centrality() {
+        let (storage, _temp_dir) = setup_temp_db();
+        let accounts = setup_star(&storage);
+
+        let config = CentralityConfig {
+            direction: EdgeDirection::Both,
+            ..CentralityConfig::default()
+        };
+        let results = betweenness_centrality(&storage, &config).unwrap();
+        // every pair of the five other accounts goes through the center except 4 and 5
+        assert_eq!(results[0], (accounts[0].id.clone(), 9.0));
+        // 5 reaches the other four accounts through 4
+        assert_eq!(score_of(&results, &accounts[4].id), 4.0);
+        assert_eq!(score_of(&results, &accounts[1].id), 0.0);
+
+        // directed paths through 4 only go from 5 to 0
+        let results = betweenness_centrality(&storage, &CentralityConfig::default()).unwrap();
+        assert_eq!(results[0], (accounts[4].id.clone(), 1.0));
+
+        let sampled = CentralityConfig {
+            direction: EdgeDirection::Both,
+            samples: Some(6),
+            ..CentralityConfig::default()
+        };
+        assert_eq!(
+            score_of(
+                &betweenness_centrality(&storage, &sampled).unwrap(),
+                &accounts[0].id
+            ),
+            9.0
+        );
+        let sampled = CentralityConfig {
+            samples: Some(3),
+            seed: 7,
+            ..sampled
+        };
+        assert_eq!(betweenness_centrality(&storage, &sampled).unwrap().len(), 6);
+        let sampled = CentralityConfig {
+            samples: Some(0),
+            ..sampled
+        };
+        assert!(betweenness_centrality(&storage, &sampled).is_err());
+    }
+}
@@ -0,0 +1,437 @@
+use std::collections::HashMap;
+
+use protocol::value::Value;
+use rayon::prelude::*;
+
+use crate::graph_algorithms::components::Components;
+use crate::graph_algorithms::projection::{GraphProjection, ProjectionFilter};
+use crate::storage_core::storage_core::HelixGraphStorage;
+use crate::types::GraphError;
+
+/// Settings for `label_propagation` and `louvain`
+#[derive(Debug, Clone)]
+pub struct CommunityConfig {
+    pub filter: ProjectionFilter,
+    /// Numeric edge property used as the edge weight, every edge weighs 1 if unset
+    pub weight_property: Option<String>,
+    /// Maximum number of label propagation rounds, or of local moving passes per Louvain level
+    pub max_iterations: usize,
+    /// Node property the community ids are written to, if set
+    pub write_property: Option<String>,
+}
+
+impl Default for CommunityConfig {
+    fn default() -> Self {
+        CommunityConfig {
+            filter: ProjectionFilter::default(),
+            weight_property: None,
+            max_iterations: 20,
+            write_property: None,
+        }
+    }
+}
+
+/// Community of every node in a graph.
+///
+/// Communities are numbered from 0 by decreasing size, so community 0 is always the largest.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Communities {
+    /// Each node id with the community it belongs to
+    pub assignments: Vec<(String, usize)>,
+    /// Number of nodes in each community
+    pub sizes: Vec<usize>,
+    /// Modularity of the partition, higher means more edges within communities than expected at random
+    pub modularity: f64,
+}
+
+/// Finds communities by repeatedly moving each node to the label most common among its neighbours.
+///
+/// Edge direction is ignored. Every round updates all nodes in parallel from the labels of the previous round.
+/// Each node also votes for its own label with the weight of its heaviest edge, otherwise
+/// neighbours could keep swapping labels and single labels would leak across bridges.
+pub fn label_propagation(
+    storage: &HelixGraphStorage,
+    config: &CommunityConfig,
+) -> Result<Communities, GraphError> {
+    let graph = load(storage, config)?;
+    let labels = propagate_labels(&graph, config.max_iterations);
+    finish(storage, config, &graph, labels)
+}
+
+/// Finds communities with the Louvain method, greedily moving nodes between communities while
+/// it increases modularity and then merging each community into a single node, until nothing moves.
+///
+/// Edge direction is ignored. Moving nodes is sequential as it depends on every earlier move,
+/// building the merged graphs and computing degrees and modularity runs in parallel.
+pub fn louvain(
+    storage: &HelixGraphStorage,
+    config: &CommunityConfig,
+) -> Result<Communities, GraphError> {
+    let graph = load(storage, config)?;
+    let labels = louvain_communities(
+        &WeightedGraph::from_projection(&graph),
+        config.max_iterations,
+    );
+    finish(storage, config, &graph, labels)
+}
+
+fn load(
+    storage: &HelixGraphStorage,
+    config: &CommunityConfig,
+) -> Result<GraphProjection, GraphError> {
+    GraphProjection::load_weighted(storage, &config.filter, config.weight_property.as_deref())
+}
+
+fn finish(
+    storage: &HelixGraphStorage,
+    config: &CommunityConfig,
+    graph: &GraphProjection,
+    labels: Vec<usize>,
+) -> Result<Communities, GraphError> {
+    let modularity = WeightedGraph::from_projection(graph).modularity(&labels);
+    let Components { assignments, sizes } = Components::from_labels(graph, labels);
+    if let Some(property) = &config.write_property {
+        storage.set_node_property(
+            property,
+            assignments
+                .iter()
+                .map(|(id, community)| (id.clone(), Value::U64(*community as u64))),
+        )?;
+    }
+    Ok(Communities {
+        assignments,
+        sizes,
+        modularity,
+    })
+}
+
+fn propagate_labels(graph: &GraphProjection, max_iterations: usize) -> Vec<usize> {
+    let n = graph.node_count();
+    let mut labels: Vec<usize> = (0..n).collect();
+
+    for _ in 0..max_iterations {
+        let next: Vec<usize> = (0..n)
+            .into_par_iter()
+            .map(|node| {
+                let mut votes: HashMap<usize, f64> = HashMap::new();
+                let mut heaviest = 0.0f64;
+                for (neighbour, weight) in graph.undirected_neighbours(node) {
+                    if neighbour != node {
+                        *votes.entry(labels[neighbour]).or_default() += weight;
+                        heaviest = heaviest.max(weight);
+                    }
+                }
+                if votes.is_empty() {
+                    return labels[node];
+                }
+                *votes.entry(labels[node]).or_default() += heaviest;
+                let best = votes.values().copied().fold(f64::NEG_INFINITY, f64::max);
+                // the smallest label wins ties so results don't depend on hash order
+                votes
+                    .into_iter()
+                    .filter(|&(_, weight)| weight == best)
+                    .map(|(label, _)| label)
+                    .min()
+                    .unwrap()
+            })
+            .collect();
+
+        if next == labels {
+            break;
+        }
+        labels = next;
+    }
+    labels
+}
+
+/// Runs Louvain levels until no node changes community.
+/// Returns the community of every node of the original graph.
+fn louvain_communities(graph: &WeightedGraph, max_passes: usize) -> Vec<usize> {
+    // node of the current level each original node has been merged into
+    let mut assignment: Vec<usize> = (0..graph.adjacency.len()).collect();
+    let mut moves = local_moving(graph, max_passes);
+    while let Some((communities, count)) = moves {
+        for node in assignment.iter_mut() {
+            *node = communities[*node];
+        }
+        let merged = graph_for_level(graph, &assignment, count);
+        moves = local_moving(&merged, max_passes);
+    }
+    assignment
+}
+
+/// Merges every node of the original graph into the node of its community
+fn graph_for_level(graph: &WeightedGraph, assignment: &[usize], count: usize) -> WeightedGraph {
+    let mut members = vec![Vec::new(); count];
+    for (node, &community) in assignment.iter().enumerate() {
+        members[community].push(node);
+    }
+    let adjacency = members
+        .par_iter()
+        .map(|nodes| {
+            let mut row: HashMap<usize, f64> = HashMap::new();
+            for &node in nodes {
+                for &(neighbour, weight) in &graph.adjacency[node] {
+                    *row.entry(assignment[neighbour]).or_default() += weight;
+                }
+            }
+            let mut row: Vec<(usize, f64)> = row.into_iter().collect();
+            row.sort_unstable_by_key(|&(neighbour, _)| neighbour);
+            row
+        })
+        .collect();
+    WeightedGraph { adjacency }
+}
+
+/// Moves single nodes to the neighbouring community with the largest modularity gain until no move improves it.
+/// Returns the communities numbered from 0 and their count, or `None` if no node moved.
+fn local_moving(graph: &WeightedGraph, max_passes: usize) -> Option<(Vec<usize>, usize)> {
+    let n = graph.adjacency.len();
+    let degrees = graph.degrees();
+    let total: f64 = degrees.par_iter().sum();
+    if total == 0.0 {
+        return None;
+    }
+    let mut community: Vec<usize> = (0..n).collect();
+    let mut community_degree = degrees.clone();
+    let mut moved = false;
+
+    for _ in 0..max_passes {
+        let mut improved = false;
+        for node in 0..n {
+            let current = community[node];
+            let mut links: Vec<(usize, f64)> = Vec::new();
+            for &(neighbour, weight) in &graph.adjacency[node] {
+                if neighbour == node {
+                    continue;
+                }
+                match links.iter_mut().find(|(c, _)| *c == community[neighbour]) {
+                    Some((_, link)) => *link += weight,
+                    None => links.push((community[neighbour], weight)),
+                }
+            }
+            links.sort_unstable_by_key(|&(c, _)| c);
+
+            community_degree[current] -= degrees[node];
+            // modularity gain of adding the node to a community, up to a constant factor
+            let gain = |c: usize, link: f64| link - community_degree[c] * degrees[node] / total;
+            let mut best = current;
+            let mut best_gain = gain(
+                current,
+                links
+                    .iter()
+                    .find(|(c, _)| *c == current)
+                    .map_or(0.0, |&(_, link)| link),
+            );
+            for &(c, link) in &links {
+                let candidate = gain(c, link);
+                if candidate > best_gain + 1e-12 {
+                    best = c;
+                    best_gain = candidate;
+                }
+            }
+            community_degree[best] += degrees[node];
+            if best != current {
+                community[node] = best;
+                improved = true;
+                moved = true;
+            }
+        }
+        if !improved {
+            break;
+        }
+    }
+    if !moved {
+        return None;
+    }
+
+    let mut numbering = HashMap::new();
+    let communities: Vec<usize> = community
+        .iter()
+        .map(|c| {
+            let next = numbering.len();
+            *numbering.entry(*c).or_insert(next)
+        })
+        .collect();
+    Some((communities, numbering.len()))
+}
+
+/// Undirected weighted graph where each node lists every neighbour once with the total weight
+/// of the edges between them. Self loops are counted twice, as in an adjacency matrix,
+/// so the degree of a node is the sum of its row.
+struct WeightedGraph {
+    adjacency: Vec<Vec<(usize, f64)>>,
+}
+
+impl WeightedGraph {
+    fn from_projection(graph: &GraphProjection) -> Self {
+        let adjacency = (0..graph.node_count())
+            .into_par_iter()
+            .map(|node| {
+                let mut row: HashMap<usize, f64> = HashMap::new();
+                for (neighbour, weight) in graph.undirected_neighbours(node) {
+                    *row.entry(neighbour).or_default() += weight;
+                }
+                let mut row: Vec<(usize, f64)> = row.into_iter().collect();
+                row.sort_unstable_by_key(|&(neighbour, _)| neighbour);
+                row
+            })
+            .collect();
+        WeightedGraph { adjacency }
+    }
+
+    fn degrees(&self) -> Vec<f64> {
+        self.adjacency
+            .par_iter()
+            .map(|row| row.iter().map(|&(_, weight)| weight).sum())
+            .collect()
+    }
+
+    fn modularity(&self, communities: &[usize]) -> f64 {
+        let degrees = self.degrees();
+        let total: f64 = degrees.par_iter().sum();
+        if total == 0.0 {
+            return 0.0;
+        }
+        let internal: f64 = (0..self.adjacency.len())
+            .into_par_iter()
+            .map(|node| {
+                self.adjacency[node]
+                    .iter()
+                    .filter(|&&(neighbour, _)| communities[neighbour] == communities[node])
+                    .map(|&(_, weight)| weight)
+                    .sum::<f64>()
+            })
+            .sum();
+        let count = communities.iter().max().map_or(0, |&c| c + 1);
+        let mut community_degree = vec![0.0; count];
+        for (node, &community) in communities.iter().enumerate() {
+            community_degree[community] += degrees[node];
+        }
+        let expected: f64 = community_degree.iter().map(|d| d * d).sum::<f64>() / (total * total);
+        internal / total - expected
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph_algorithms::setup_temp_db;
+    use crate::props;
+    use crate::storage_core::storage_methods::StorageMethods;
+    use protocol::Node;
+
+    /// Two groups of four users who all follow each other, joined by a single follow
+    fn setup_two_groups(storage: &HelixGraphStorage) -> Vec<Node> {
+        let users: Vec<Node> = (0..8)
+            .map(|_| storage.create_node("user", props!()).unwrap())
+            .collect();
+        for group in [0, 4] {
+            for from in group..group + 4 {
+                for to in from + 1..group + 4 {
+                    storage
+                        .create_edge("follows", &users[from].id, &users[to].id, props!())
+                        .unwrap();
+                }
+            }
+        }
+        storage
+            .create_edge("follows", &users[3].id, &users[4].id, props!())
+            .unwrap();
+        users
+    }
+
+    fn community_of(communities: &Communities, id: &str) -> usize {
+        communities
+            .assignments
+            .iter()
+            .find(|(node_id, _)| node_id == id)
+            .unwrap()
+            .1
+    }
+
+    fn assert_two_groups(communities: &Communities, users: &[Node]) {
+        assert_eq!(communities.sizes, vec![4, 4]);
+        for group in [0, 4] {
+            let community = community_of(communities, &users[group].id);
+            for user in &users[group..group + 4] {
+                assert_eq!(community_of(communities, &user.id), community);
+            }
+        }
+        assert!(communities.modularity > 0.4);
+    }
+
+    #[test]
+    fn test_label_propagation() {
+        let (storage, _temp_dir) = setup_temp_db();
+        let users = setup_two_groups(&storage);
+
+        let communities = label_propagation(&storage, &CommunityConfig::default()).unwrap();
+        assert_two_groups(&communities, &users);
+    }
+
+    #[test]
+    fn test_louvain_writes_communities() {
+        let (storage, _temp_dir) = setup_temp_db();
+        let users = setup_two_groups(&storage);
+
+        let config = CommunityConfig {
+            write_property: Some("community".to_string()),
+            ..CommunityConfig::default()
+        };
+        let communities = louvain(&storage, &config).unwrap();
+        assert_two_groups(&communities, &users);
+        for user in &users {
+            assert_eq!(
+                storage
+                    .get_node(&user.id)
+                    .unwrap()
+                    .properties
+                    .get("community"),
+                Some(&Value::U64(community_of(&communities, &user.id) as u64))
+            );
+        }
+    }
+
+    #[test]
+    fn test_louvain_uses_edge_weights() {
+        let (storage, _temp_dir) = setup_temp_db();
+        // a ring of six users where every other friendship is much stronger
+        let users: Vec<Node> = (0..6)
+            .map(|_| storage.create_node("user", props!()).unwrap())
+            .collect();
+        for i in 0..6 {
+            let strength = if i % 2 == 0 { 10 } else { 1 };
+            storage
+                .create_edge(
+                    "friends",
+                    &users[i].id,
+                    &users[(i + 1) % 6].id,
+                    props! { "strength" => strength },
+                )
+                .unwrap();
+        }
+
+        let config = CommunityConfig {
+            weight_property: Some("strength".to_string()),
+            ..CommunityConfig::default()
+        };
+        let communities = louvain(&storage, &config).unwrap();
+        assert_eq!(communities.sizes, vec![2, 2, 2]);
+        for i in [0, 2, 4] {
+            assert_eq!(
+                community_of(&communities, &users[i].id),
+                community_of(&communities, &users[i + 1].id)
+            );
+        }
+
+        let missing = CommunityConfig {
+            weight_property: Some("missing".to_string()),
+            ..CommunityConfig::default()
+        };
+        assert!(matches!(
+            louvain(&storage, &missing),
+            Err(GraphError::AlgorithmError(_))
+        ));
+    }
+}
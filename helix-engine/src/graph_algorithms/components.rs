@@ -0,0 +1,263 @@
+use crate::graph_algorithms::projection::{GraphProjection, ProjectionFilter};
+use crate::storage_core::storage_core::HelixGraphStorage;
+use crate::types::GraphError;
+
+/// Component of every node in a graph.
+///
+/// Components are numbered from 0 by decreasing size, so component 0 is always the largest.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Components {
+    /// Each node id with the component it belongs to
+    pub assignments: Vec<(String, usize)>,
+    /// Number of nodes in each component
+    pub sizes: Vec<usize>,
+}
+
+impl Components {
+    /// Number of components
+    #[inline]
+    pub fn count(&self) -> usize {
+        self.sizes.len()
+    }
+
+    /// Groups nodes by component label, relabelling components by decreasing size.
+    /// Ties keep the order in which components were first seen so results are stable.
+    pub(crate) fn from_labels(graph: &GraphProjection, labels: Vec<usize>) -> Components {
+        let mut first_seen = Vec::new();
+        let mut sizes_by_label = vec![0; labels.len()];
+        for &label in &labels {
+            if sizes_by_label[label] == 0 {
+                first_seen.push(label);
+            }
+            sizes_by_label[label] += 1;
+        }
+        first_seen.sort_by_key(|&label| std::cmp::Reverse(sizes_by_label[label]));
+
+        let mut component_of_label = vec![0; labels.len()];
+        for (component, &label) in first_seen.iter().enumerate() {
+            component_of_label[label] = component;
+        }
+        Components {
+            assignments: graph.with_ids(labels.iter().map(|&l| component_of_label[l]).collect()),
+            sizes: first_seen
+                .iter()
+                .map(|&label| sizes_by_label[label])
+                .collect(),
+        }
+    }
+}
+
+/// Finds the weakly connected components of the filtered graph, ignoring edge direction
+pub fn weakly_connected_components(
+    storage: &HelixGraphStorage,
+    filter: &ProjectionFilter,
+) -> Result<Components, GraphError> {
+    Ok(weakly_connected_components_of(&GraphProjection::load(
+        storage, filter,
+    )?))
+}
+
+/// Finds the strongly connected components of the filtered graph,
+/// where every node of a component can reach every other node along edge directions.
+/// Nodes in components with more than one node are on a cycle, but a node in a component
+/// of its own can still be on one through an edge to itself.
+pub fn strongly_connected_components(
+    storage: &HelixGraphStorage,
+    filter: &ProjectionFilter,
+) -> Result<Components, GraphError> {
+    Ok(strongly_connected_components_of(&GraphProjection::load(
+        storage, filter,
+    )?))
+}
+
+/// Disjoint sets with path halving and union by size
+pub(crate) struct UnionFind {
+    parent: Vec<usize>,
+    size: Vec<usize>,
+}
+
+impl UnionFind {
+    pub(crate) fn new(n: usize) -> Self {
+        UnionFind {
+            parent: (0..n).collect(),
+            size: vec![1; n],
+        }
+    }
+
+    /// Representative of the set containing `node`
+    pub(crate) fn find(&mut self, mut node: usize) -> usize {
+        while self.parent[node] != node {
+            self.parent[node] = self.parent[self.parent[node]];
+            node = self.parent[node];
+        }
+        node
+    }
+
+    /// Merges the sets of `a` and `b`, returning false if they were already the same set
+    pub(crate) fn union(&mut self, a: usize, b: usize) -> bool {
+        let (a, b) = (self.find(a), self.find(b));
+        if a == b {
+            return false;
+        }
+        let (small, large) = if self.size[a] < self.size[b] {
+            (a, b)
+        } else {
+            (b, a)
+        };
+        self.parent[small] = large;
+        self.size[large] += self.size[small];
+        true
+    }
+}
+
+/// Merges the endpoints of every edge with union-find
+pub fn weakly_connected_components_of(graph: &GraphProjection) -> Components {
+    let n = graph.node_count();
+    let mut sets = UnionFind::new(n);
+    for node in 0..n {
+        for &neighbour in graph.out_neighbours(node) {
+            sets.union(node, neighbour);
+        }
+    }
+
+    let labels = (0..n).map(|node| sets.find(node)).collect();
+    Components::from_labels(graph, labels)
+}
+
+/// Tarjan's algorithm with an explicit call stack so deep graphs can't overflow the thread stack
+pub fn strongly_connected_components_of(graph: &GraphProjection) -> Components {
+    const UNVISITED: usize = usize::MAX;
+    let n = graph.node_count();
+    let mut index = vec![UNVISITED; n];
+    let mut low_link = vec![0; n];
+    let mut on_stack = vec![false; n];
+    let mut stack = Vec::new();
+    let mut labels = vec![0; n];
+    let mut next_index = 0;
+    let mut next_label = 0;
+
+    for root in 0..n {
+        if index[root] != UNVISITED {
+            continue;
+        }
+        // each frame is a node and the position of the next outgoing edge to visit
+        let mut call_stack = vec![(root, 0)];
+        index[root] = next_index;
+        low_link[root] = next_index;
+        next_index += 1;
+        stack.push(root);
+        on_stack[root] = true;
+
+        while let Some(&(node, edge)) = call_stack.last() {
+            let neighbours = graph.out_neighbours(node);
+            if edge < neighbours.len() {
+                call_stack.last_mut().unwrap().1 += 1;
+                let neighbour = neighbours[edge];
+                if index[neighbour] == UNVISITED {
+                    index[neighbour] = next_index;
+                    low_link[neighbour] = next_index;
+                    next_index += 1;
+                    stack.push(neighbour);
+                    on_stack[neighbour] = true;
+                    call_stack.push((neighbour, 0));
+                } else if on_stack[neighbour] {
+                    low_link[node] = low_link[node].min(index[neighbour]);
+                }
+                continue;
+            }
+
+            call_stack.pop();
+            if let Some(&(caller, _)) = call_stack.last() {
+                low_link[caller] = low_link[caller].min(low_link[node]);
+            }
+            if low_link[node] == index[node] {
+                while let Some(member) = stack.pop() {
+                    on_stack[member] = false;
+                    labels[member] = next_label;
+                    if member == node {
+                        break;
+                    }
+                }
+                next_label += 1;
+            }
+        }
+    }
+
+    Components::from_labels(graph, labels)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph_algorithms::setup_temp_db;
+    use crate::props;
+    use crate::storage_core::storage_methods::StorageMethods;
+    use protocol::Node;
+
+    /// Packages 0 -> 1 -> 2 -> 0 form a dependency cycle that 3 depends on,
+    /// 4 -> 5 are a separate pair and 6 has no dependencies
+    fn setup_dependencies(storage: &HelixGraphStorage) -> Vec<Node> {
+        let packages: Vec<Node> = (0..7)
+            .map(|_| storage.create_node("package", props!()).unwrap())
+            .collect();
+        for (from, to) in [(0, 1), (1, 2), (2, 0), (3, 2), (4, 5)] {
+            storage
+                .create_edge("depends_on", &packages[from].id, &packages[to].id, props!())
+                .unwrap();
+        }
+        storage
+            .create_edge("mentions", &packages[5].id, &packages[4].id, props!())
+            .unwrap();
+        packages
+    }
+
+    fn component_of(components: &Components, id: &str) -> usize {
+        components
+            .assignments
+            .iter()
+            .find(|(node_id, _)| node_id == id)
+            .unwrap()
+            .1
+    }
+
+    #[test]
+    fn test_weakly_connected_components() {
+        let (storage, _temp_dir) = setup_temp_db();
+        let packages = setup_dependencies(&storage);
+
+        let components =
+            weakly_connected_components(&storage, &ProjectionFilter::default()).unwrap();
+        assert_eq!(components.sizes, vec![4, 2, 1]);
+        assert_eq!(components.assignments.len(), 7);
+        for package in &packages[..4] {
+            assert_eq!(component_of(&components, &package.id), 0);
+        }
+        assert_eq!(component_of(&components, &packages[4].id), 1);
+        assert_eq!(component_of(&components, &packages[5].id), 1);
+        assert_eq!(component_of(&components, &packages[6].id), 2);
+    }
+
+    #[test]
+    fn test_strongly_connected_components() {
+        let (storage, _temp_dir) = setup_temp_db();
+        let packages = setup_dependencies(&storage);
+
+        let components =
+            strongly_connected_components(&storage, &ProjectionFilter::default()).unwrap();
+        assert_eq!(components.count(), 4);
+        assert_eq!(components.sizes[..2], [3, 2]);
+        let cycle = component_of(&components, &packages[0].id);
+        assert_eq!(cycle, 0);
+        assert_eq!(component_of(&components, &packages[1].id), cycle);
+        assert_eq!(component_of(&components, &packages[2].id), cycle);
+        assert_ne!(component_of(&components, &packages[3].id), cycle);
+
+        // only following dependencies breaks the cycle between 4 and 5
+        let filter = ProjectionFilter {
+            node_labels: vec![],
+            edge_labels: vec!["depends_on".to_string()],
+        };
+        let components = strongly_connected_components(&storage, &filter).unwrap();
+        assert_eq!(components.sizes, vec![3, 1, 1, 1, 1]);
+    }
+}
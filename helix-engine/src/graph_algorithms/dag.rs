@@ -0,0 +1,244 @@
+use std::collections::{HashSet, VecDeque};
+
+use crate::graph_algorithms::components::strongly_connected_components_of;
+use crate::graph_algorithms::projection::{GraphProjection, ProjectionFilter};
+use crate::storage_core::storage_core::HelixGraphStorage;
+use crate::storage_core::storage_methods::EdgeDirection;
+use crate::types::GraphError;
+
+/// Finds the cycles of the filtered graph, one for each group of nodes that can all reach each other.
+///
+/// Each cycle lists node ids in edge order without repeating the first node, so a self loop is
+/// a single node. An empty result means the graph is acyclic.
+pub fn find_cycles(
+    storage: &HelixGraphStorage,
+    filter: &ProjectionFilter,
+) -> Result<Vec<Vec<String>>, GraphError> {
+    let graph = GraphProjection::load(storage, filter)?;
+    Ok(cycles_of(&graph)
+        .into_iter()
+        .map(|cycle| {
+            cycle
+                .into_iter()
+                .map(|node| graph.node_id(node).to_string())
+                .collect()
+        })
+        .collect())
+}
+
+/// Orders the nodes of the filtered graph so every edge goes from an earlier node to a later one,
+/// e.g. so dependencies run before what depends on them when edges point from dependency to dependent.
+///
+/// Nodes with no order between them keep their storage order.
+/// Fails with the offending cycle if the graph has one.
+pub fn topological_sort(
+    storage: &HelixGraphStorage,
+    filter: &ProjectionFilter,
+) -> Result<Vec<String>, GraphError> {
+    let graph = GraphProjection::load(storage, filter)?;
+    let n = graph.node_count();
+    let mut in_degree: Vec<usize> = (0..n).map(|node| graph.in_degree(node)).collect();
+    let mut ready: VecDeque<usize> = (0..n).filter(|&node| in_degree[node] == 0).collect();
+    let mut order = Vec::with_capacity(n);
+
+    while let Some(node) = ready.pop_front() {
+        order.push(graph.node_id(node).to_string());
+        for &next in graph.out_neighbours(node) {
+            in_degree[next] -= 1;
+            if in_degree[next] == 0 {
+                ready.push_back(next);
+            }
+        }
+    }
+
+    if order.len() < n {
+        let cycle = &cycles_of(&graph)[0];
+        let mut ids: Vec<&str> = cycle.iter().map(|&node| graph.node_id(node)).collect();
+        ids.push(ids[0]);
+        return Err(GraphError::AlgorithmError(format!(
+            "Graph has a cycle: {}",
+            ids.join(" -> ")
+        )));
+    }
+    Ok(order)
+}
+
+/// Whether adding an edge from `from_id` to `to_id` would close a cycle among edges with a label
+/// in `edge_labels`, which is the case when `from_id` can already be reached from `to_id`.
+///
+/// Meant to be called before `create_edge` to keep a graph acyclic.
+pub fn would_create_cycle(
+    storage: &HelixGraphStorage,
+    from_id: &str,
+    to_id: &str,
+    edge_labels: &[String],
+) -> Result<bool, GraphError> {
+    if from_id == to_id {
+        return Ok(true);
+    }
+    let mut visited = HashSet::from([to_id.to_string()]);
+    let mut queue = VecDeque::from([to_id.to_string()]);
+    while let Some(node_id) = queue.pop_front() {
+        let mut found = false;
+        storage.for_each_adjacent_edge(&node_id, EdgeDirection::Out, edge_labels, |next, _| {
+            if next == from_id {
+                found = true;
+            } else if !visited.contains(next) {
+                visited.insert(next.to_string());
+                queue.push_back(next.to_string());
+            }
+            Ok(())
+        })?;
+        if found {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+/// One shortest cycle through the first node of every strongly connected component
+/// with more than one node, plus every self loop outside those components
+fn cycles_of(graph: &GraphProjection) -> Vec<Vec<usize>> {
+    let components = strongly_connected_components_of(graph);
+    let component: Vec<usize> = components.assignments.iter().map(|(_, c)| *c).collect();
+    let mut handled = vec![false; components.count()];
+    let mut cycles = Vec::new();
+
+    for start in 0..graph.node_count() {
+        let c = component[start];
+        if handled[c] {
+            continue;
+        }
+        if components.sizes[c] == 1 {
+            if graph.out_neighbours(start).contains(&start) {
+                cycles.push(vec![start]);
+            }
+            continue;
+        }
+        handled[c] = true;
+
+        // breadth first search inside the component until an edge leads back to the start
+        let mut parent = vec![usize::MAX; graph.node_count()];
+        parent[start] = start;
+        let mut queue = VecDeque::from([start]);
+        'search: while let Some(node) = queue.pop_front() {
+            for &next in graph.out_neighbours(node) {
+                if next == start && node != start {
+                    let mut cycle = vec![node];
+                    while *cycle.last().unwrap() != start {
+                        cycle.push(parent[*cycle.last().unwrap()]);
+                    }
+                    cycle.reverse();
+                    cycles.push(cycle);
+                    break 'search;
+                }
+                if component[next] == c && parent[next] == usize::MAX {
+                    parent[next] = node;
+                    queue.push_back(next);
+                }
+            }
+        }
+    }
+    cycles
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph_algorithms::setup_temp_db;
+    use crate::props;
+    use crate::storage_core::storage_methods::StorageMethods;
+    use protocol::Node;
+
+    /// Build steps where 0 runs before 1 and 2, which both run before 3
+    fn setup_steps(storage: &HelixGraphStorage) -> Vec<Node> {
+        let steps: Vec<Node> = (0..4)
+            .map(|_| storage.create_node("step", props!()).unwrap())
+            .collect();
+        for (from, to) in [(0, 1), (0, 2), (1, 3), (2, 3)] {
+            storage
+                .create_edge("before", &steps[from].id, &steps[to].id, props!())
+                .unwrap();
+        }
+        steps
+    }
+
+    fn before() -> ProjectionFilter {
+        ProjectionFilter {
+            node_labels: vec![],
+            edge_labels: vec!["before".to_string()],
+        }
+    }
+
+    #[test]
+    fn test_topological_sort() {
+        let (storage, _temp_dir) = setup_temp_db();
+        let steps = setup_steps(&storage);
+
+        let order = topological_sort(&storage, &before()).unwrap();
+        let position = |node: &Node| order.iter().position(|id| id == &node.id).unwrap();
+        assert_eq!(order.len(), 4);
+        assert_eq!(position(&steps[0]), 0);
+        assert_eq!(position(&steps[3]), 3);
+
+        storage
+            .create_edge("before", &steps[3].id, &steps[1].id, props!())
+            .unwrap();
+        let err = topological_sort(&storage, &before()).unwrap_err();
+        let message = err.to_string();
+        assert!(message.contains(&steps[1].id) && message.contains(&steps[3].id));
+        assert!(!message.contains(&steps[0].id));
+    }
+
+    #[test]
+    fn test_find_cycles() {
+        let (storage, _temp_dir) = setup_temp_db();
+        let steps = setup_steps(&storage);
+        assert!(find_cycles(&storage, &before()).unwrap().is_empty());
+
+        storage
+            .create_edge("before", &steps[3].id, &steps[0].id, props!())
+            .unwrap();
+        storage
+            .create_edge("retries", &steps[2].id, &steps[2].id, props!())
+            .unwrap();
+
+        let cycles = find_cycles(&storage, &before()).unwrap();
+        assert_eq!(cycles.len(), 1);
+        let cycle = &cycles[0];
+        assert_eq!(cycle.len(), 3);
+        // consecutive nodes of the cycle are linked, including the last back to the first
+        for (i, node_id) in cycle.iter().enumerate() {
+            let next = &cycle[(i + 1) % cycle.len()];
+            let targets = storage.get_out_nodes(node_id, "before").unwrap();
+            assert!(targets.iter().any(|n| &n.id == next));
+        }
+
+        let cycles = find_cycles(&storage, &ProjectionFilter::default()).unwrap();
+        assert_eq!(cycles.len(), 1);
+        let filter = ProjectionFilter {
+            node_labels: vec![],
+            edge_labels: vec!["retries".to_string()],
+        };
+        assert_eq!(
+            find_cycles(&storage, &filter).unwrap(),
+            vec![vec![steps[2].id.clone()]]
+        );
+    }
+
+    #[test]
+    fn test_would_create_cycle() {
+        let (storage, _temp_dir) = setup_temp_db();
+        let steps = setup_steps(&storage);
+        let labels = ["before".to_string()];
+
+        assert!(would_create_cycle(&storage, &steps[3].id, &steps[0].id, &labels).unwrap());
+        assert!(would_create_cycle(&storage, &steps[2].id, &steps[2].id, &labels).unwrap());
+        assert!(!would_create_cycle(&storage, &steps[0].id, &steps[3].id, &labels).unwrap());
+        assert!(!would_create_cycle(&storage, &steps[1].id, &steps[2].id, &labels).unwrap());
+        assert!(
+            !would_create_cycle(&storage, &steps[3].id, &steps[0].id, &["other".to_string()])
+                .unwrap()
+        );
+    }
+}
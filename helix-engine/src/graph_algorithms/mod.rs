@@ -0,0 +1,25 @@
+pub mod centrality;
+pub mod community;
+pub mod components;
+pub mod dag;
+pub mod max_flow;
+pub mod pagerank;
+pub mod pattern;
+pub mod projection;
+pub mod random_walk;
+pub mod similarity;
+pub mod spanning_tree;
+pub mod triangles;
+
+#[cfg(test)]
+use crate::storage_core::storage_core::HelixGraphStorage;
+#[cfg(test)]
+use tempfile::TempDir;
+
+/// Opens storage in a temporary directory, which is removed when the `TempDir` is dropped
+#[cfg(test)]
+pub(crate) fn setup_temp_db() -> (HelixGraphStorage, TempDir) {
+    let temp_dir = TempDir::new().unwrap();
+    let storage = HelixGraphStorage::new(temp_dir.path().to_str().unwrap()).unwrap();
+    (storage, temp_dir)
+}
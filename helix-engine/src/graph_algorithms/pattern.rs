@@ -0,0 +1,550 @@
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+
+use protocol::{value::Value, Edge, Node};
+
+use crate::storage_core::record::ValueRef;
+use crate::storage_core::storage_core::HelixGraphStorage;
+use crate::storage_core::storage_methods::{EdgeDirection, StorageMethods};
+use crate::types::GraphError;
+
+/// Comparison applied by a `PropertyPredicate`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompareOp {
+    Eq,
+    Ne,
+    Lt,
+    Lte,
+    Gt,
+    Gte,
+}
+
+impl CompareOp {
+    #[inline]
+    fn holds(self, ordering: Ordering) -> bool {
+        match self {
+            CompareOp::Eq => ordering == Ordering::Equal,
+            CompareOp::Ne => ordering != Ordering::Equal,
+            CompareOp::Lt => ordering == Ordering::Less,
+            CompareOp::Lte => ordering != Ordering::Greater,
+            CompareOp::Gt => ordering == Ordering::Greater,
+            CompareOp::Gte => ordering != Ordering::Less,
+        }
+    }
+}
+
+/// Condition on a property of a matched node or edge, compared with the total ordering of `Value`.
+/// Fails if the property is missing.
+#[derive(Debug, Clone)]
+pub struct PropertyPredicate {
+    pub property: String,
+    pub op: CompareOp,
+    pub value: Value,
+}
+
+impl PropertyPredicate {
+    pub fn new(property: &str, op: CompareOp, value: impl Into<Value>) -> Self {
+        PropertyPredicate {
+            property: property.to_string(),
+            op,
+            value: value.into(),
+        }
+    }
+
+    fn holds(&self, value: Option<ValueRef<'_>>) -> bool {
+        match value {
+            Some(value) => self.op.holds(value.to_value().cmp(&self.value)),
+            None => false,
+        }
+    }
+}
+
+/// Node of a pattern, bound to data nodes with the given label (any label if `None`)
+/// for which every predicate holds
+#[derive(Debug, Clone)]
+pub struct PatternNode {
+    pub variable: String,
+    pub label: Option<String>,
+    pub predicates: Vec<PropertyPredicate>,
+}
+
+/// Edge of a pattern from the node bound to `from` to the node bound to `to`.
+/// The edge itself is only returned if it has a variable.
+#[derive(Debug, Clone)]
+pub struct PatternEdge {
+    pub variable: Option<String>,
+    pub from: String,
+    pub to: String,
+    pub label: Option<String>,
+    pub predicates: Vec<PropertyPredicate>,
+}
+
+/// Small graph of labelled nodes and edges to look for in the data graph
+#[derive(Debug, Clone, Default)]
+pub struct Pattern {
+    pub nodes: Vec<PatternNode>,
+    pub edges: Vec<PatternEdge>,
+}
+
+impl Pattern {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a node bound to `variable`
+    pub fn node(
+        mut self,
+        variable: &str,
+        label: Option<&str>,
+        predicates: Vec<PropertyPredicate>,
+    ) -> Self {
+        self.nodes.push(PatternNode {
+            variable: variable.to_string(),
+            label: label.map(str::to_string),
+            predicates,
+        });
+        self
+    }
+
+    /// Adds an edge between two nodes of the pattern, bound to `variable` if given
+    pub fn edge(
+        mut self,
+        variable: Option<&str>,
+        from: &str,
+        to: &str,
+        label: Option<&str>,
+        predicates: Vec<PropertyPredicate>,
+    ) -> Self {
+        self.edges.push(PatternEdge {
+            variable: variable.map(str::to_string),
+            from: from.to_string(),
+            to: to.to_string(),
+            label: label.map(str::to_string),
+            predicates,
+        });
+        self
+    }
+}
+
+/// One binding of a pattern in the data graph, keyed by variable
+#[derive(Debug, Clone, Default)]
+pub struct PatternMatch {
+    pub nodes: HashMap<String, Node>,
+    pub edges: HashMap<String, Edge>,
+}
+
+/// Finds every binding of `pattern` in the data graph, up to `limit` if given.
+///
+/// Distinct pattern nodes bind distinct data nodes and distinct pattern edges bind distinct data edges,
+/// so a ring of three only matches actual triangles. Each ring is found once per rotation.
+///
+/// Matching backtracks through the pattern one node or edge at a time: unconnected nodes are found
+/// with a scan over nodes, and every other node by following an edge from a node already bound,
+/// reading the adjacency indices. Pattern edges between two bound nodes are checked as soon as possible
+/// to cut off partial bindings early.
+pub fn match_pattern(
+    storage: &HelixGraphStorage,
+    pattern: &Pattern,
+    limit: Option<usize>,
+) -> Result<Vec<PatternMatch>, GraphError> {
+    let edge_ends = validate(pattern)?;
+    let plan = plan(pattern, &edge_ends);
+    let mut search = Search {
+        storage,
+        pattern,
+        edge_ends: &edge_ends,
+        plan: &plan,
+        node_bindings: vec![None; pattern.nodes.len()],
+        edge_bindings: vec![None; pattern.edges.len()],
+        found: Vec::new(),
+        limit: limit.unwrap_or(usize::MAX),
+    };
+    if search.limit > 0 {
+        search.extend(0)?;
+    }
+
+    // data shared between rows is only read once
+    let mut nodes: HashMap<String, Node> = HashMap::new();
+    let mut edges: HashMap<String, Edge> = HashMap::new();
+    let mut rows = Vec::with_capacity(search.found.len());
+    for (node_ids, edge_ids) in search.found {
+        let mut row = PatternMatch::default();
+        for (pattern_node, id) in pattern.nodes.iter().zip(node_ids) {
+            if !nodes.contains_key(&id) {
+                nodes.insert(id.clone(), storage.get_node(&id)?);
+            }
+            row.nodes
+                .insert(pattern_node.variable.clone(), nodes[&id].clone());
+        }
+        for (pattern_edge, id) in pattern.edges.iter().zip(edge_ids) {
+            let variable = match &pattern_edge.variable {
+                Some(variable) => variable,
+                None => continue,
+            };
+            if !edges.contains_key(&id) {
+                edges.insert(id.clone(), storage.get_edge(&id)?);
+            }
+            row.edges.insert(variable.clone(), edges[&id].clone());
+        }
+        rows.push(row);
+    }
+    Ok(rows)
+}
+
+/// Checks variables are unique and every edge joins declared nodes,
+/// returning the positions of the end nodes of each edge
+fn validate(pattern: &Pattern) -> Result<Vec<(usize, usize)>, GraphError> {
+    if pattern.nodes.is_empty() {
+        return Err(GraphError::AlgorithmError(
+            "Pattern has no nodes".to_string(),
+        ));
+    }
+    let mut variables = HashSet::new();
+    let edge_variables = pattern.edges.iter().filter_map(|e| e.variable.as_ref());
+    for variable in pattern
+        .nodes
+        .iter()
+        .map(|n| &n.variable)
+        .chain(edge_variables)
+    {
+        if !variables.insert(variable) {
+            return Err(GraphError::AlgorithmError(format!(
+                "Pattern variable {} is used more than once",
+                variable
+            )));
+        }
+    }
+
+    let position = |variable: &str| {
+        pattern
+            .nodes
+            .iter()
+            .position(|n| n.variable == variable)
+            .ok_or_else(|| {
+                GraphError::AlgorithmError(format!(
+                    "Pattern edge uses undeclared node {}",
+                    variable
+                ))
+            })
+    };
+    pattern
+        .edges
+        .iter()
+        .map(|edge| Ok((position(&edge.from)?, position(&edge.to)?)))
+        .collect()
+}
+
+#[derive(Debug, Clone, Copy)]
+enum PlanStep {
+    /// Binds a node not connected to any bound node by scanning every node
+    Scan(usize),
+    /// Binds the unbound end of an edge by following it from the bound end
+    Expand { edge: usize, from_bound: bool },
+    /// Binds an edge whose ends are both bound
+    Connect(usize),
+}
+
+/// Orders the pattern so each step binds one node or edge from the ones before it
+fn plan(pattern: &Pattern, edge_ends: &[(usize, usize)]) -> Vec<PlanStep> {
+    let mut bound_nodes = vec![false; pattern.nodes.len()];
+    let mut bound_edges = vec![false; pattern.edges.len()];
+    let mut steps = Vec::with_capacity(pattern.nodes.len() + pattern.edges.len());
+
+    // an expansion binds a node and an edge in one step, so count what is bound rather than steps
+    while bound_nodes.contains(&false) || bound_edges.contains(&false) {
+        let unbound: Vec<usize> = (0..edge_ends.len()).filter(|&e| !bound_edges[e]).collect();
+        let connect = unbound
+            .iter()
+            .copied()
+            .find(|&e| bound_nodes[edge_ends[e].0] && bound_nodes[edge_ends[e].1]);
+        let expand = unbound
+            .iter()
+            .copied()
+            .find(|&e| bound_nodes[edge_ends[e].0] || bound_nodes[edge_ends[e].1]);
+
+        if let Some(edge) = connect {
+            bound_edges[edge] = true;
+            steps.push(PlanStep::Connect(edge));
+        } else if let Some(edge) = expand {
+            let (from, to) = edge_ends[edge];
+            let from_bound = bound_nodes[from];
+            bound_nodes[if from_bound { to } else { from }] = true;
+            bound_edges[edge] = true;
+            steps.push(PlanStep::Expand { edge, from_bound });
+        } else {
+            // start from the most constrained node as it has the fewest candidates
+            let node = (0..pattern.nodes.len())
+                .filter(|&n| !bound_nodes[n])
+                .max_by_key(|&n| {
+                    let pattern_node = &pattern.nodes[n];
+                    (pattern_node.label.is_some(), pattern_node.predicates.len())
+                })
+                .unwrap();
+            bound_nodes[node] = true;
+            steps.push(PlanStep::Scan(node));
+        }
+    }
+    steps
+}
+
+struct Search<'a> {
+    storage: &'a HelixGraphStorage,
+    pattern: &'a Pattern,
+    edge_ends: &'a [(usize, usize)],
+    plan: &'a [PlanStep],
+    node_bindings: Vec<Option<String>>,
+    edge_bindings: Vec<Option<String>>,
+    /// Ids bound to each pattern node and edge of the complete matches
+    found: Vec<(Vec<String>, Vec<String>)>,
+    limit: usize,
+}
+
+impl Search<'_> {
+    /// Tries every binding for step `step` of the plan and recurses into the next step,
+    /// returning false once `limit` matches have been found
+    fn extend(&mut self, step: usize) -> Result<bool, GraphError> {
+        let Some(&plan_step) = self.plan.get(step) else {
+            let nodes = self
+                .node_bindings
+                .iter()
+                .map(|id| id.clone().unwrap())
+                .collect();
+            let edges = self
+                .edge_bindings
+                .iter()
+                .map(|id| id.clone().unwrap())
+                .collect();
+            self.found.push((nodes, edges));
+            return Ok(self.found.len() < self.limit);
+        };
+
+        match plan_step {
+            PlanStep::Scan(node) => {
+                let pattern_node = &self.pattern.nodes[node];
+                let candidates = self.storage.filter_all_nodes(|data| {
+                    pattern_node
+                        .label
+                        .as_deref()
+                        .is_none_or(|label| label == data.label())
+                        && pattern_node
+                            .predicates
+                            .iter()
+                            .all(|p| p.holds(data.check_property(&p.property)))
+                })?;
+                for candidate in candidates {
+                    if !self.try_node(node, candidate.id, step)? {
+                        return Ok(false);
+                    }
+                }
+            }
+            PlanStep::Expand { edge, from_bound } => {
+                let (from, to) = self.edge_ends[edge];
+                let (bound, free, direction) = match from_bound {
+                    true => (from, to, EdgeDirection::Out),
+                    false => (to, from, EdgeDirection::In),
+                };
+                let bound_id = self.node_bindings[bound].clone().unwrap();
+                for (edge_id, other) in self.adjacent_edges(edge, &bound_id, direction)? {
+                    if !self.node_matches(free, &other)? {
+                        continue;
+                    }
+                    self.edge_bindings[edge] = Some(edge_id);
+                    let more = self.try_node(free, other, step)?;
+                    self.edge_bindings[edge] = None;
+                    if !more {
+                        return Ok(false);
+                    }
+                }
+            }
+            PlanStep::Connect(edge) => {
+                let (from, to) = self.edge_ends[edge];
+                let from_id = self.node_bindings[from].clone().unwrap();
+                let to_id = self.node_bindings[to].clone().unwrap();
+                for (edge_id, other) in self.adjacent_edges(edge, &from_id, EdgeDirection::Out)? {
+                    if other != to_id {
+                        continue;
+                    }
+                    self.edge_bindings[edge] = Some(edge_id);
+                    let more = self.extend(step + 1)?;
+                    self.edge_bindings[edge] = None;
+                    if !more {
+                        return Ok(false);
+                    }
+                }
+            }
+        }
+        Ok(true)
+    }
+
+    /// Binds `node` to the data node `id` unless another pattern node already has it
+    fn try_node(&mut self, node: usize, id: String, step: usize) -> Result<bool, GraphError> {
+        if self
+            .node_bindings
+            .iter()
+            .any(|bound| bound.as_deref() == Some(&id))
+        {
+            return Ok(true);
+        }
+        self.node_bindings[node] = Some(id);
+        let more = self.extend(step + 1)?;
+        self.node_bindings[node] = None;
+        Ok(more)
+    }
+
+    /// Unbound data edges matching pattern edge `edge` adjacent to `node_id` in `direction`,
+    /// along with the node at their other end
+    fn adjacent_edges(
+        &self,
+        edge: usize,
+        node_id: &str,
+        direction: EdgeDirection,
+    ) -> Result<Vec<(String, String)>, GraphError> {
+        let pattern_edge = &self.pattern.edges[edge];
+        let labels: Vec<String> = pattern_edge.label.iter().cloned().collect();
+        let mut adjacent = Vec::new();
+        self.storage
+            .for_each_adjacent_edge(node_id, direction, &labels, |other, data| {
+                let already_bound = self
+                    .edge_bindings
+                    .iter()
+                    .any(|bound| bound.as_deref() == Some(data.id()));
+                let holds = pattern_edge
+                    .predicates
+                    .iter()
+                    .all(|p| p.holds(data.check_property(&p.property)));
+                if !already_bound && holds {
+                    adjacent.push((data.id().to_string(), other.to_string()));
+                }
+                Ok(())
+            })?;
+        Ok(adjacent)
+    }
+
+    fn node_matches(&self, node: usize, id: &str) -> Result<bool, GraphError> {
+        let pattern_node = &self.pattern.nodes[node];
+        self.storage.with_temp_node(id, |data| {
+            pattern_node
+                .label
+                .as_deref()
+                .is_none_or(|label| label == data.label())
+                && pattern_node
+                    .predicates
+                    .iter()
+                    .all(|p| p.holds(data.check_property(&p.property)))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph_algorithms::setup_temp_db;
+    use crate::props;
+
+    fn ids(rows: &[PatternMatch], variable: &str) -> Vec<String> {
+        let mut ids: Vec<String> = rows
+            .iter()
+            .map(|row| row.nodes[variable].id.clone())
+            .collect();
+        ids.sort();
+        ids
+    }
+
+    #[test]
+    fn test_fan_in_pattern() {
+        let (storage, _temp_dir) = setup_temp_db();
+        let accounts: Vec<Node> = (0..5)
+            .map(|i| {
+                storage
+                    .create_node("account", props! { "risk" => i })
+                    .unwrap()
+            })
+            .collect();
+        // accounts 0, 1 and 2 all pay account 3, account 4 pays account 0
+        for (from, to, amount) in [(0, 3, 50), (1, 3, 500), (2, 3, 700), (4, 0, 900)] {
+            storage
+                .create_edge(
+                    "pays",
+                    &accounts[from].id,
+                    &accounts[to].id,
+                    props! { "amount" => amount },
+                )
+                .unwrap();
+        }
+
+        // two different accounts each paying more than 100 into the same account
+        let large = || vec![PropertyPredicate::new("amount", CompareOp::Gt, 100)];
+        let pattern = Pattern::new()
+            .node("a", Some("account"), vec![])
+            .node("b", Some("account"), vec![])
+            .node("target", Some("account"), vec![])
+            .edge(Some("pa"), "a", "target", Some("pays"), large())
+            .edge(Some("pb"), "b", "target", Some("pays"), large());
+        let rows = match_pattern(&storage, &pattern, None).unwrap();
+        // (1, 2) and (2, 1)
+        assert_eq!(rows.len(), 2);
+        assert!(rows
+            .iter()
+            .all(|row| row.nodes["target"].id == accounts[3].id));
+        assert_ne!(rows[0].nodes["a"].id, rows[0].nodes["b"].id);
+        assert_eq!(rows[0].edges["pa"].to_node, accounts[3].id);
+        let mut payers = vec![accounts[1].id.clone(), accounts[2].id.clone()];
+        payers.sort();
+        assert_eq!(ids(&rows, "a"), payers);
+
+        // node predicates narrow the bindings down
+        let pattern = Pattern::new()
+            .node(
+                "a",
+                None,
+                vec![PropertyPredicate::new("risk", CompareOp::Lte, 1)],
+            )
+            .node("target", None, vec![])
+            .edge(None, "a", "target", Some("pays"), vec![]);
+        let rows = match_pattern(&storage, &pattern, None).unwrap();
+        let mut payers = vec![accounts[0].id.clone(), accounts[1].id.clone()];
+        payers.sort();
+        assert_eq!(ids(&rows, "a"), payers);
+        assert!(rows[0].edges.is_empty());
+
+        assert_eq!(match_pattern(&storage, &pattern, Some(1)).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_ring_pattern() {
+        let (storage, _temp_dir) = setup_temp_db();
+        let people: Vec<Node> = (0..4)
+            .map(|_| storage.create_node("person", props!()).unwrap())
+            .collect();
+        // a ring 0 -> 1 -> 2 -> 0, and 2 -> 3 -> 2 which is not long enough to be one
+        for (from, to) in [(0, 1), (1, 2), (2, 0), (2, 3), (3, 2)] {
+            storage
+                .create_edge("knows", &people[from].id, &people[to].id, props!())
+                .unwrap();
+        }
+
+        let pattern = Pattern::new()
+            .node("x", Some("person"), vec![])
+            .node("y", Some("person"), vec![])
+            .node("z", Some("person"), vec![])
+            .edge(None, "x", "y", Some("knows"), vec![])
+            .edge(None, "y", "z", Some("knows"), vec![])
+            .edge(None, "z", "x", Some("knows"), vec![]);
+        let rows = match_pattern(&storage, &pattern, None).unwrap();
+        // one row per rotation of the ring
+        assert_eq!(rows.len(), 3);
+        let mut ring: Vec<String> = people[..3].iter().map(|p| p.id.clone()).collect();
+        ring.sort();
+        assert_eq!(ids(&rows, "x"), ring);
+
+        let invalid =
+            Pattern::new()
+                .node("x", None, vec![])
+                .edge(None, "x", "missing", None, vec![]);
+        assert!(match_pattern(&storage, &invalid, None).is_err());
+        let duplicate = Pattern::new()
+            .node("x", None, vec![])
+            .node("x", None, vec![]);
+        assert!(match_pattern(&storage, &duplicate, None).is_err());
+    }
+}
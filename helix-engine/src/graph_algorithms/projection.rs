@@ -0,0 +1,357 @@
+use std::collections::HashMap;
+
+use rayon::prelude::*;
+
+use crate::storage_core::record::EdgeRef;
+use crate::storage_core::storage_core::HelixGraphStorage;
+use crate::storage_core::storage_methods::EdgeDirection;
+use crate::types::GraphError;
+
+/// Selects the part of the graph an algorithm runs on
+#[derive(Debug, Clone, Default)]
+pub struct ProjectionFilter {
+    /// Labels of the nodes to include, every node if empty
+    pub node_labels: Vec<String>,
+    /// Labels of the edges to include, every edge if empty.
+    /// Edges are only included if both of their nodes are.
+    pub edge_labels: Vec<String>,
+}
+
+impl ProjectionFilter {
+    pub(crate) fn includes(labels: &[String], label: &str) -> bool {
+        labels.is_empty() || labels.iter().any(|l| l == label)
+    }
+}
+
+/// Nodes of a filtered graph numbered like a `GraphProjection`, with the edges between them
+pub(crate) struct EdgeList {
+    pub node_ids: Vec<String>,
+    pub index: HashMap<String, usize>,
+    pub edges: Vec<ListedEdge>,
+}
+
+pub(crate) struct ListedEdge {
+    pub id: String,
+    pub from: usize,
+    pub to: usize,
+    pub weight: f64,
+}
+
+/// Compact in-memory copy of the graph structure used by graph algorithms.
+///
+/// Nodes are numbered `0..node_count()` and adjacency is stored as offset arrays (CSR),
+/// so algorithms can iterate neighbours by index without going back to storage.
+/// Parallel edges are kept, so degrees count edges rather than distinct neighbours.
+/// Every edge has a weight, which is 1 unless the projection was loaded with a weight property.
+#[derive(Debug, Clone)]
+pub struct GraphProjection {
+    node_ids: Vec<String>,
+    index: HashMap<String, usize>,
+    out_offsets: Vec<usize>,
+    out_targets: Vec<usize>,
+    out_weights: Vec<f64>,
+    in_offsets: Vec<usize>,
+    in_sources: Vec<usize>,
+    in_weights: Vec<f64>,
+}
+
+impl GraphProjection {
+    /// Loads the nodes and edges matching `filter` from storage
+    pub fn load(
+        storage: &HelixGraphStorage,
+        filter: &ProjectionFilter,
+    ) -> Result<Self, GraphError> {
+        Self::load_weighted(storage, filter, None)
+    }
+
+    /// Loads the nodes and edges matching `filter` from storage,
+    /// reading edge weights from the numeric `weight_property` of each edge if given.
+    /// Weights must not be negative.
+    pub fn load_weighted(
+        storage: &HelixGraphStorage,
+        filter: &ProjectionFilter,
+        weight_property: Option<&str>,
+    ) -> Result<Self, GraphError> {
+        let (node_ids, index) = Self::load_node_index(storage, filter)?;
+        let mut edges = Vec::new();
+        Self::for_each_indexed_edge(
+            storage,
+            &node_ids,
+            &index,
+            &filter.edge_labels,
+            |from, to, edge| {
+                let weight = match weight_property {
+                    Some(property) => Self::edge_weight(edge, property)?,
+                    None => 1.0,
+                };
+                edges.push((from, to, weight));
+                Ok(())
+            },
+        )?;
+
+        Ok(Self::from_edges(node_ids, index, &edges))
+    }
+
+    /// Loads the ids, endpoints and `weight_property` of the edges matching `filter`,
+    /// for algorithms that return edges rather than nodes
+    pub(crate) fn load_edge_list(
+        storage: &HelixGraphStorage,
+        filter: &ProjectionFilter,
+        weight_property: &str,
+    ) -> Result<EdgeList, GraphError> {
+        let (node_ids, index) = Self::load_node_index(storage, filter)?;
+        let mut edges = Vec::new();
+        Self::for_each_indexed_edge(
+            storage,
+            &node_ids,
+            &index,
+            &filter.edge_labels,
+            |from, to, edge| {
+                edges.push(ListedEdge {
+                    id: edge.id().to_string(),
+                    from,
+                    to,
+                    weight: Self::edge_weight(edge, weight_property)?,
+                });
+                Ok(())
+            },
+        )?;
+        Ok(EdgeList {
+            node_ids,
+            index,
+            edges,
+        })
+    }
+
+    fn load_node_index(
+        storage: &HelixGraphStorage,
+        filter: &ProjectionFilter,
+    ) -> Result<(Vec<String>, HashMap<String, usize>), GraphError> {
+        let mut node_ids = Vec::new();
+        let mut index = HashMap::new();
+        storage.for_each_node_ref(|node| {
+            if ProjectionFilter::includes(&filter.node_labels, node.label()) {
+                index.insert(node.id().to_string(), node_ids.len());
+                node_ids.push(node.id().to_string());
+            }
+            Ok(())
+        })?;
+        Ok((node_ids, index))
+    }
+
+    /// Loads the subgraph induced by the given nodes, reading edges from the outgoing edge indices
+    pub fn load_nodes(
+        storage: &HelixGraphStorage,
+        node_ids: &[String],
+        edge_labels: &[String],
+    ) -> Result<Self, GraphError> {
+        let mut ids = Vec::with_capacity(node_ids.len());
+        let mut index = HashMap::with_capacity(node_ids.len());
+        for node_id in node_ids {
+            if !index.contains_key(node_id) {
+                index.insert(node_id.clone(), ids.len());
+                ids.push(node_id.clone());
+            }
+        }
+
+        let mut edges = Vec::new();
+        Self::for_each_indexed_edge(storage, &ids, &index, edge_labels, |from, to, _| {
+            edges.push((from, to, 1.0));
+            Ok(())
+        })?;
+
+        Ok(Self::from_edges(ids, index, &edges))
+    }
+
+    /// Calls `f` with the indices of both ends of every edge with a label in `edge_labels`
+    /// between nodes in `index`, reading each node's outgoing edge index
+    fn for_each_indexed_edge<F>(
+        storage: &HelixGraphStorage,
+        node_ids: &[String],
+        index: &HashMap<String, usize>,
+        edge_labels: &[String],
+        mut f: F,
+    ) -> Result<(), GraphError>
+    where
+        F: FnMut(usize, usize, EdgeRef<'_>) -> Result<(), GraphError>,
+    {
+        for (from, node_id) in node_ids.iter().enumerate() {
+            storage.for_each_adjacent_edge(
+                node_id,
+                EdgeDirection::Out,
+                edge_labels,
+                |to_id, edge| match index.get(to_id) {
+                    Some(&to) => f(from, to, edge),
+                    None => Ok(()),
+                },
+            )?;
+        }
+        Ok(())
+    }
+
+    pub(crate) fn edge_weight(edge: EdgeRef<'_>, property: &str) -> Result<f64, GraphError> {
+        let weight = edge
+            .check_property(property)
+            .and_then(|v| v.as_f64())
+            .ok_or_else(|| {
+                GraphError::AlgorithmError(format!(
+                    "Edge {} has no numeric {} property",
+                    edge.id(),
+                    property
+                ))
+            })?;
+        if weight.is_nan() || weight < 0.0 {
+            return Err(GraphError::AlgorithmError(format!(
+                "Edge {} has a negative {} of {}",
+                edge.id(),
+                property,
+                weight
+            )));
+        }
+        Ok(weight)
+    }
+
+    fn from_edges(
+        node_ids: Vec<String>,
+        index: HashMap<String, usize>,
+        edges: &[(usize, usize, f64)],
+    ) -> Self {
+        let (out_offsets, out_targets, out_weights) =
+            Self::build_adjacency(node_ids.len(), edges.iter().copied());
+        let (in_offsets, in_sources, in_weights) = Self::build_adjacency(
+            node_ids.len(),
+            edges.iter().map(|&(from, to, weight)| (to, from, weight)),
+        );
+        GraphProjection {
+            node_ids,
+            index,
+            out_offsets,
+            out_targets,
+            out_weights,
+            in_offsets,
+            in_sources,
+            in_weights,
+        }
+    }
+
+    /// Counting sort of `(node, neighbour, weight)` triples into offset, neighbour and weight arrays
+    fn build_adjacency(
+        node_count: usize,
+        edges: impl Iterator<Item = (usize, usize, f64)> + Clone,
+    ) -> (Vec<usize>, Vec<usize>, Vec<f64>) {
+        let mut offsets = vec![0; node_count + 1];
+        for (node, _, _) in edges.clone() {
+            offsets[node + 1] += 1;
+        }
+        for i in 0..node_count {
+            offsets[i + 1] += offsets[i];
+        }
+        let mut next = offsets.clone();
+        let mut neighbours = vec![0; offsets[node_count]];
+        let mut weights = vec![0.0; offsets[node_count]];
+        for (node, neighbour, weight) in edges {
+            neighbours[next[node]] = neighbour;
+            weights[next[node]] = weight;
+            next[node] += 1;
+        }
+        (offsets, neighbours, weights)
+    }
+
+    #[inline]
+    pub fn node_count(&self) -> usize {
+        self.node_ids.len()
+    }
+
+    #[inline]
+    pub fn edge_count(&self) -> usize {
+        self.out_targets.len()
+    }
+
+    /// Id of the node with the given index
+    #[inline]
+    pub fn node_id(&self, node: usize) -> &str {
+        &self.node_ids[node]
+    }
+
+    /// Index of the node with the given id, if it is part of the projection
+    #[inline]
+    pub fn index_of(&self, node_id: &str) -> Option<usize> {
+        self.index.get(node_id).copied()
+    }
+
+    /// Targets of the outgoing edges of a node
+    #[inline]
+    pub fn out_neighbours(&self, node: usize) -> &[usize] {
+        &self.out_targets[self.out_offsets[node]..self.out_offsets[node + 1]]
+    }
+
+    /// Sources of the incoming edges of a node
+    #[inline]
+    pub fn in_neighbours(&self, node: usize) -> &[usize] {
+        &self.in_sources[self.in_offsets[node]..self.in_offsets[node + 1]]
+    }
+
+    /// Weights of the outgoing edges of a node, in the same order as `out_neighbours`
+    #[inline]
+    pub fn out_weights(&self, node: usize) -> &[f64] {
+        &self.out_weights[self.out_offsets[node]..self.out_offsets[node + 1]]
+    }
+
+    /// Weights of the incoming edges of a node, in the same order as `in_neighbours`
+    #[inline]
+    pub fn in_weights(&self, node: usize) -> &[f64] {
+        &self.in_weights[self.in_offsets[node]..self.in_offsets[node + 1]]
+    }
+
+    /// Neighbours along outgoing and incoming edges with the edge weights,
+    /// for algorithms that treat the graph as undirected
+    pub fn undirected_neighbours(&self, node: usize) -> impl Iterator<Item = (usize, f64)> + '_ {
+        let out = self
+            .out_neighbours(node)
+            .iter()
+            .copied()
+            .zip(self.out_weights(node).iter().copied());
+        let incoming = self
+            .in_neighbours(node)
+            .iter()
+            .copied()
+            .zip(self.in_weights(node).iter().copied());
+        out.chain(incoming)
+    }
+
+    #[inline]
+    pub fn out_degree(&self, node: usize) -> usize {
+        self.out_offsets[node + 1] - self.out_offsets[node]
+    }
+
+    #[inline]
+    pub fn in_degree(&self, node: usize) -> usize {
+        self.in_offsets[node + 1] - self.in_offsets[node]
+    }
+
+    /// Sorted distinct neighbours of each node in the given direction, without self loops,
+    /// for algorithms that need a simple graph
+    pub fn simple_adjacency(&self, direction: EdgeDirection) -> Vec<Vec<usize>> {
+        (0..self.node_count())
+            .into_par_iter()
+            .map(|node| {
+                let mut neighbours: Vec<usize> = match direction {
+                    EdgeDirection::Out => self.out_neighbours(node).to_vec(),
+                    EdgeDirection::In => self.in_neighbours(node).to_vec(),
+                    EdgeDirection::Both => {
+                        [self.out_neighbours(node), self.in_neighbours(node)].concat()
+                    }
+                };
+                neighbours.sort_unstable();
+                neighbours.dedup();
+                neighbours.retain(|&neighbour| neighbour != node);
+                neighbours
+            })
+            .collect()
+    }
+
+    /// Pairs each node id with its entry in `values`
+    pub fn with_ids<T>(&self, values: Vec<T>) -> Vec<(String, T)> {
+        self.node_ids.iter().cloned().zip(values).collect()
+    }
+}
@@ -0,0 +1,424 @@
+use std::io::Write;
+
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+use serde::Serialize;
+
+use crate::graph_algorithms::projection::{GraphProjection, ProjectionFilter};
+use crate::storage_core::storage_core::HelixGraphStorage;
+use crate::storage_core::storage_methods::EdgeDirection;
+use crate::types::GraphError;
+
+/// Settings for `random_walks`
+#[derive(Debug, Clone)]
+pub struct RandomWalkConfig {
+    pub filter: ProjectionFilter,
+    /// Direction of the edges walks follow
+    pub direction: EdgeDirection,
+    /// Number of nodes in each walk, including the start node.
+    /// Walks reaching a node without neighbours end early.
+    pub walk_length: usize,
+    pub walks_per_node: usize,
+    /// Nodes to start walks from, every node of the filtered graph if empty
+    pub start_nodes: Vec<String>,
+    /// node2vec return parameter, higher values make walks less likely to go back to the previous node
+    pub return_param: f64,
+    /// node2vec in-out parameter, higher values keep walks close to the previous node
+    /// and lower values push them outwards
+    pub in_out_param: f64,
+    pub seed: u64,
+}
+
+impl Default for RandomWalkConfig {
+    fn default() -> Self {
+        RandomWalkConfig {
+            filter: ProjectionFilter::default(),
+            direction: EdgeDirection::Both,
+            walk_length: 80,
+            walks_per_node: 10,
+            start_nodes: vec![],
+            return_param: 1.0,
+            in_out_param: 1.0,
+            seed: 0,
+        }
+    }
+}
+
+/// Settings for `sample_neighbourhoods`
+#[derive(Debug, Clone)]
+pub struct NeighbourSampleConfig {
+    pub filter: ProjectionFilter,
+    /// Direction of the edges neighbours are sampled along
+    pub direction: EdgeDirection,
+    /// Maximum number of neighbours sampled for each node of the previous layer, one entry per layer
+    pub fanouts: Vec<usize>,
+    pub seed: u64,
+}
+
+impl Default for NeighbourSampleConfig {
+    fn default() -> Self {
+        NeighbourSampleConfig {
+            filter: ProjectionFilter::default(),
+            direction: EdgeDirection::Out,
+            fanouts: vec![10, 5],
+            seed: 0,
+        }
+    }
+}
+
+/// Neighbours sampled around a seed node, layer by layer
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SampledNeighbourhood {
+    pub seed: String,
+    /// Sampled edges of each layer as `(node, neighbour)` pairs,
+    /// where the nodes of a layer are the neighbours sampled in the previous one
+    pub layers: Vec<Vec<(String, String)>>,
+}
+
+/// Generates random walks over the filtered graph.
+///
+/// Walks are uniform when both node2vec parameters are 1 and biased otherwise.
+/// Walks are produced lazily, `walks_per_node` for each start node in turn, and each walk
+/// uses its own generator derived from `seed` so the same config always gives the same walks.
+pub fn random_walks(
+    storage: &HelixGraphStorage,
+    config: &RandomWalkConfig,
+) -> Result<RandomWalks, GraphError> {
+    if config.return_param <= 0.0 || config.in_out_param <= 0.0 {
+        return Err(GraphError::AlgorithmError(
+            "node2vec parameters must be positive".to_string(),
+        ));
+    }
+    let graph = GraphProjection::load(storage, &config.filter)?;
+    let starts = start_indices(&graph, &config.start_nodes)?;
+    Ok(RandomWalks {
+        adjacency: graph.simple_adjacency(config.direction),
+        graph,
+        starts,
+        config: config.clone(),
+        next: 0,
+    })
+}
+
+/// Samples a fixed number of neighbours per node and layer around each seed node, as in GraphSAGE.
+///
+/// Nodes with fewer neighbours than the fanout keep all of them. Neighbourhoods are produced lazily
+/// in the order of `seed_ids`, each with its own generator derived from `seed`.
+pub fn sample_neighbourhoods(
+    storage: &HelixGraphStorage,
+    seed_ids: &[String],
+    config: &NeighbourSampleConfig,
+) -> Result<NeighbourSamples, GraphError> {
+    let graph = GraphProjection::load(storage, &config.filter)?;
+    let seeds = start_indices(&graph, seed_ids)?;
+    Ok(NeighbourSamples {
+        adjacency: graph.simple_adjacency(config.direction),
+        graph,
+        seeds,
+        config: config.clone(),
+        next: 0,
+    })
+}
+
+/// Writes each item as one JSON document per line, returning the number of lines written
+pub fn write_json_lines<T: Serialize, W: Write>(
+    items: impl IntoIterator<Item = T>,
+    mut writer: W,
+) -> Result<usize, GraphError> {
+    let mut count = 0;
+    for item in items {
+        serde_json::to_writer(&mut writer, &item)?;
+        writer.write_all(b"\n")?;
+        count += 1;
+    }
+    writer.flush()?;
+    Ok(count)
+}
+
+fn start_indices(graph: &GraphProjection, node_ids: &[String]) -> Result<Vec<usize>, GraphError> {
+    if node_ids.is_empty() {
+        return Ok((0..graph.node_count()).collect());
+    }
+    node_ids
+        .iter()
+        .map(|id| {
+            graph.index_of(id).ok_or_else(|| {
+                GraphError::AlgorithmError(format!("Node {} is not in the filtered graph", id))
+            })
+        })
+        .collect()
+}
+
+/// Generator for the walk or sample with the given position, independent of the others
+fn rng_for(seed: u64, position: usize) -> StdRng {
+    StdRng::seed_from_u64(seed ^ (position as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15))
+}
+
+/// Lazy iterator over random walks, each a list of node ids. See `random_walks`.
+pub struct RandomWalks {
+    graph: GraphProjection,
+    adjacency: Vec<Vec<usize>>,
+    starts: Vec<usize>,
+    config: RandomWalkConfig,
+    next: usize,
+}
+
+impl RandomWalks {
+    fn walk(&self, start: usize, rng: &mut StdRng) -> Vec<usize> {
+        let mut walk = Vec::with_capacity(self.config.walk_length);
+        if self.config.walk_length == 0 {
+            return walk;
+        }
+        walk.push(start);
+        let uniform = self.config.return_param == 1.0 && self.config.in_out_param == 1.0;
+        let mut weights = Vec::new();
+        while walk.len() < self.config.walk_length {
+            let current = walk[walk.len() - 1];
+            let neighbours = &self.adjacency[current];
+            if neighbours.is_empty() {
+                break;
+            }
+            if walk.len() == 1 || uniform {
+                walk.push(*neighbours.choose(rng).unwrap());
+                continue;
+            }
+
+            let previous = walk[walk.len() - 2];
+            weights.clear();
+            weights.extend(
+                neighbours
+                    .iter()
+                    .map(|&candidate| self.bias(previous, candidate)),
+            );
+            let mut target = rng.gen::<f64>() * weights.iter().sum::<f64>();
+            let mut next = neighbours[neighbours.len() - 1];
+            for (&candidate, &weight) in neighbours.iter().zip(&weights) {
+                if target < weight {
+                    next = candidate;
+                    break;
+                }
+                target -= weight;
+            }
+            walk.push(next);
+        }
+        walk
+    }
+
+    /// Unnormalised node2vec probability of moving to `candidate` after coming from `previous`
+    fn bias(&self, previous: usize, candidate: usize) -> f64 {
+        if candidate == previous {
+            1.0 / self.config.return_param
+        } else if self.adjacency[previous].binary_search(&candidate).is_ok() {
+            1.0
+        } else {
+            1.0 / self.config.in_out_param
+        }
+    }
+}
+
+impl Iterator for RandomWalks {
+    type Item = Vec<String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.config.walks_per_node == 0 {
+            return None;
+        }
+        let start = *self.starts.get(self.next / self.config.walks_per_node)?;
+        let mut rng = rng_for(self.config.seed, self.next);
+        self.next += 1;
+        let walk = self.walk(start, &mut rng);
+        Some(
+            walk.into_iter()
+                .map(|node| self.graph.node_id(node).to_string())
+                .collect(),
+        )
+    }
+}
+
+/// Lazy iterator over sampled neighbourhoods. See `sample_neighbourhoods`.
+pub struct NeighbourSamples {
+    graph: GraphProjection,
+    adjacency: Vec<Vec<usize>>,
+    seeds: Vec<usize>,
+    config: NeighbourSampleConfig,
+    next: usize,
+}
+
+impl Iterator for NeighbourSamples {
+    type Item = SampledNeighbourhood;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let seed = *self.seeds.get(self.next)?;
+        let mut rng = rng_for(self.config.seed, self.next);
+        self.next += 1;
+
+        let mut frontier = vec![seed];
+        let mut layers = Vec::with_capacity(self.config.fanouts.len());
+        for &fanout in &self.config.fanouts {
+            let mut layer = Vec::new();
+            let mut next_frontier = Vec::new();
+            for &node in &frontier {
+                for &neighbour in self.adjacency[node].choose_multiple(&mut rng, fanout) {
+                    layer.push((
+                        self.graph.node_id(node).to_string(),
+                        self.graph.node_id(neighbour).to_string(),
+                    ));
+                    next_frontier.push(neighbour);
+                }
+            }
+            layers.push(layer);
+            frontier = next_frontier;
+        }
+        Some(SampledNeighbourhood {
+            seed: self.graph.node_id(seed).to_string(),
+            layers,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph_algorithms::setup_temp_db;
+    use crate::props;
+    use crate::storage_core::storage_methods::StorageMethods;
+    use protocol::Node;
+
+    /// Items 0 -> 1 -> 2 -> 3 in a chain, with 0 also linking to 4, 5 and 6 and 7 on its own
+    fn setup_items(storage: &HelixGraphStorage) -> Vec<Node> {
+        let items: Vec<Node> = (0..8)
+            .map(|_| storage.create_node("item", props!()).unwrap())
+            .collect();
+        for (from, to) in [(0, 1), (1, 2), (2, 3), (0, 4), (0, 5), (0, 6)] {
+            storage
+                .create_edge("related", &items[from].id, &items[to].id, props!())
+                .unwrap();
+        }
+        items
+    }
+
+    fn linked(storage: &HelixGraphStorage, from: &str, to: &str) -> bool {
+        storage
+            .get_out_nodes(from, "related")
+            .unwrap()
+            .iter()
+            .chain(storage.get_in_nodes(from, "related").unwrap().iter())
+            .any(|node| node.id == to)
+    }
+
+    #[test]
+    fn test_random_walks() {
+        let (storage, _temp_dir) = setup_temp_db();
+        let items = setup_items(&storage);
+
+        let config = RandomWalkConfig {
+            walk_length: 5,
+            walks_per_node: 3,
+            seed: 7,
+            ..RandomWalkConfig::default()
+        };
+        let walks: Vec<Vec<String>> = random_walks(&storage, &config).unwrap().collect();
+        assert_eq!(walks.len(), 8 * 3);
+        for walk in &walks {
+            if walk[0] == items[7].id {
+                assert_eq!(walk.len(), 1);
+                continue;
+            }
+            assert_eq!(walk.len(), 5);
+            for step in walk.windows(2) {
+                assert!(linked(&storage, &step[0], &step[1]));
+            }
+        }
+        let again: Vec<Vec<String>> = random_walks(&storage, &config).unwrap().collect();
+        assert_eq!(walks, again);
+
+        // walks following edge directions stop at the end of the chain
+        let config = RandomWalkConfig {
+            direction: EdgeDirection::Out,
+            walk_length: 10,
+            walks_per_node: 1,
+            start_nodes: vec![items[1].id.clone()],
+            ..RandomWalkConfig::default()
+        };
+        let walks: Vec<Vec<String>> = random_walks(&storage, &config).unwrap().collect();
+        assert_eq!(
+            walks,
+            vec![vec![
+                items[1].id.clone(),
+                items[2].id.clone(),
+                items[3].id.clone()
+            ]]
+        );
+    }
+
+    #[test]
+    fn test_biased_random_walks() {
+        let (storage, _temp_dir) = setup_temp_db();
+        let items = setup_items(&storage);
+
+        // a tiny return parameter makes walks bounce straight back
+        let config = RandomWalkConfig {
+            walk_length: 6,
+            walks_per_node: 5,
+            start_nodes: vec![items[2].id.clone()],
+            return_param: 1e-9,
+            ..RandomWalkConfig::default()
+        };
+        for walk in random_walks(&storage, &config).unwrap() {
+            assert_eq!(walk[0], walk[2]);
+            assert_eq!(walk[2], walk[4]);
+        }
+
+        let invalid = RandomWalkConfig {
+            in_out_param: 0.0,
+            ..RandomWalkConfig::default()
+        };
+        assert!(random_walks(&storage, &invalid).is_err());
+    }
+
+    #[test]
+    fn test_sample_neighbourhoods() {
+        let (storage, _temp_dir) = setup_temp_db();
+        let items = setup_items(&storage);
+
+        let config = NeighbourSampleConfig {
+            fanouts: vec![2, 3],
+            seed: 3,
+            ..NeighbourSampleConfig::default()
+        };
+        let seeds = vec![items[0].id.clone(), items[7].id.clone()];
+        let samples: Vec<SampledNeighbourhood> = sample_neighbourhoods(&storage, &seeds, &config)
+            .unwrap()
+            .collect();
+        assert_eq!(samples.len(), 2);
+
+        let layers = &samples[0].layers;
+        assert_eq!(layers[0].len(), 2);
+        for (node, neighbour) in &layers[0] {
+            assert_eq!(node, &items[0].id);
+            assert!(linked(&storage, node, neighbour));
+        }
+        // only 1 and 2 have outgoing edges, each to a single node
+        let expected = layers[0]
+            .iter()
+            .filter(|(_, n)| *n == items[1].id || *n == items[2].id)
+            .count();
+        assert_eq!(layers[1].len(), expected);
+        assert_eq!(samples[1].layers, vec![vec![], vec![]]);
+
+        let again: Vec<SampledNeighbourhood> = sample_neighbourhoods(&storage, &seeds, &config)
+            .unwrap()
+            .collect();
+        assert_eq!(samples, again);
+
+        let mut output = Vec::new();
+        assert_eq!(write_json_lines(samples, &mut output).unwrap(), 2);
+        let lines: Vec<serde_json::Value> = String::from_utf8(output)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines[1]["seed"], serde_json::json!(items[7].id));
+
+        assert!(sample_neighbourhoods(&storage, &["missing".to_string()], &config).is_err());
+    }
+}
@@ -0,0 +1,380 @@
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+
+use crate::storage_core::storage_core::HelixGraphStorage;
+use crate::storage_core::storage_methods::{EdgeDirection, StorageMethods};
+use crate::types::GraphError;
+
+/// Scores how alike two nodes are from the neighbours they share
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SimilarityMetric {
+    /// Number of shared neighbours
+    CommonNeighbours,
+    /// Shared neighbours over the union of both neighbourhoods
+    Jaccard,
+    /// Sum of `1 / ln(degree)` over shared neighbours, so rare connections weigh more
+    AdamicAdar,
+    /// Product of both degrees
+    PreferentialAttachment,
+    /// Shared neighbours over the geometric mean of both degrees
+    Cosine,
+}
+
+/// Settings for the similarity functions
+#[derive(Debug, Clone)]
+pub struct SimilarityConfig {
+    /// Labels of the edges defining neighbours, every edge if empty
+    pub edge_labels: Vec<String>,
+    pub direction: EdgeDirection,
+    /// Whether nodes already connected to the node are suggested by `most_similar`
+    pub include_neighbours: bool,
+}
+
+impl Default for SimilarityConfig {
+    fn default() -> Self {
+        SimilarityConfig {
+            edge_labels: vec![],
+            direction: EdgeDirection::Both,
+            include_neighbours: false,
+        }
+    }
+}
+
+/// Similarity of two nodes by `metric`, 0 if they share no neighbours.
+/// Parallel edges and self loops are ignored. Errors with `NodeNotFound` if either node does not exist.
+pub fn similarity(
+    storage: &HelixGraphStorage,
+    from_id: &str,
+    to_id: &str,
+    metric: SimilarityMetric,
+    config: &SimilarityConfig,
+) -> Result<f64, GraphError> {
+    if !storage.check_exists(from_id)? || !storage.check_exists(to_id)? {
+        return Err(GraphError::NodeNotFound);
+    }
+    let mut neighbourhoods = Neighbourhoods::new(storage, config);
+    let from = neighbourhoods.get(from_id)?;
+    let to = neighbourhoods.get(to_id)?;
+    neighbourhoods.score(&from, &to, metric)
+}
+
+/// The `k` nodes most similar to `node_id` by `metric`, highest first.
+///
+/// Candidates are the nodes two hops away, since every other node shares no neighbours with it.
+/// Direct neighbours are left out unless `include_neighbours` is set.
+pub fn most_similar(
+    storage: &HelixGraphStorage,
+    node_id: &str,
+    metric: SimilarityMetric,
+    k: usize,
+    config: &SimilarityConfig,
+) -> Result<Vec<(String, f64)>, GraphError> {
+    most_similar_to_any(storage, &[node_id.to_string()], metric, k, config)
+}
+
+/// The `k` nodes most similar to any of `node_ids` by `metric`, each scored by the node it is most similar to,
+/// highest first.
+///
+/// The given nodes are never returned, and neither are the direct neighbours of any of them
+/// unless `include_neighbours` is set.
+pub fn most_similar_to_any(
+    storage: &HelixGraphStorage,
+    node_ids: &[String],
+    metric: SimilarityMetric,
+    k: usize,
+    config: &SimilarityConfig,
+) -> Result<Vec<(String, f64)>, GraphError> {
+    let mut neighbourhoods = Neighbourhoods::new(storage, config);
+    let mut sources = Vec::with_capacity(node_ids.len());
+    for node_id in node_ids {
+        if !storage.check_exists(node_id)? {
+            return Err(GraphError::NodeNotFound);
+        }
+        sources.push(neighbourhoods.get(node_id)?);
+    }
+
+    let mut candidates = HashSet::new();
+    for neighbours in &sources {
+        for neighbour in neighbours.iter() {
+            candidates.extend(neighbourhoods.get(neighbour)?.iter().cloned());
+        }
+    }
+    for neighbours in &sources {
+        if config.include_neighbours {
+            candidates.extend(neighbours.iter().cloned());
+        } else {
+            for neighbour in neighbours.iter() {
+                candidates.remove(neighbour);
+            }
+        }
+    }
+    for node_id in node_ids {
+        candidates.remove(node_id);
+    }
+
+    let mut scores = Vec::with_capacity(candidates.len());
+    for candidate in candidates {
+        let candidate_neighbours = neighbourhoods.get(&candidate)?;
+        let mut best = f64::NEG_INFINITY;
+        for neighbours in &sources {
+            best = best.max(neighbourhoods.score(neighbours, &candidate_neighbours, metric)?);
+        }
+        scores.push((candidate, best));
+    }
+    scores.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    scores.truncate(k);
+    Ok(scores)
+}
+
+/// Sorted distinct neighbours of nodes, read from storage once per node
+struct Neighbourhoods<'a> {
+    storage: &'a HelixGraphStorage,
+    config: &'a SimilarityConfig,
+    cache: HashMap<String, Rc<[String]>>,
+}
+
+impl<'a> Neighbourhoods<'a> {
+    fn new(storage: &'a HelixGraphStorage, config: &'a SimilarityConfig) -> Self {
+        Neighbourhoods {
+            storage,
+            config,
+            cache: HashMap::new(),
+        }
+    }
+
+    fn get(&mut self, node_id: &str) -> Result<Rc<[String]>, GraphError> {
+        if let Some(neighbours) = self.cache.get(node_id) {
+            return Ok(Rc::clone(neighbours));
+        }
+        let mut neighbours = Vec::new();
+        self.storage.for_each_adjacent_edge(
+            node_id,
+            self.config.direction,
+            &self.config.edge_labels,
+            |neighbour, _| {
+                if neighbour != node_id {
+                    neighbours.push(neighbour.to_string());
+                }
+                Ok(())
+            },
+        )?;
+        neighbours.sort_unstable();
+        neighbours.dedup();
+        let neighbours: Rc<[String]> = neighbours.into();
+        self.cache
+            .insert(node_id.to_string(), Rc::clone(&neighbours));
+        Ok(neighbours)
+    }
+
+    fn score(
+        &mut self,
+        a: &[String],
+        b: &[String],
+        metric: SimilarityMetric,
+    ) -> Result<f64, GraphError> {
+        let common = common_neighbours(a, b);
+        Ok(match metric {
+            SimilarityMetric::CommonNeighbours => common.len() as f64,
+            SimilarityMetric::Jaccard => {
+                let union = a.len() + b.len() - common.len();
+                if union == 0 {
+                    0.0
+                } else {
+                    common.len() as f64 / union as f64
+                }
+            }
+            SimilarityMetric::AdamicAdar => {
+                let mut score = 0.0;
+                for neighbour in common {
+                    let degree = self.get(neighbour)?.len();
+                    // a shared neighbour of two other nodes has a degree of at least 2
+                    if degree > 1 {
+                        score += 1.0 / (degree as f64).ln();
+                    }
+                }
+                score
+            }
+            SimilarityMetric::PreferentialAttachment => (a.len() * b.len()) as f64,
+            SimilarityMetric::Cosine => {
+                if a.is_empty() || b.is_empty() {
+                    0.0
+                } else {
+                    common.len() as f64 / ((a.len() * b.len()) as f64).sqrt()
+                }
+            }
+        })
+    }
+}
+
+/// Values two sorted slices have in common
+fn common_neighbours<'s>(a: &'s [String], b: &[String]) -> Vec<&'s String> {
+    let (mut i, mut j) = (0, 0);
+    let mut common = Vec::new();
+    while i < a.len() && j < b.len() {
+        match a[i].cmp(&b[j]) {
+            std::cmp::Ordering::Less => i += 1,
+            std::cmp::Ordering::Greater => j += 1,
+            std::cmp::Ordering::Equal => {
+                common.push(&a[i]);
+                i += 1;
+                j += 1;
+            }
+        }
+    }
+    common
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph_algorithms::setup_temp_db;
+    use crate::props;
+    use protocol::Node;
+
+    /// Person 0 knows 1 and 2, person 3 knows 1 and 2 as well, person 4 only knows 1,
+    /// and 1 also knows 5 which is only linked to 0 through a `blocks` edge
+    fn setup_people(storage: &HelixGraphStorage) -> Vec<Node> {
+        let people: Vec<Node> = (0..6)
+            .map(|_| storage.create_node("person", props!()).unwrap())
+            .collect();
+        for (from, to) in [(0, 1), (0, 2), (3, 1), (2, 3), (4, 1), (1, 5), (0, 1)] {
+            storage
+                .create_edge("knows", &people[from].id, &people[to].id, props!())
+                .unwrap();
+        }
+        storage
+            .create_edge("blocks", &people[0].id, &people[5].id, props!())
+            .unwrap();
+        people
+    }
+
+    fn knows() -> SimilarityConfig {
+        SimilarityConfig {
+            edge_labels: vec!["knows".to_string()],
+            ..SimilarityConfig::default()
+        }
+    }
+
+    #[test]
+    fn test_similarity() {
+        let (storage, _temp_dir) = setup_temp_db();
+        let people = setup_people(&storage);
+        let (a, b) = (&people[0].id, &people[3].id);
+
+        let score = |metric| similarity(&storage, a, b, metric, &knows()).unwrap();
+        assert_eq!(score(SimilarityMetric::CommonNeighbours), 2.0);
+        assert_eq!(score(SimilarityMetric::Jaccard), 1.0);
+        assert_eq!(score(SimilarityMetric::PreferentialAttachment), 4.0);
+        assert!((score(SimilarityMetric::Cosine) - 1.0).abs() < 1e-9);
+        // 1 knows four people and 2 knows two
+        let expected = 1.0 / 4f64.ln() + 1.0 / 2f64.ln();
+        assert!((score(SimilarityMetric::AdamicAdar) - expected).abs() < 1e-9);
+
+        let score = similarity(
+            &storage,
+            a,
+            &people[4].id,
+            SimilarityMetric::Jaccard,
+            &knows(),
+        )
+        .unwrap();
+        assert_eq!(score, 0.5);
+        assert!(matches!(
+            similarity(&storage, a, "missing", SimilarityMetric::Cosine, &knows()),
+            Err(GraphError::NodeNotFound)
+        ));
+    }
+
+    #[test]
+    fn test_most_similar() {
+        let (storage, _temp_dir) = setup_temp_db();
+        let people = setup_people(&storage);
+
+        let results = most_similar(
+            &storage,
+            &people[0].id,
+            SimilarityMetric::CommonNeighbours,
+            2,
+            &knows(),
+        )
+        .unwrap();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0], (people[3].id.clone(), 2.0));
+        assert!(results[1].0 == people[4].id || results[1].0 == people[5].id);
+        assert_eq!(results[1].1, 1.0);
+
+        // the people 0 already knows are left out unless asked for
+        let all = most_similar(
+            &storage,
+            &people[0].id,
+            SimilarityMetric::Jaccard,
+            10,
+            &knows(),
+        )
+        .unwrap();
+        assert_eq!(all.len(), 3);
+        let config = SimilarityConfig {
+            include_neighbours: true,
+            ..knows()
+        };
+        let all = most_similar(
+            &storage,
+            &people[0].id,
+            SimilarityMetric::Jaccard,
+            10,
+            &config,
+        )
+        .unwrap();
+        assert_eq!(all.len(), 5);
+
+        // 5 is a neighbour through `blocks` when every edge counts
+        let all = most_similar(
+            &storage,
+            &people[0].id,
+            SimilarityMetric::Jaccard,
+            10,
+            &SimilarityConfig::default(),
+        )
+        .unwrap();
+        assert!(all.iter().all(|(id, _)| id != &people[5].id));
+
+        assert!(
+            most_similar(&storage, "missing", SimilarityMetric::Jaccard, 10, &knows()).is_err()
+        );
+    }
+    #[test]
+    fn test_most_similar_to_any() {
+        let (storage, _temp_dir) = setup_temp_db();
+        let people = setup_people(&storage);
+        let sources = vec![people[4].id.clone(), people[2].id.clone()];
+
+        // 0 and 3 share 1 with person 4 but are neighbours of person 2
+        let results = most_similar_to_any(
+            &storage,
+            &sources,
+            SimilarityMetric::CommonNeighbours,
+            10,
+            &knows(),
+        )
+        .unwrap();
+        assert_eq!(results, vec![(people[5].id.clone(), 1.0)]);
+
+        // each candidate keeps its best score against any of the nodes
+        let sources = vec![people[0].id.clone(), people[4].id.clone()];
+        let results = most_similar_to_any(
+            &storage,
+            &sources,
+            SimilarityMetric::CommonNeighbours,
+            1,
+            &knows(),
+        )
+        .unwrap();
+        assert_eq!(results, vec![(people[3].id.clone(), 2.0)]);
+
+        let sources = vec![people[0].id.clone(), "missing".to_string()];
+        assert!(matches!(
+            most_similar_to_any(&storage, &sources, SimilarityMetric::Jaccard, 10, &knows()),
+            Err(GraphError::NodeNotFound)
+        ));
+    }
+}
@@ -0,0 +1,106 @@
+use protocol::traversal_value::TraversalValue;
+
+use crate::graph_algorithms::components::UnionFind;
+use crate::graph_algorithms::projection::{GraphProjection, ProjectionFilter};
+use crate::storage_core::storage_core::HelixGraphStorage;
+use crate::storage_core::storage_methods::StorageMethods;
+use crate::types::GraphError;
+
+/// Edges of a minimum spanning tree or forest
+#[derive(Debug, Clone)]
+pub struct SpanningTree {
+    /// The selected edges as a `TraversalValue::EdgeArray`, lightest first
+    pub edges: TraversalValue,
+    pub total_weight: f64,
+}
+
+/// Finds a minimum spanning forest of the filtered graph with Kruskal's algorithm,
+/// reading edge weights from the numeric `weight_property` of each edge.
+///
+/// Edge directions are ignored. A graph that is not connected gets one tree per component,
+/// so the result has `node count - component count` edges.
+pub fn minimum_spanning_tree(
+    storage: &HelixGraphStorage,
+    filter: &ProjectionFilter,
+    weight_property: &str,
+) -> Result<SpanningTree, GraphError> {
+    let mut list = GraphProjection::load_edge_list(storage, filter, weight_property)?;
+    list.edges.sort_by(|a, b| a.weight.total_cmp(&b.weight));
+
+    let mut sets = UnionFind::new(list.node_ids.len());
+    let mut edges = Vec::new();
+    let mut total_weight = 0.0;
+    for edge in &list.edges {
+        if edges.len() + 1 == list.node_ids.len() {
+            break;
+        }
+        if sets.union(edge.from, edge.to) {
+            edges.push(storage.get_edge(&edge.id)?);
+            total_weight += edge.weight;
+        }
+    }
+    Ok(SpanningTree {
+        edges: TraversalValue::EdgeArray(edges),
+        total_weight,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph_algorithms::setup_temp_db;
+    use crate::props;
+    use protocol::{value::Value, Edge, Node};
+
+    fn edges_of(value: &TraversalValue) -> &[Edge] {
+        match value {
+            TraversalValue::EdgeArray(edges) => edges,
+            _ => panic!("Expected edges"),
+        }
+    }
+
+    #[test]
+    fn test_minimum_spanning_tree() {
+        let (storage, _temp_dir) = setup_temp_db();
+        let sites: Vec<Node> = (0..5)
+            .map(|_| storage.create_node("site", props!()).unwrap())
+            .collect();
+        // a square of links with one diagonal, plus site 4 off on its own
+        for (from, to, cost) in [
+            (0, 1, 1.0),
+            (1, 2, 2.0),
+            (2, 3, 1.5),
+            (3, 0, 4.0),
+            (0, 2, 3.0),
+        ] {
+            storage
+                .create_edge(
+                    "link",
+                    &sites[from].id,
+                    &sites[to].id,
+                    props! { "cost" => cost },
+                )
+                .unwrap();
+        }
+        storage
+            .create_edge("plan", &sites[3].id, &sites[4].id, props!())
+            .unwrap();
+
+        let filter = ProjectionFilter {
+            node_labels: vec![],
+            edge_labels: vec!["link".to_string()],
+        };
+        let tree = minimum_spanning_tree(&storage, &filter, "cost").unwrap();
+        let edges = edges_of(&tree.edges);
+        assert_eq!(edges.len(), 3);
+        assert_eq!(tree.total_weight, 4.5);
+        let costs: Vec<Value> = edges.iter().map(|e| e.properties["cost"].clone()).collect();
+        assert_eq!(
+            costs,
+            vec![Value::Float(1.0), Value::Float(1.5), Value::Float(2.0)]
+        );
+
+        // the unweighted edge to site 4 can't be read without a cost
+        assert!(minimum_spanning_tree(&storage, &ProjectionFilter::default(), "cost").is_err());
+    }
+}
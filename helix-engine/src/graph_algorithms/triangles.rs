@@ -0,0 +1,188 @@
+use rayon::prelude::*;
+
+use crate::graph_algorithms::projection::{GraphProjection, ProjectionFilter};
+use crate::storage_core::storage_core::HelixGraphStorage;
+use crate::storage_core::storage_methods::EdgeDirection;
+use crate::types::GraphError;
+
+/// Triangles each node is part of, treating the graph as undirected
+#[derive(Debug, Clone, PartialEq)]
+pub struct Triangles {
+    /// Each node id with the number of triangles it is part of
+    pub per_node: Vec<(String, usize)>,
+    /// Number of distinct triangles in the graph
+    pub total: usize,
+}
+
+/// Local clustering coefficient of every node, treating the graph as undirected
+#[derive(Debug, Clone, PartialEq)]
+pub struct Clustering {
+    /// Each node id with the fraction of pairs of its neighbours that are connected
+    pub coefficients: Vec<(String, f64)>,
+    /// Mean of the local coefficients over all nodes, counting nodes with fewer than two neighbours as 0
+    pub average: f64,
+}
+
+/// Counts the triangles of the filtered graph, per node and in total.
+/// Edge directions, parallel edges and self loops are ignored.
+pub fn triangle_count(
+    storage: &HelixGraphStorage,
+    filter: &ProjectionFilter,
+) -> Result<Triangles, GraphError> {
+    let graph = GraphProjection::load(storage, filter)?;
+    let counts = triangles_of(&graph.simple_adjacency(EdgeDirection::Both));
+    let total = counts.iter().sum::<usize>() / 3;
+    Ok(Triangles {
+        per_node: graph.with_ids(counts),
+        total,
+    })
+}
+
+/// Computes the local clustering coefficient of every node of the filtered graph and their average
+pub fn clustering_coefficients(
+    storage: &HelixGraphStorage,
+    filter: &ProjectionFilter,
+) -> Result<Clustering, GraphError> {
+    let graph = GraphProjection::load(storage, filter)?;
+    let coefficients = clustering_of(&graph.simple_adjacency(EdgeDirection::Both));
+    let average = if coefficients.is_empty() {
+        0.0
+    } else {
+        coefficients.iter().sum::<f64>() / coefficients.len() as f64
+    };
+    Ok(Clustering {
+        coefficients: graph.with_ids(coefficients),
+        average,
+    })
+}
+
+/// Triangles through each node of an undirected simple adjacency with sorted neighbour lists.
+///
+/// Every triangle through a node shows up twice among the common neighbours of the node and
+/// each of its neighbours, so the sum of those intersections is halved.
+pub fn triangles_of(adjacency: &[Vec<usize>]) -> Vec<usize> {
+    adjacency
+        .par_iter()
+        .map(|neighbours| {
+            neighbours
+                .iter()
+                .map(|&neighbour| intersection_size(neighbours, &adjacency[neighbour]))
+                .sum::<usize>()
+                / 2
+        })
+        .collect()
+}
+
+/// Local clustering coefficient of each node of an undirected simple adjacency with sorted neighbour lists
+pub fn clustering_of(adjacency: &[Vec<usize>]) -> Vec<f64> {
+    triangles_of(adjacency)
+        .into_par_iter()
+        .zip(adjacency.par_iter())
+        .map(|(triangles, neighbours)| {
+            let degree = neighbours.len();
+            if degree < 2 {
+                return 0.0;
+            }
+            2.0 * triangles as f64 / (degree * (degree - 1)) as f64
+        })
+        .collect()
+}
+
+/// Number of values two sorted slices have in common
+pub(crate) fn intersection_size(a: &[usize], b: &[usize]) -> usize {
+    let (mut i, mut j, mut count) = (0, 0, 0);
+    while i < a.len() && j < b.len() {
+        match a[i].cmp(&b[j]) {
+            std::cmp::Ordering::Less => i += 1,
+            std::cmp::Ordering::Greater => j += 1,
+            std::cmp::Ordering::Equal => {
+                count += 1;
+                i += 1;
+                j += 1;
+            }
+        }
+    }
+    count
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph_algorithms::setup_temp_db;
+    use crate::props;
+    use crate::storage_core::storage_methods::StorageMethods;
+    use protocol::Node;
+
+    /// Transfers forming triangles 0-1-2 and 1-2-3 with account 4 hanging off 3,
+    /// plus a `knows` edge between 0 and 3 that closes two more triangles
+    fn setup_transfers(storage: &HelixGraphStorage) -> Vec<Node> {
+        let accounts: Vec<Node> = (0..5)
+            .map(|_| storage.create_node("account", props!()).unwrap())
+            .collect();
+        // 1 -> 0 repeats 0 -> 1 in the other direction and must not count twice
+        for (from, to) in [
+            (0, 1),
+            (1, 0),
+            (1, 2),
+            (2, 0),
+            (1, 3),
+            (3, 2),
+            (4, 3),
+            (4, 4),
+        ] {
+            storage
+                .create_edge("transfers", &accounts[from].id, &accounts[to].id, props!())
+                .unwrap();
+        }
+        storage
+            .create_edge("knows", &accounts[0].id, &accounts[3].id, props!())
+            .unwrap();
+        accounts
+    }
+
+    fn transfers() -> ProjectionFilter {
+        ProjectionFilter {
+            node_labels: vec![],
+            edge_labels: vec!["transfers".to_string()],
+        }
+    }
+
+    fn value_of<T: Copy>(values: &[(String, T)], id: &str) -> T {
+        values.iter().find(|(node_id, _)| node_id == id).unwrap().1
+    }
+
+    #[test]
+    fn test_triangle_count() {
+        let (storage, _temp_dir) = setup_temp_db();
+        let accounts = setup_transfers(&storage);
+
+        let triangles = triangle_count(&storage, &transfers()).unwrap();
+        assert_eq!(triangles.total, 2);
+        let counts: Vec<usize> = accounts
+            .iter()
+            .map(|account| value_of(&triangles.per_node, &account.id))
+            .collect();
+        assert_eq!(counts, vec![1, 2, 2, 1, 0]);
+
+        let triangles = triangle_count(&storage, &ProjectionFilter::default()).unwrap();
+        assert_eq!(triangles.total, 4);
+        assert_eq!(value_of(&triangles.per_node, &accounts[0].id), 3);
+    }
+
+    #[test]
+    fn test_clustering_coefficients() {
+        let (storage, _temp_dir) = setup_temp_db();
+        let accounts = setup_transfers(&storage);
+
+        let clustering = clustering_coefficients(&storage, &transfers()).unwrap();
+        let expected = [1.0, 2.0 / 3.0, 2.0 / 3.0, 1.0 / 3.0, 0.0];
+        for (account, expected) in accounts.iter().zip(expected) {
+            assert!((value_of(&clustering.coefficients, &account.id) - expected).abs() < 1e-9);
+        }
+        assert!((clustering.average - 8.0 / 15.0).abs() < 1e-9);
+
+        let (empty, _temp_dir) = setup_temp_db();
+        let clustering = clustering_coefficients(&empty, &ProjectionFilter::default()).unwrap();
+        assert_eq!(clustering.average, 0.0);
+    }
+}
@@ -1,19 +1,29 @@
 use crate::storage_core::storage_methods::StorageMethods;
 use crate::types::GraphError;
 use crate::{props, HelixGraphStorage};
+use std::cmp::Ordering;
 use std::collections::HashMap;
 use std::ops::Deref;
 use std::str;
 use std::sync::{Arc, RwLock};
 
-use super::traversal::TraversalBuilder;
-use super::traversal_steps::{SourceTraversalSteps, TraversalMethods, TraversalSteps};
+use super::traversal::{AnonymousTraversal, Order, Projection, Repeat, TraversalBuilder};
+use super::traversal_steps::{
+    SourceTraversalSteps, TraversalAlgorithmMethods, TraversalMethods, TraversalSearchMethods,
+    TraversalSteps,
+};
+use crate::graph_algorithms::centrality::{CentralityConfig, CentralityMeasure};
+use crate::graph_algorithms::projection::ProjectionFilter;
+use crate::graph_algorithms::similarity::SimilarityMetric;
+use crate::storage_core::record::{NodeRef, ValueRef};
+use crate::storage_core::storage_methods::{EdgeDirection, PathOptions};
 use helixc::parser::helix_parser::{
-    BooleanOp, Expression, GraphStep, HelixParser, IdType, Source, StartNode, Statement, Step,
-    Traversal,
+    AggregateFunction, BooleanOp, CentralityMeasure as ParsedCentrality, ComponentKind, Expression,
+    GraphStep, HelixParser, IdType, PathDirection, SimilarityMetric as ParsedSimilarity, SortOrder,
+    Source, StartNode, Statement, Step, Traversal,
 };
 use protocol::traversal_value::TraversalValue;
-use protocol::{value::Value, Node, ReturnValue};
+use protocol::{value::Value, Edge, Node, ReturnValue};
 use serde_json::json;
 
 #[derive(Debug)]
@@ -133,24 +143,44 @@ impl HelixGraphEngine {
                             }
                             _ => {
                                 // insert variable to hashmap
-                                let var =
-                                    match ass.value {
-                                        Expression::StringLiteral(value) => TraversalValue::from((
-                                            ass.variable.clone(),
-                                            Value::String(value),
-                                        )),
-                                        Expression::IntegerLiteral(value) => TraversalValue::from(
-                                            (ass.variable.clone(), Value::Integer(value)),
-                                        ),
-                                        Expression::FloatLiteral(value) => TraversalValue::from((
-                                            ass.variable.clone(),
-                                            Value::Float(value),
-                                        )),
-                                        Expression::BooleanLiteral(value) => TraversalValue::from(
-                                            (ass.variable.clone(), Value::Boolean(value)),
-                                        ),
-                                        _ => unreachable!(),
-                                    };
+                                let var = match ass.value {
+                                    Expression::StringLiteral(value) => TraversalValue::from((
+                                        ass.variable.clone(),
+                                        Value::String(value),
+                                    )),
+                                    Expression::IntegerLiteral(value) => TraversalValue::from((
+                                        ass.variable.clone(),
+                                        Value::Integer(value),
+                                    )),
+                                    Expression::FloatLiteral(value) => TraversalValue::from((
+                                        ass.variable.clone(),
+                                        Value::Float(value),
+                                    )),
+                                    Expression::BooleanLiteral(value) => TraversalValue::from((
+                                        ass.variable.clone(),
+                                        Value::Boolean(value),
+                                    )),
+                                    Expression::I64Literal(value) => TraversalValue::from((
+                                        ass.variable.clone(),
+                                        Value::I64(value),
+                                    )),
+                                    Expression::U64Literal(value) => TraversalValue::from((
+                                        ass.variable.clone(),
+                                        Value::U64(value),
+                                    )),
+                                    Expression::TimestampLiteral(value) => TraversalValue::from((
+                                        ass.variable.clone(),
+                                        Value::Timestamp(value),
+                                    )),
+                                    Expression::BytesLiteral(value) => TraversalValue::from((
+                                        ass.variable.clone(),
+                                        Value::Bytes(value),
+                                    )),
+                                    Expression::NullLiteral => {
+                                        TraversalValue::from((ass.variable.clone(), Value::Null))
+                                    }
+                                    _ => unreachable!(),
+                                };
                                 ReturnValue::TraversalValues(var)
                             }
                         };
@@ -216,8 +246,15 @@ impl HelixGraphEngine {
         vars: Arc<RwLock<HashMap<String, ReturnValue>>>,
         anon_start: TraversalValue,
     ) -> Result<ReturnValue, GraphError> {
+        // leading steps already applied while reading the start nodes
+        let mut fused_steps = 0;
+        // a LIMIT straight after the source only needs that many nodes or edges read
+        let limit = match tr.steps.first() {
+            Some(Step::Limit(limit)) => Some(*limit),
+            _ => None,
+        };
         let start_nodes: TraversalValue = match tr.start {
-            StartNode::Vertex { types, ids } | StartNode::Edge { types, ids } => {
+            StartNode::Vertex { types, ids } => {
                 let types = match types {
                     Some(types) => types,
                     None => vec![],
@@ -227,9 +264,31 @@ impl HelixGraphEngine {
                     None => vec![],
                 };
 
+                // a WHERE on a single property straight after the source is checked
+                // before the nodes are decoded
+                let filter = match ids.is_empty() {
+                    true => tr.steps.first().and_then(PropertyFilter::from_step),
+                    false => None,
+                };
+                if filter.is_some() {
+                    fused_steps = 1;
+                }
+
                 match ids.len() {
-                    0 => match types.len() {
-                        0 => TraversalValue::NodeArray(Arc::clone(&self.storage).get_all_nodes()?),
+                    0 => match (types.len(), limit, filter) {
+                        (_, Some(limit), _) => TraversalValue::NodeArray(
+                            Arc::clone(&self.storage).get_nodes_limit(&types, limit)?,
+                        ),
+                        (0, None, Some(filter)) => TraversalValue::NodeArray(
+                            self.storage.filter_all_nodes(|node| filter.matches(node))?,
+                        ),
+                        (_, None, Some(filter)) => TraversalValue::NodeArray(
+                            self.storage
+                                .filter_nodes_by_types(&types, |node| filter.matches(node))?,
+                        ),
+                        (0, None, None) => {
+                            TraversalValue::NodeArray(Arc::clone(&self.storage).get_all_nodes()?)
+                        }
                         _ => TraversalValue::NodeArray(
                             Arc::clone(&self.storage).get_nodes_by_types(&types)?,
                         ),
@@ -249,6 +308,35 @@ impl HelixGraphEngine {
                     ),
                 }
             }
+            StartNode::Edge { types, ids } => {
+                let types = types.unwrap_or_default();
+                let ids = ids.unwrap_or_default();
+
+                match ids.len() {
+                    0 => match (types.len(), limit) {
+                        (_, Some(limit)) => {
+                            TraversalValue::EdgeArray(self.storage.get_edges_limit(&types, limit)?)
+                        }
+                        (0, None) => TraversalValue::EdgeArray(self.storage.get_all_edges()?),
+                        _ => TraversalValue::EdgeArray(
+                            self.storage
+                                .get_all_edges()?
+                                .into_iter()
+                                .filter(|edge| types.contains(&edge.label))
+                                .collect(),
+                        ),
+                    },
+                    _ => TraversalValue::EdgeArray(
+                        ids.iter()
+                            .map(|id| {
+                                self.storage.get_edge(id).map_err(|_| {
+                                    GraphError::from(format!("Edge with id: {} not found!", id))
+                                })
+                            })
+                            .collect::<Result<Vec<Edge>, GraphError>>()?,
+                    ),
+                }
+            }
             StartNode::Variable(var_name) => match vars.read().unwrap().get(&var_name) {
                 Some(vals) => match vals.clone() {
                     ReturnValue::TraversalValues(vals) => vals,
@@ -267,103 +355,268 @@ impl HelixGraphEngine {
 
         let mut tr_builder = TraversalBuilder::new(Arc::clone(&self.storage), start_nodes);
 
-        for step in &tr.steps {
+        let mut steps = tr.steps.iter().skip(fused_steps).peekable();
+        while let Some(step) = steps.next() {
             match step {
-                Step::Vertex(graph_step) => match graph_step {
-                    GraphStep::Out(labels) => match labels {
-                        Some(l) => {
-                            if l.len() > 1 {
-                                return Err(GraphError::from("Cannot use more than 1 label yet! This feature will be coming soon."));
-                            }
-                            if let Some(label) = l.first() {
-                                tr_builder.out(label);
-                            }
+                Step::Vertex(graph_step) | Step::Edge(graph_step) => {
+                    // a WHERE on a single property straight after a hop is checked
+                    // before the nodes it reaches are decoded
+                    let filter = steps.peek().copied().and_then(PropertyFilter::from_step);
+                    match (graph_step, filter) {
+                        (GraphStep::Out(labels), Some(filter)) => {
+                            tr_builder
+                                .out_where(&Self::hop_label(labels)?, |node| filter.matches(node));
+                            steps.next();
                         }
-                        None => {
-                            tr_builder.out("");
-                        }
-                    },
-                    GraphStep::In(labels) => match labels {
-                        Some(l) => {
-                            if l.len() > 1 {
-                                return Err(GraphError::from("Cannot use more than 1 label yet! This feature will be coming soon."));
-                            }
-                            if let Some(label) = l.first() {
-                                tr_builder.in_(label);
-                            }
-                        }
-                        None => {
-                            tr_builder.in_("");
-                        }
-                    },
-                    GraphStep::OutE(labels) => match labels {
-                        Some(l) => {
-                            if l.len() > 1 {
-                                return Err(GraphError::from("Cannot use more than 1 label yet! This feature will be coming soon."));
-                            }
-                            if let Some(label) = l.first() {
-                                tr_builder.out_e(label);
-                            }
+                        (GraphStep::In(labels), Some(filter)) => {
+                            tr_builder
+                                .in_where(&Self::hop_label(labels)?, |node| filter.matches(node));
+                            steps.next();
                         }
-                        None => {
-                            tr_builder.out_e("");
+                        _ => Self::apply_graph_step(&mut tr_builder, graph_step)?,
+                    }
+                }
+                Step::Count => {
+                    tr_builder.count();
+                }
+                Step::ShortestPath(path_step) => {
+                    let to_id = Self::id_type_to_id(path_step.to.clone(), Arc::clone(&vars))?;
+                    let edge_labels = path_step.edge_types.clone().unwrap_or_default();
+                    let direction = Self::edge_direction(path_step.direction);
+                    match &path_step.weight {
+                        Some(_) if path_step.max_depth.is_some() => {
+                            return Err(GraphError::from(
+                                "Max depth is only supported for unweighted shortest paths!",
+                            ));
                         }
-                    },
-                    GraphStep::InE(labels) => match labels {
-                        Some(l) => {
-                            if l.len() > 1 {
-                                return Err(GraphError::from("Cannot use more than 1 label yet! This feature will be coming soon."));
-                            }
-                            if let Some(label) = l.first() {
-                                tr_builder.in_e(label);
-                            }
+                        Some(weight) => {
+                            tr_builder.weighted_shortest_path_to(
+                                &to_id,
+                                weight,
+                                direction,
+                                &edge_labels,
+                            );
                         }
                         None => {
-                            tr_builder.in_e("");
-                        }
-                    },
-                    GraphStep::Both(labels) => match labels {
-                        Some(l) => {
-                            if l.len() > 1 {
-                                return Err(GraphError::from("Cannot use more than 1 label yet! This feature will be coming soon."));
-                            }
-                            if let Some(label) = l.first() {
-                                tr_builder.both(label);
-                            }
+                            let options = PathOptions {
+                                direction,
+                                edge_labels,
+                                max_depth: path_step.max_depth,
+                            };
+                            tr_builder.shortest_path_to_with(&to_id, &options);
                         }
-                        None => {
-                            tr_builder.both("");
+                    }
+                    if let Some(err) = tr_builder.error.take() {
+                        return Err(err);
+                    }
+                }
+                Step::Components(components_step) => {
+                    let edge_labels = components_step.edge_types.clone().unwrap_or_default();
+                    match components_step.kind {
+                        ComponentKind::Weak => tr_builder.connected_components(&edge_labels),
+                        ComponentKind::Strong => {
+                            tr_builder.strongly_connected_components(&edge_labels)
                         }
-                    },
-                    GraphStep::BothE(labels) => match labels {
-                        Some(l) => {
-                            if l.len() > 1 {
-                                return Err(GraphError::from("Cannot use more than 1 label yet! This feature will be coming soon."));
-                            }
-                            if let Some(label) = l.first() {
-                                tr_builder.both_e(label);
-                            }
+                    };
+                    if let Some(err) = tr_builder.error.take() {
+                        return Err(err);
+                    }
+                }
+                Step::Centrality(centrality_step) => {
+                    let config = CentralityConfig {
+                        filter: ProjectionFilter {
+                            node_labels: vec![],
+                            edge_labels: centrality_step.edge_types.clone().unwrap_or_default(),
+                        },
+                        direction: Self::edge_direction(centrality_step.direction),
+                        samples: centrality_step.samples,
+                        top_k: centrality_step.top,
+                        ..CentralityConfig::default()
+                    };
+                    let measure = match centrality_step.measure {
+                        ParsedCentrality::Degree => CentralityMeasure::Degree,
+                        ParsedCentrality::Closeness => CentralityMeasure::Closeness,
+                        ParsedCentrality::Betweenness => CentralityMeasure::Betweenness,
+                    };
+                    tr_builder.centrality(measure, &config);
+                    if let Some(err) = tr_builder.error.take() {
+                        return Err(err);
+                    }
+                }
+                Step::Similar(similar_step) => {
+                    let edge_labels = similar_step.edge_types.clone().unwrap_or_default();
+                    let metric = match similar_step.metric {
+                        ParsedSimilarity::CommonNeighbours => SimilarityMetric::CommonNeighbours,
+                        ParsedSimilarity::Jaccard => SimilarityMetric::Jaccard,
+                        ParsedSimilarity::AdamicAdar => SimilarityMetric::AdamicAdar,
+                        ParsedSimilarity::PreferentialAttachment => {
+                            SimilarityMetric::PreferentialAttachment
                         }
-                        None => {
-                            tr_builder.both_e("");
+                        ParsedSimilarity::Cosine => SimilarityMetric::Cosine,
+                    };
+                    tr_builder.similar(&edge_labels, metric, similar_step.top);
+                    if let Some(err) = tr_builder.error.take() {
+                        return Err(err);
+                    }
+                }
+                Step::Repeat(repeat_step) => {
+                    let graph_step = &repeat_step.step;
+                    let repeat = Repeat::new(|t: &mut TraversalBuilder| {
+                        if let Err(err) = Self::apply_graph_step(t, graph_step) {
+                            t.error = Some(err);
                         }
-                    },
-                    _ => unreachable!(),
-                },
-                Step::Edge(graph_step) => match graph_step {
-                    GraphStep::OutV => {
-                        tr_builder.out_v();
+                    })
+                    .times(repeat_step.max)
+                    .emit_from(repeat_step.min);
+                    tr_builder.repeat(repeat);
+                    if let Some(err) = tr_builder.error.take() {
+                        return Err(err);
                     }
-                    GraphStep::InV => {
-                        tr_builder.in_v();
+                }
+                Step::Dedup(property) => {
+                    match property {
+                        Some(property) => tr_builder.dedup_by(property),
+                        None => tr_builder.dedup(),
+                    };
+                    if let Some(err) = tr_builder.error.take() {
+                        return Err(err);
                     }
-                    GraphStep::BothV => {
-                        tr_builder.both_v();
+                }
+                Step::OrderBy(order_by) => {
+                    let order = match order_by.order {
+                        SortOrder::Asc => Order::Asc,
+                        SortOrder::Desc => Order::Desc,
+                    };
+                    tr_builder.order_by(&order_by.property, order);
+                    if let Some(err) = tr_builder.error.take() {
+                        return Err(err);
+                    }
+                }
+                Step::Limit(limit) => {
+                    tr_builder.limit(*limit);
+                    if let Some(err) = tr_builder.error.take() {
+                        return Err(err);
+                    }
+                }
+                Step::GroupBy(key) => {
+                    tr_builder.group_by(key);
+                    if let Some(err) = tr_builder.error.take() {
+                        return Err(err);
+                    }
+                }
+                Step::GroupCount(key) => {
+                    tr_builder.group_count(key);
+                    if let Some(err) = tr_builder.error.take() {
+                        return Err(err);
+                    }
+                }
+                Step::Aggregate(aggregate) => {
+                    let property = &aggregate.property;
+                    match aggregate.function {
+                        AggregateFunction::Sum => tr_builder.sum(property),
+                        AggregateFunction::Mean => tr_builder.mean(property),
+                        AggregateFunction::Min => tr_builder.min(property),
+                        AggregateFunction::Max => tr_builder.max(property),
+                        AggregateFunction::CollectSet => tr_builder.collect_set(property),
+                    };
+                    if let Some(err) = tr_builder.error.take() {
+                        return Err(err);
+                    }
+                }
+                Step::Union(traversals) => {
+                    let branches = traversals
+                        .iter()
+                        .map(|anon_tr| self.anonymous_traversal(anon_tr, &vars))
+                        .collect();
+                    tr_builder.union(branches);
+                    if let Some(err) = tr_builder.error.take() {
+                        return Err(err);
+                    }
+                }
+                Step::Coalesce(traversals) => {
+                    let branches = traversals
+                        .iter()
+                        .map(|anon_tr| self.anonymous_traversal(anon_tr, &vars))
+                        .collect();
+                    tr_builder.coalesce(branches);
+                    if let Some(err) = tr_builder.error.take() {
+                        return Err(err);
+                    }
+                }
+                Step::Choose(choose) => {
+                    tr_builder.choose(
+                        self.anonymous_traversal(&choose.predicate, &vars),
+                        self.anonymous_traversal(&choose.then, &vars),
+                        choose
+                            .otherwise
+                            .as_ref()
+                            .map(|anon_tr| self.anonymous_traversal(anon_tr, &vars)),
+                    );
+                    if let Some(err) = tr_builder.error.take() {
+                        return Err(err);
+                    }
+                }
+                Step::Optional(anon_tr) => {
+                    tr_builder.optional(self.anonymous_traversal(anon_tr, &vars));
+                    if let Some(err) = tr_builder.error.take() {
+                        return Err(err);
+                    }
+                }
+                Step::AddField(fields) => {
+                    let projections = fields
+                        .iter()
+                        .map(|field| {
+                            // properties are read with `_::Props(name)`,
+                            // so a bare identifier is always a query variable
+                            let projection = match &field.value {
+                                Expression::Identifier(var_name) => {
+                                    let value = match vars.read().unwrap().get(var_name) {
+                                        Some(ReturnValue::TraversalValues(value)) => value.clone(),
+                                        _ => {
+                                            return Err(GraphError::from(format!(
+                                                "Variable: {} not found!",
+                                                var_name
+                                            )))
+                                        }
+                                    };
+                                    Projection::Traversal(Box::new(move |t: &mut TraversalBuilder| {
+                                        t.current_step = value.clone();
+                                    }))
+                                }
+                                Expression::Traversal(anon_tr) => {
+                                    match (&anon_tr.start, &anon_tr.steps[..]) {
+                                        (StartNode::Anonymous, [Step::Props(properties)])
+                                            if properties.len() == 1 =>
+                                        {
+                                            Projection::Property(properties[0].clone())
+                                        }
+                                        (StartNode::Anonymous | StartNode::Variable(_), _) => {
+                                            Projection::Traversal(
+                                                self.anonymous_traversal(anon_tr, &vars),
+                                            )
+                                        }
+                                        _ => {
+                                            return Err(GraphError::from(format!(
+                                                "Field {} must be a property access such as _::Props(name), a variable or an anonymous traversal!",
+                                                field.name
+                                            )))
+                                        }
+                                    }
+                                }
+                                _ => {
+                                    return Err(GraphError::from(format!(
+                                        "Field {} must be a property access such as _::Props(name), a variable or an anonymous traversal!",
+                                        field.name
+                                    )))
+                                }
+                            };
+                            Ok((field.name.as_str(), projection))
+                        })
+                        .collect::<Result<Vec<_>, GraphError>>()?;
+                    tr_builder.project(projections);
+                    if let Some(err) = tr_builder.error.take() {
+                        return Err(err);
                     }
-                    _ => unreachable!(),
-                },
-                Step::Count => {
-                    tr_builder.count();
                 }
                 Step::Props(property_names) => {
                     assert!(property_names.len() > 0, "Property names must be provided!");
@@ -526,33 +779,22 @@ impl HelixGraphEngine {
 
                     match tr_builder.current_step {
                         TraversalValue::Count(count) => {
-                            return Ok(ReturnValue::Boolean(Self::manage_int_bool_exp(
+                            return Ok(ReturnValue::Boolean(Self::manage_bool_exp(
                                 op,
-                                count.value() as i32,
+                                &Value::U64(count.value() as u64),
                             )))
                         }
                         TraversalValue::ValueArray(ref vals) => {
-                            let mut res = Vec::with_capacity(vals.len());
-                            for (_, val) in vals {
-                                match val {
-                                    Value::Integer(val) => {
-                                        res.push(Self::manage_int_bool_exp(op, val.clone()));
-                                    }
-                                    Value::Float(val) => {
-                                        res.push(Self::manage_float_bool_exp(op, val.clone()));
-                                    }
-                                    _ => {
-                                        return Err(GraphError::from(
-                                            "Expression should resolve to a number!",
-                                        ));
-                                    }
-                                }
-                            }
-                            return Ok(ReturnValue::Boolean(res.iter().all(|&x| x)));
+                            return Ok(ReturnValue::Boolean(
+                                vals.iter().all(|(_, val)| Self::manage_bool_exp(op, val)),
+                            ));
+                        }
+                        TraversalValue::Value(ref val) => {
+                            return Ok(ReturnValue::Boolean(Self::manage_bool_exp(op, val)));
                         }
                         _ => {
                             return Err(GraphError::from(
-                                format!("Boolean operation must follow a count or numerical property step! Got step: {:?} for traversal {:?}", tr_builder.current_step, step),
+                                format!("Boolean operation must follow a count, property or aggregation step! Got step: {:?} for traversal {:?}", tr_builder.current_step, step),
                             ));
                         }
                     };
@@ -564,116 +806,117 @@ impl HelixGraphEngine {
         Ok(ReturnValue::TraversalValues(tr_builder.current_step))
     }
 
-    fn manage_float_bool_exp(op: &BooleanOp, fl: f64) -> bool {
-        match op {
-            BooleanOp::GreaterThan(expr) => match **expr {
-                Expression::FloatLiteral(val) => {
-                    return fl > val;
-                }
-                _ => {
-                    return false;
-                }
-            },
-            BooleanOp::GreaterThanOrEqual(expr) => match **expr {
-                Expression::FloatLiteral(val) => {
-                    return fl >= val;
-                }
-                _ => {
-                    return false;
-                }
-            },
-            BooleanOp::LessThan(expr) => match **expr {
-                Expression::FloatLiteral(val) => {
-                    return fl < val;
-                }
-                _ => {
-                    return false;
-                }
-            },
-            BooleanOp::LessThanOrEqual(expr) => match **expr {
-                Expression::FloatLiteral(val) => {
-                    return fl <= val;
-                }
-                _ => {
-                    return false;
-                }
-            },
-            BooleanOp::Equal(expr) => match **expr {
-                Expression::FloatLiteral(val) => {
-                    return fl == val;
-                }
-                _ => {
-                    return false;
-                }
-            },
-            BooleanOp::NotEqual(expr) => match **expr {
-                Expression::FloatLiteral(val) => {
-                    return fl != val;
-                }
-                _ => {
-                    return false;
-                }
-            },
-            _ => {
-                return false;
+    /// Wraps an anonymous traversal so the branching steps can run it from a single node or edge,
+    /// evaluating it the same way as in WHERE. Booleans it evaluates to become `TraversalValue::Value`.
+    fn anonymous_traversal<'a>(
+        &'a self,
+        anon_tr: &'a Traversal,
+        vars: &'a Arc<RwLock<HashMap<String, ReturnValue>>>,
+    ) -> AnonymousTraversal<'a> {
+        Box::new(move |t: &mut TraversalBuilder| {
+            let start = std::mem::replace(&mut t.current_step, TraversalValue::Empty);
+            match self.evaluate_traversal(Box::new(anon_tr.clone()), Arc::clone(vars), start) {
+                Ok(ReturnValue::TraversalValues(value)) => t.current_step = value,
+                Ok(ReturnValue::Count(count)) => t.current_step = TraversalValue::Count(count),
+                Ok(ReturnValue::Boolean(b)) => {
+                    t.current_step = TraversalValue::Value(Value::Boolean(b))
+                }
+                Ok(ReturnValue::Empty) => {}
+                Err(err) => t.error = Some(err),
             }
+        })
+    }
+
+    /// The edge label of a hop step, an empty label follows edges of any label
+    fn hop_label(labels: &Option<Vec<String>>) -> Result<String, GraphError> {
+        match labels {
+            Some(l) if l.len() > 1 => Err(GraphError::from(
+                "Cannot use more than 1 label yet! This feature will be coming soon.",
+            )),
+            Some(l) => Ok(l.first().cloned().unwrap_or_default()),
+            None => Ok(String::new()),
+        }
+    }
+
+    /// Applies a single hop step, following edges of any label if none is given
+    fn apply_graph_step(
+        tr_builder: &mut TraversalBuilder,
+        graph_step: &GraphStep,
+    ) -> Result<(), GraphError> {
+        let label = Self::hop_label;
+        match graph_step {
+            GraphStep::Out(labels) => tr_builder.out(&label(labels)?),
+            GraphStep::In(labels) => tr_builder.in_(&label(labels)?),
+            GraphStep::Both(labels) => tr_builder.both(&label(labels)?),
+            GraphStep::OutE(labels) => tr_builder.out_e(&label(labels)?),
+            GraphStep::InE(labels) => tr_builder.in_e(&label(labels)?),
+            GraphStep::BothE(labels) => tr_builder.both_e(&label(labels)?),
+            GraphStep::OutV => tr_builder.out_v(),
+            GraphStep::InV => tr_builder.in_v(),
+            GraphStep::BothV => tr_builder.both_v(),
         };
+        Ok(())
     }
 
-    fn manage_int_bool_exp(op: &BooleanOp, i: i32) -> bool {
-        match op {
-            BooleanOp::GreaterThan(expr) => match **expr {
-                Expression::IntegerLiteral(val) => {
-                    return i > val;
-                }
-                _ => {
-                    return false;
-                }
-            },
-            BooleanOp::GreaterThanOrEqual(expr) => match **expr {
-                Expression::IntegerLiteral(val) => {
-                    return i >= val;
-                }
-                _ => {
-                    return false;
-                }
-            },
-            BooleanOp::LessThan(expr) => match **expr {
-                Expression::IntegerLiteral(val) => {
-                    return i < val;
-                }
-                _ => {
-                    return false;
-                }
-            },
-            BooleanOp::LessThanOrEqual(expr) => match **expr {
-                Expression::IntegerLiteral(val) => {
-                    return i <= val;
-                }
-                _ => {
-                    return false;
-                }
-            },
-            BooleanOp::Equal(expr) => match **expr {
-                Expression::IntegerLiteral(val) => {
-                    return i == val;
-                }
-                _ => {
-                    return false;
-                }
-            },
-            BooleanOp::NotEqual(expr) => match **expr {
-                Expression::IntegerLiteral(val) => {
-                    return i != val;
-                }
-                _ => {
-                    return false;
-                }
-            },
-            _ => {
-                return false;
-            }
+    /// Compares a value against the literal in a boolean operation using the total ordering of `Value`,
+    /// so numbers of different widths, strings and booleans can all be compared.
+    /// A missing property never matches, whatever the operation.
+    fn manage_bool_exp(op: &BooleanOp, value: &Value) -> bool {
+        if let Value::Empty = value {
+            return false;
+        }
+        match Self::bool_exp_literal(op, matches!(value, Value::Timestamp(_))) {
+            Some((literal, cmp)) => cmp(value.cmp(&literal)),
+            None => false,
+        }
+    }
+
+    /// `manage_bool_exp` for a value borrowed from a stored record
+    fn manage_bool_exp_ref(op: &BooleanOp, value: ValueRef<'_>) -> bool {
+        if let ValueRef::Empty = value {
+            return false;
+        }
+        match Self::bool_exp_literal(op, matches!(value, ValueRef::Timestamp(_))) {
+            Some((literal, cmp)) => cmp(value.cmp_value(&literal)),
+            None => false,
+        }
+    }
+
+    /// The literal a boolean operation compares against and the orderings that satisfy it
+    fn bool_exp_literal(op: &BooleanOp, timestamp: bool) -> Option<(Value, OrderingCheck)> {
+        let (expr, cmp): (&Expression, OrderingCheck) = match op {
+            BooleanOp::GreaterThan(expr) => (expr, |o| o == Ordering::Greater),
+            BooleanOp::GreaterThanOrEqual(expr) => (expr, |o| o != Ordering::Less),
+            BooleanOp::LessThan(expr) => (expr, |o| o == Ordering::Less),
+            BooleanOp::LessThanOrEqual(expr) => (expr, |o| o != Ordering::Greater),
+            BooleanOp::Equal(expr) => (expr, |o| o == Ordering::Equal),
+            BooleanOp::NotEqual(expr) => (expr, |o| o != Ordering::Equal),
+            _ => return None,
         };
+        let literal = match *expr {
+            // integer literals are compared against timestamps as milliseconds since the epoch
+            Expression::IntegerLiteral(val) if timestamp => Value::Timestamp(val as i64),
+            Expression::I64Literal(val) if timestamp => Value::Timestamp(val),
+            Expression::IntegerLiteral(val) => Value::Integer(val),
+            Expression::I64Literal(val) => Value::I64(val),
+            Expression::U64Literal(val) => Value::U64(val),
+            Expression::FloatLiteral(val) => Value::Float(val),
+            Expression::StringLiteral(ref val) => Value::String(val.clone()),
+            Expression::BooleanLiteral(val) => Value::Boolean(val),
+            Expression::TimestampLiteral(val) => Value::Timestamp(val),
+            Expression::BytesLiteral(ref val) => Value::Bytes(val.clone()),
+            Expression::NullLiteral => Value::Null,
+            _ => return None,
+        };
+        Some((literal, cmp))
+    }
+
+    fn edge_direction(direction: PathDirection) -> EdgeDirection {
+        match direction {
+            PathDirection::Out => EdgeDirection::Out,
+            PathDirection::In => EdgeDirection::In,
+            PathDirection::Both => EdgeDirection::Both,
+        }
     }
 
     fn id_type_to_id(
@@ -720,3 +963,46 @@ impl HelixGraphEngine {
         }
     }
 }
+
+/// Whether the ordering of a value against a literal satisfies a boolean operation
+type OrderingCheck = fn(Ordering) -> bool;
+
+/// A WHERE step comparing a single property with a literal, e.g. `WHERE(_::{age}::GT(30))`.
+///
+/// These are checked against borrowed views of the stored nodes,
+/// so the nodes that fail the comparison are never decoded.
+struct PropertyFilter<'a> {
+    property: &'a str,
+    op: &'a BooleanOp,
+}
+
+impl PropertyFilter<'_> {
+    fn from_step(step: &Step) -> Option<PropertyFilter<'_>> {
+        let Step::Where(expression) = step else {
+            return None;
+        };
+        let Expression::Traversal(tr) = &**expression else {
+            return None;
+        };
+        match (&tr.start, tr.steps.as_slice()) {
+            (StartNode::Anonymous, [Step::Props(names), Step::BooleanOperation(op)])
+                if names.len() == 1 =>
+            {
+                Some(PropertyFilter {
+                    property: names[0].as_str(),
+                    op,
+                })
+            }
+            _ => None,
+        }
+    }
+
+    /// Same result as evaluating the WHERE step on the decoded node,
+    /// where a missing property never matches
+    fn matches(&self, node: NodeRef<'_>) -> bool {
+        match node.check_property(self.property) {
+            Some(value) => HelixGraphEngine::manage_bool_exp_ref(self.op, value),
+            None => false,
+        }
+    }
+}
@@ -1,21 +1,33 @@
 use crate::{
+    graph_algorithms::{
+        centrality::{self, CentralityConfig, CentralityMeasure},
+        components::{self, Components},
+        projection::GraphProjection,
+        similarity::{self, SimilarityConfig, SimilarityMetric},
+    },
     graph_core::traversal_steps::{
-        SourceTraversalSteps, TraversalMethods, TraversalSearchMethods, TraversalSteps,
+        SourceTraversalSteps, TraversalAlgorithmMethods, TraversalMethods, TraversalSearchMethods,
+        TraversalSteps,
     },
     props,
     storage_core::{
+        record::NodeRef,
         storage_core::HelixGraphStorage,
-        storage_methods::{SearchMethods, StorageMethods},
+        storage_methods::{EdgeDirection, PathOptions, SearchMethods, StorageMethods},
     },
     types::GraphError,
 };
 use core::panic;
 use protocol::{
-    count::Count, filterable::Filterable, traversal_value::TraversalValue, value::Value, Edge, Node,
+    count::Count,
+    filterable::Filterable,
+    traversal_value::{Row, TraversalValue},
+    value::Value,
+    Edge, Node,
 };
 use std::{
     borrow::Cow,
-    collections::{HashMap, HashSet},
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
     sync::Arc,
 };
 
@@ -26,6 +38,35 @@ pub struct TraversalBuilder {
     pub current_step: TraversalValue,
     pub storage: Arc<HelixGraphStorage>,
     pub error: Option<GraphError>,
+    /// Route taken to each current node or edge, in the same order, while path tracking is on
+    pub paths: Option<Vec<Path>>,
+}
+
+/// Nodes and edges visited by a traverser, in order,
+/// with the node or edge it was at when tagged by each `as_` step
+pub type Path = (Vec<Node>, Vec<Edge>, Vec<(String, TraversalValue)>);
+
+/// Paths found without weights, which carry no cost
+fn unweighted_paths(paths: impl IntoIterator<Item = (Vec<Node>, Vec<Edge>)>) -> TraversalValue {
+    TraversalValue::Paths(
+        paths
+            .into_iter()
+            .map(|(nodes, edges)| (nodes, edges, None))
+            .collect(),
+    )
+}
+
+/// A row with the node under `node` and the values computed for it by an algorithm beside it,
+/// so they never shadow the node's own properties
+fn node_row<const N: usize>(node: Node, fields: [(&str, Value); N]) -> Row {
+    let mut row = Vec::with_capacity(N + 1);
+    row.push(("node".to_string(), TraversalValue::from(node)));
+    row.extend(
+        fields
+            .into_iter()
+            .map(|(name, value)| (name.to_string(), TraversalValue::Value(value))),
+    );
+    Row(row)
 }
 
 impl TraversalBuilder {
@@ -35,6 +76,7 @@ impl TraversalBuilder {
             current_step: start_nodes,
             storage,
             error: None,
+            paths: None,
         }
     }
 
@@ -69,6 +111,491 @@ impl TraversalBuilder {
             self.error = Some(err);
         }
     }
+
+    /// Starts a new path at each current node or edge if path tracking is on
+    fn restart_paths(&mut self) {
+        if self.paths.is_some() {
+            self.paths = Some(Self::initial_paths(&self.current_step));
+        }
+    }
+
+    fn initial_paths(step: &TraversalValue) -> Vec<Path> {
+        match step {
+            TraversalValue::NodeArray(nodes) => nodes
+                .iter()
+                .map(|node| (vec![node.clone()], vec![], vec![]))
+                .collect(),
+            TraversalValue::EdgeArray(edges) => edges
+                .iter()
+                .map(|edge| (vec![], vec![edge.clone()], vec![]))
+                .collect(),
+            _ => vec![],
+        }
+    }
+
+    /// Keeps the paths of the traversers for which `keep` is true
+    fn retain_paths(&mut self, keep: &[bool]) {
+        if let Some(paths) = &mut self.paths {
+            let mut keep = keep.iter();
+            paths.retain(|_| *keep.next().unwrap_or(&false));
+        }
+    }
+
+    /// Hop steps from nodes while path tracking is on.
+    /// Edges are always read so they can be added to the paths, even when only the nodes are kept.
+    fn hop_tracked(
+        &mut self,
+        direction: EdgeDirection,
+        edge_label: &str,
+        to_edges: bool,
+    ) -> &mut Self {
+        self.hop_tracked_where(direction, edge_label, to_edges, |_| true)
+    }
+
+    /// `hop_tracked` keeping only the nodes for which `f` returns true, along with their paths
+    fn hop_tracked_where<F: Fn(NodeRef<'_>) -> bool>(
+        &mut self,
+        direction: EdgeDirection,
+        edge_label: &str,
+        to_edges: bool,
+        f: F,
+    ) -> &mut Self {
+        let nodes = match &self.current_step {
+            TraversalValue::NodeArray(nodes) => nodes,
+            _ => return self,
+        };
+        let paths = self.paths.take().unwrap_or_default();
+        let mut e = GraphError::Empty;
+        let mut new_nodes = Vec::with_capacity(nodes.len());
+        let mut new_edges = Vec::new();
+        let mut new_paths = Vec::with_capacity(nodes.len());
+        for (node, path) in nodes.iter().zip(paths) {
+            let mut adjacent = Vec::new();
+            if direction != EdgeDirection::Out {
+                match self.storage.get_in_edges(&node.id, edge_label) {
+                    Ok(edges) => adjacent.extend(edges.into_iter().map(|edge| {
+                        let other = edge.from_node.clone();
+                        (edge, other)
+                    })),
+                    Err(err) => e = err,
+                }
+            }
+            if direction != EdgeDirection::In {
+                match self.storage.get_out_edges(&node.id, edge_label) {
+                    Ok(edges) => adjacent.extend(edges.into_iter().map(|edge| {
+                        let other = edge.to_node.clone();
+                        (edge, other)
+                    })),
+                    Err(err) => e = err,
+                }
+            }
+
+            for (edge, other) in adjacent {
+                let mut path = path.clone();
+                path.1.push(edge.clone());
+                if to_edges {
+                    new_edges.push(edge);
+                } else {
+                    match self
+                        .storage
+                        .with_temp_node(&other, |node| f(node).then(|| node.to_node()))
+                    {
+                        Ok(Some(next)) => {
+                            path.0.push(next.clone());
+                            new_nodes.push(next);
+                        }
+                        Ok(None) => continue,
+                        Err(err) => {
+                            e = err;
+                            continue;
+                        }
+                    }
+                }
+                new_paths.push(path);
+            }
+        }
+
+        self.current_step = match (to_edges, new_paths.is_empty()) {
+            (_, true) => TraversalValue::Empty,
+            (true, false) => TraversalValue::EdgeArray(new_edges),
+            (false, false) => TraversalValue::NodeArray(new_nodes),
+        };
+        self.paths = Some(new_paths);
+        self.store_error(e);
+        self
+    }
+
+    /// Steps from edges to their end nodes while path tracking is on
+    fn edge_ends_tracked(&mut self, from: bool, to: bool) -> &mut Self {
+        let edges = match &self.current_step {
+            TraversalValue::EdgeArray(edges) => edges,
+            _ => return self,
+        };
+        let paths = self.paths.take().unwrap_or_default();
+        let mut e = GraphError::Empty;
+        let mut new_nodes = Vec::with_capacity(edges.len());
+        let mut new_paths = Vec::with_capacity(edges.len());
+        for (edge, path) in edges.iter().zip(paths) {
+            for (wanted, id) in [(from, &edge.from_node), (to, &edge.to_node)] {
+                if !wanted {
+                    continue;
+                }
+                match self.storage.get_node(id) {
+                    Ok(node) => {
+                        let mut path = path.clone();
+                        path.0.push(node.clone());
+                        new_nodes.push(node);
+                        new_paths.push(path);
+                    }
+                    Err(err) => e = err,
+                }
+            }
+        }
+
+        self.current_step = match new_nodes.is_empty() {
+            true => TraversalValue::Empty,
+            false => TraversalValue::NodeArray(new_nodes),
+        };
+        self.paths = Some(new_paths);
+        self.store_error(e);
+        self
+    }
+
+    /// Keeps the traversers whose path visits a node twice if `cyclic`, or every other one if not
+    fn filter_paths(&mut self, cyclic: bool, function_name: &str) -> &mut Self {
+        let keep: Vec<bool> = match &self.paths {
+            Some(paths) => paths
+                .iter()
+                .map(|(nodes, _, _)| {
+                    let mut seen = HashSet::with_capacity(nodes.len());
+                    nodes.iter().any(|node| !seen.insert(&node.id)) == cyclic
+                })
+                .collect(),
+            None => {
+                self.store_error(GraphError::TraversalError(format!(
+                    "{} needs path tracking to be turned on with track_paths",
+                    function_name
+                )));
+                return self;
+            }
+        };
+        self.retain_traversers(&keep);
+        self
+    }
+
+    /// Runs `traversal` from a single node or edge and returns what it ends on
+    fn run_anonymous(
+        &self,
+        start: TraversalValue,
+        traversal: &AnonymousTraversal<'_>,
+    ) -> Result<TraversalValue, GraphError> {
+        let mut sub = TraversalBuilder::new(Arc::clone(&self.storage), start);
+        traversal(&mut sub);
+        match sub.error.take() {
+            Some(err) => Err(err),
+            None => Ok(sub.current_step),
+        }
+    }
+
+    /// Calls `branch` with each current node or edge on its own and replaces the current step
+    /// with everything the calls output, in order
+    fn branch<F>(&mut self, function_name: &str, branch: F) -> &mut Self
+    where
+        F: Fn(&Self, TraversalValue) -> Result<Vec<TraversalValue>, GraphError>,
+    {
+        let traversers: Vec<TraversalValue> = match &self.current_step {
+            TraversalValue::NodeArray(nodes) => nodes.iter().map(TraversalValue::from).collect(),
+            TraversalValue::EdgeArray(edges) => edges.iter().map(TraversalValue::from).collect(),
+            TraversalValue::Empty => return self,
+            _ => {
+                self.store_error(GraphError::TraversalError(format!(
+                    "Invalid traversal step for {} {:?}",
+                    function_name, &self.current_step
+                )));
+                return self;
+            }
+        };
+
+        let mut nodes = Vec::new();
+        let mut edges = Vec::new();
+        for traverser in traversers {
+            let outputs = match branch(self, traverser) {
+                Ok(outputs) => outputs,
+                Err(err) => {
+                    self.store_error(err);
+                    return self;
+                }
+            };
+            for output in outputs {
+                match output {
+                    TraversalValue::NodeArray(n) => nodes.extend(n),
+                    TraversalValue::EdgeArray(e) => edges.extend(e),
+                    TraversalValue::Empty => {}
+                    other => {
+                        self.store_error(GraphError::TraversalError(format!(
+                            "Branches of {} must end on nodes or edges, got {:?}",
+                            function_name, other
+                        )));
+                        return self;
+                    }
+                }
+            }
+        }
+        if !nodes.is_empty() && !edges.is_empty() {
+            self.store_error(GraphError::TraversalError(format!(
+                "Branches of {} must all end on nodes or all end on edges",
+                function_name
+            )));
+            return self;
+        }
+
+        self.current_step = match (nodes.is_empty(), edges.is_empty()) {
+            (false, _) => TraversalValue::NodeArray(nodes),
+            (true, false) => TraversalValue::EdgeArray(edges),
+            (true, true) => TraversalValue::Empty,
+        };
+        self.paths = None;
+        self
+    }
+
+    /// Applies `aggregate` to the values of `property` on the current nodes or edges,
+    /// or on those of each group if the current step is grouped
+    fn aggregate<F>(&mut self, property: &str, function_name: &str, aggregate: F) -> &mut Self
+    where
+        F: Fn(Vec<Value>) -> Result<Value, GraphError>,
+    {
+        let result = match &self.current_step {
+            TraversalValue::Groups(groups) => groups
+                .iter()
+                .map(|(key, group)| match present_values(group, property) {
+                    Some(values) => Ok((key.clone(), TraversalValue::Value(aggregate(values)?))),
+                    None => Err(GraphError::TraversalError(format!(
+                        "Invalid group for {} {:?}",
+                        function_name, group
+                    ))),
+                })
+                .collect::<Result<Vec<_>, GraphError>>()
+                .map(TraversalValue::Groups),
+            step => match present_values(step, property) {
+                Some(values) => aggregate(values).map(TraversalValue::Value),
+                None => Err(GraphError::TraversalError(format!(
+                    "Invalid traversal step for {} {:?}",
+                    function_name, step
+                ))),
+            },
+        };
+        match result {
+            Ok(value) => {
+                self.current_step = value;
+                self.paths = None;
+            }
+            Err(err) => self.store_error(err),
+        }
+        self
+    }
+
+    /// Keeps the current nodes or edges for which `keep` is true, along with their paths
+    fn retain_traversers(&mut self, keep: &[bool]) {
+        let mut kept = keep.iter();
+        match &mut self.current_step {
+            TraversalValue::NodeArray(nodes) => nodes.retain(|_| *kept.next().unwrap_or(&false)),
+            TraversalValue::EdgeArray(edges) => edges.retain(|_| *kept.next().unwrap_or(&false)),
+            _ => {}
+        }
+        self.retain_paths(keep);
+    }
+
+    /// The value of `property` on each current node or edge, `Value::Empty` where it is not set
+    fn property_values(&mut self, property: &str, function_name: &str) -> Option<Vec<Value>> {
+        let value = |v: Option<&Value>| v.cloned().unwrap_or(Value::Empty);
+        match &self.current_step {
+            TraversalValue::NodeArray(nodes) => Some(
+                nodes
+                    .iter()
+                    .map(|n| value(n.check_property(property)))
+                    .collect(),
+            ),
+            TraversalValue::EdgeArray(edges) => Some(
+                edges
+                    .iter()
+                    .map(|e| value(e.check_property(property)))
+                    .collect(),
+            ),
+            TraversalValue::Empty => None,
+            _ => {
+                self.store_error(GraphError::TraversalError(format!(
+                    "Invalid traversal step for {} {:?}",
+                    function_name, &self.current_step
+                )));
+                None
+            }
+        }
+    }
+}
+
+/// Values of `property` on the nodes or edges of `step` that have it
+fn present_values(step: &TraversalValue, property: &str) -> Option<Vec<Value>> {
+    match step {
+        TraversalValue::NodeArray(nodes) => Some(
+            nodes
+                .iter()
+                .filter_map(|n| n.check_property(property).cloned())
+                .collect(),
+        ),
+        TraversalValue::EdgeArray(edges) => Some(
+            edges
+                .iter()
+                .filter_map(|e| e.check_property(property).cloned())
+                .collect(),
+        ),
+        TraversalValue::Empty => Some(Vec::new()),
+        _ => None,
+    }
+}
+
+/// Sums integers exactly as an `I64`, falling back to a `Float` if any value is a float or the sum overflows
+fn sum_values(values: Vec<Value>) -> Result<Value, GraphError> {
+    let mut int_sum = Some(0i64);
+    let mut float_sum = 0.0;
+    for value in &values {
+        let float = value.as_f64().ok_or_else(|| {
+            GraphError::TraversalError(format!("Cannot sum non-numeric value {:?}", value))
+        })?;
+        float_sum += float;
+        int_sum = match (value, int_sum) {
+            (Value::Float(_), _) | (_, None) => None,
+            (value, Some(sum)) => value.as_i64().and_then(|i| sum.checked_add(i)),
+        };
+    }
+    Ok(match int_sum {
+        Some(sum) => Value::I64(sum),
+        None => Value::Float(float_sum),
+    })
+}
+
+fn mean_values(values: Vec<Value>) -> Result<Value, GraphError> {
+    if values.is_empty() {
+        return Ok(Value::Empty);
+    }
+    let count = values.len() as f64;
+    let sum = sum_values(values)?;
+    Ok(Value::Float(sum.as_f64().unwrap_or_default() / count))
+}
+
+/// Direction of `order_by`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Order {
+    #[default]
+    Asc,
+    Desc,
+}
+
+/// Moves the item at `order[i]` to position `i`
+fn reorder<T>(items: Vec<T>, order: &[usize]) -> Vec<T> {
+    let mut items: Vec<Option<T>> = items.into_iter().map(Some).collect();
+    order.iter().filter_map(|&i| items[i].take()).collect()
+}
+
+/// Steps run from a single node or edge by the branching steps, e.g. `Box::new(|t| { t.out("follows"); })`
+pub type AnonymousTraversal<'a> = Box<dyn Fn(&mut TraversalBuilder) + 'a>;
+
+/// How `project` computes a field of the row of each node or edge
+pub enum Projection<'a> {
+    Id,
+    Label,
+    /// Value of a property, `Value::Empty` if it is not set
+    Property(String),
+    /// Number of nodes or edges an anonymous traversal from the node or edge outputs
+    Count(AnonymousTraversal<'a>),
+    /// Whatever an anonymous traversal from the node or edge outputs, which may itself be rows
+    Traversal(AnonymousTraversal<'a>),
+}
+
+/// Number of items in the output of an anonymous traversal
+fn output_count(value: &TraversalValue) -> usize {
+    match value {
+        TraversalValue::Empty => 0,
+        TraversalValue::Count(count) => count.value(),
+        TraversalValue::NodeArray(nodes) => nodes.len(),
+        TraversalValue::EdgeArray(edges) => edges.len(),
+        TraversalValue::ValueArray(values) => values.len(),
+        TraversalValue::Paths(paths) => paths.len(),
+        TraversalValue::Groups(groups) => groups.len(),
+        TraversalValue::Rows(rows) => rows.len(),
+        TraversalValue::Value(_) => 1,
+    }
+}
+
+/// Whether the output of an anonymous traversal is empty
+fn is_empty_output(value: &TraversalValue) -> bool {
+    match value {
+        TraversalValue::Empty => true,
+        TraversalValue::NodeArray(nodes) => nodes.is_empty(),
+        TraversalValue::EdgeArray(edges) => edges.is_empty(),
+        _ => false,
+    }
+}
+
+/// Whether the output of a `choose` predicate counts as true:
+/// a true boolean, a non-zero count, a non-null value or any nodes or edges
+fn is_truthy(value: &TraversalValue) -> bool {
+    match value {
+        TraversalValue::Value(Value::Boolean(b)) => *b,
+        TraversalValue::Value(value) => !value.is_null(),
+        TraversalValue::Count(count) => count.value() > 0,
+        value => !is_empty_output(value),
+    }
+}
+
+/// Steps applied over and over by `TraversalMethods::repeat`, along with when to stop and what to output,
+/// e.g. `Repeat::new(|t| { t.out("follows"); }).times(3).emit()` for everyone within 3 hops.
+pub struct Repeat<'a> {
+    steps: AnonymousTraversal<'a>,
+    times: Option<usize>,
+    until: Option<Box<dyn Fn(&Node) -> Result<bool, GraphError> + 'a>>,
+    emit_from: Option<usize>,
+}
+
+impl<'a> Repeat<'a> {
+    /// Repeats `steps`, which must go from nodes to nodes, until no new nodes are reached
+    pub fn new<F>(steps: F) -> Self
+    where
+        F: Fn(&mut TraversalBuilder) + 'a,
+    {
+        Repeat {
+            steps: Box::new(steps),
+            times: None,
+            until: None,
+            emit_from: None,
+        }
+    }
+
+    /// Stops after `times` iterations
+    pub fn times(mut self, times: usize) -> Self {
+        self.times = Some(times);
+        self
+    }
+
+    /// Stops following a traverser once it reaches a node for which `predicate` returns true,
+    /// keeping that node in the result
+    pub fn until<F>(mut self, predicate: F) -> Self
+    where
+        F: Fn(&Node) -> Result<bool, GraphError> + 'a,
+    {
+        self.until = Some(Box::new(predicate));
+        self
+    }
+
+    /// Outputs the nodes reached by every iteration rather than only the last one
+    pub fn emit(self) -> Self {
+        self.emit_from(1)
+    }
+
+    /// Outputs the nodes reached by every iteration from the `depth`th one onwards
+    pub fn emit_from(mut self, depth: usize) -> Self {
+        self.emit_from = Some(depth);
+        self
+    }
 }
 
 impl SourceTraversalSteps for TraversalBuilder {
@@ -81,6 +608,20 @@ impl SourceTraversalSteps for TraversalBuilder {
                 self.store_error(err);
             }
         }
+        self.restart_paths();
+        self
+    }
+
+    fn v_where<F: Fn(NodeRef<'_>) -> bool>(&mut self, f: F) -> &mut Self {
+        match self.storage.filter_all_nodes(f) {
+            Ok(nodes) => {
+                self.current_step = TraversalValue::NodeArray(nodes);
+            }
+            Err(err) => {
+                self.store_error(err);
+            }
+        }
+        self.restart_paths();
         self
     }
 
@@ -93,6 +634,33 @@ impl SourceTraversalSteps for TraversalBuilder {
                 self.store_error(err);
             }
         }
+        self.restart_paths();
+        self
+    }
+
+    fn v_limit(&mut self, n: usize) -> &mut Self {
+        match self.storage.get_nodes_limit(&[], n) {
+            Ok(nodes) => {
+                self.current_step = TraversalValue::NodeArray(nodes);
+            }
+            Err(err) => {
+                self.store_error(err);
+            }
+        }
+        self.restart_paths();
+        self
+    }
+
+    fn e_limit(&mut self, n: usize) -> &mut Self {
+        match self.storage.get_edges_limit(&[], n) {
+            Ok(edges) => {
+                self.current_step = TraversalValue::EdgeArray(edges);
+            }
+            Err(err) => {
+                self.store_error(err);
+            }
+        }
+        self.restart_paths();
         self
     }
 
@@ -105,6 +673,7 @@ impl SourceTraversalSteps for TraversalBuilder {
                 self.store_error(err);
             }
         }
+        self.restart_paths();
         self
     }
 
@@ -123,6 +692,7 @@ impl SourceTraversalSteps for TraversalBuilder {
                 self.store_error(err);
             }
         }
+        self.restart_paths();
         self
     }
 
@@ -135,6 +705,7 @@ impl SourceTraversalSteps for TraversalBuilder {
                 self.store_error(err);
             }
         }
+        self.restart_paths();
         self
     }
 
@@ -147,17 +718,25 @@ impl SourceTraversalSteps for TraversalBuilder {
                 self.store_error(err);
             }
         }
+        self.restart_paths();
         self
     }
 }
 
 impl TraversalSteps for TraversalBuilder {
     fn out(&mut self, edge_label: &str) -> &mut Self {
+        self.out_where(edge_label, |_| true)
+    }
+
+    fn out_where<F: Fn(NodeRef<'_>) -> bool>(&mut self, edge_label: &str, f: F) -> &mut Self {
+        if self.paths.is_some() {
+            return self.hop_tracked_where(EdgeDirection::Out, edge_label, false, f);
+        }
         let mut e = GraphError::Empty;
         if let TraversalValue::NodeArray(nodes) = &self.current_step {
             let mut new_current = Vec::with_capacity(nodes.len());
             for node in nodes {
-                match self.storage.get_out_nodes(&node.id, edge_label) {
+                match self.storage.filter_out_nodes(&node.id, edge_label, &f) {
                     Ok(nodes) => match nodes.is_empty() {
                         false => new_current.extend(nodes),
                         true => continue,
@@ -176,6 +755,9 @@ impl TraversalSteps for TraversalBuilder {
     }
 
     fn out_e(&mut self, edge_label: &str) -> &mut Self {
+        if self.paths.is_some() {
+            return self.hop_tracked(EdgeDirection::Out, edge_label, true);
+        }
         let mut e = GraphError::Empty;
         if let TraversalValue::NodeArray(nodes) = &self.current_step {
             let mut new_current = Vec::with_capacity(nodes.len());
@@ -199,11 +781,18 @@ impl TraversalSteps for TraversalBuilder {
     }
 
     fn in_(&mut self, edge_label: &str) -> &mut Self {
+        self.in_where(edge_label, |_| true)
+    }
+
+    fn in_where<F: Fn(NodeRef<'_>) -> bool>(&mut self, edge_label: &str, f: F) -> &mut Self {
+        if self.paths.is_some() {
+            return self.hop_tracked_where(EdgeDirection::In, edge_label, false, f);
+        }
         let mut e = GraphError::Empty;
         if let TraversalValue::NodeArray(nodes) = &self.current_step {
             let mut new_current = Vec::with_capacity(nodes.len());
             for node in nodes {
-                match self.storage.get_in_nodes(&node.id, edge_label) {
+                match self.storage.filter_in_nodes(&node.id, edge_label, &f) {
                     Ok(nodes) => match nodes.is_empty() {
                         false => new_current.extend(nodes),
                         true => continue,
@@ -222,6 +811,9 @@ impl TraversalSteps for TraversalBuilder {
     }
 
     fn in_e(&mut self, edge_label: &str) -> &mut Self {
+        if self.paths.is_some() {
+            return self.hop_tracked(EdgeDirection::In, edge_label, true);
+        }
         let mut e = GraphError::Empty;
         if let TraversalValue::NodeArray(nodes) = &self.current_step {
             let mut new_current = Vec::with_capacity(nodes.len());
@@ -245,6 +837,9 @@ impl TraversalSteps for TraversalBuilder {
     }
 
     fn both_e(&mut self, edge_label: &str) -> &mut Self {
+        if self.paths.is_some() {
+            return self.hop_tracked(EdgeDirection::Both, edge_label, true);
+        }
         let mut e = GraphError::Empty;
         if let TraversalValue::NodeArray(nodes) = &self.current_step {
             let mut new_current = Vec::with_capacity(nodes.len());
@@ -277,6 +872,9 @@ impl TraversalSteps for TraversalBuilder {
     }
 
     fn both(&mut self, edge_label: &str) -> &mut Self {
+        if self.paths.is_some() {
+            return self.hop_tracked(EdgeDirection::Both, edge_label, false);
+        }
         let mut e = GraphError::Empty;
         if let TraversalValue::NodeArray(nodes) = &self.current_step {
             let mut new_current = Vec::with_capacity(nodes.len());
@@ -309,6 +907,9 @@ impl TraversalSteps for TraversalBuilder {
     }
 
     fn out_v(&mut self) -> &mut Self {
+        if self.paths.is_some() {
+            return self.edge_ends_tracked(true, false);
+        }
         let mut e = GraphError::Empty;
         if let TraversalValue::EdgeArray(edges) = &self.current_step {
             let mut new_current = Vec::with_capacity(edges.len());
@@ -329,6 +930,9 @@ impl TraversalSteps for TraversalBuilder {
     }
 
     fn in_v(&mut self) -> &mut Self {
+        if self.paths.is_some() {
+            return self.edge_ends_tracked(false, true);
+        }
         let mut e = GraphError::Empty;
         if let TraversalValue::EdgeArray(edges) = &self.current_step {
             let mut new_current = Vec::with_capacity(edges.len());
@@ -349,6 +953,9 @@ impl TraversalSteps for TraversalBuilder {
     }
 
     fn both_v(&mut self) -> &mut Self {
+        if self.paths.is_some() {
+            return self.edge_ends_tracked(true, true);
+        }
         let mut e = GraphError::Empty;
         if let TraversalValue::EdgeArray(edges) = &self.current_step {
             let mut new_current = Vec::with_capacity(edges.len() * 2);
@@ -374,6 +981,7 @@ impl TraversalSteps for TraversalBuilder {
 
     fn mutual(&mut self, edge_label: &str) -> &mut Self {
         let mut e: GraphError = GraphError::Empty;
+        self.paths = None;
 
         if let TraversalValue::NodeArray(nodes) = &self.current_step {
             let result: Vec<Node> = nodes
@@ -417,6 +1025,7 @@ impl TraversalSteps for TraversalBuilder {
         props: Vec<(String, Value)>,
     ) -> &mut Self {
         let mut e = GraphError::Empty;
+        self.paths = None;
         match &self.current_step {
             TraversalValue::NodeArray(nodes) => {
                 let mut new_current = Vec::with_capacity(nodes.len());
@@ -444,6 +1053,7 @@ impl TraversalSteps for TraversalBuilder {
         props: Vec<(String, Value)>,
     ) -> &mut Self {
         let mut e = GraphError::Empty;
+        self.paths = None;
         match &self.current_step {
             TraversalValue::NodeArray(nodes) => {
                 let mut new_current = Vec::with_capacity(nodes.len());
@@ -476,25 +1086,30 @@ impl TraversalMethods for TraversalBuilder {
         self
     }
     fn range(&mut self, start: usize, end: usize) -> &mut Self {
+        // bounds past the end are clamped the same way for the paths and the nodes or edges,
+        // so they stay in step
+        let bounds = |len: usize| {
+            let end = end.min(len);
+            start.min(end)..end
+        };
+        if let Some(paths) = &mut self.paths {
+            *paths = paths[bounds(paths.len())].to_vec();
+        }
         match &self.current_step {
             TraversalValue::NodeArray(nodes) => {
                 if nodes.len() == 0 {
                     self.current_step = TraversalValue::Empty;
-                } else if nodes.len() < end {
-                    self.current_step =
-                        TraversalValue::NodeArray(nodes[start..nodes.len() - 1].to_vec());
                 } else {
-                    self.current_step = TraversalValue::NodeArray(nodes[start..end].to_vec());
+                    self.current_step =
+                        TraversalValue::NodeArray(nodes[bounds(nodes.len())].to_vec());
                 }
             }
             TraversalValue::EdgeArray(edges) => {
                 if edges.len() == 0 {
                     self.current_step = TraversalValue::Empty;
-                } else if edges.len() < end {
-                    self.current_step =
-                        TraversalValue::EdgeArray(edges[start..edges.len() - 1].to_vec());
                 } else {
-                    self.current_step = TraversalValue::EdgeArray(edges[start..end].to_vec());
+                    self.current_step =
+                        TraversalValue::EdgeArray(edges[bounds(edges.len())].to_vec());
                 }
             }
             TraversalValue::Empty => {}
@@ -503,45 +1118,524 @@ impl TraversalMethods for TraversalBuilder {
         self
     }
 
-    // Then modify the filter function
-    fn filter_nodes<F>(&mut self, predicate: F) -> &mut Self
-    where
-        F: Fn(&Node) -> Result<bool, GraphError>,
-    {
-        if let TraversalValue::NodeArray(nodes) = &mut self.current_step {
-            nodes.retain(|node| predicate(node).unwrap());
-        }
+    fn track_paths(&mut self) -> &mut Self {
+        self.paths = Some(Self::initial_paths(&self.current_step));
         self
     }
 
-    fn filter_edges<F>(&mut self, predicate: F) -> &mut Self
-    where
-        F: Fn(&Edge) -> Result<bool, GraphError>,
-    {
-        if let TraversalValue::EdgeArray(edges) = &mut self.current_step {
-            edges.retain(|edge| predicate(edge).unwrap());
+    fn path(&mut self) -> &mut Self {
+        let count = match &self.current_step {
+            TraversalValue::NodeArray(nodes) => nodes.len(),
+            TraversalValue::EdgeArray(edges) => edges.len(),
+            TraversalValue::Empty => 0,
+            _ => usize::MAX,
+        };
+        match self.paths.take() {
+            Some(paths) if paths.len() == count => {
+                self.current_step = match paths.is_empty() {
+                    true => TraversalValue::Empty,
+                    false => {
+                        unweighted_paths(paths.into_iter().map(|(nodes, edges, _)| (nodes, edges)))
+                    }
+                };
+            }
+            Some(_) => self.store_error(GraphError::TraversalError(format!(
+                "Invalid traversal step for path {:?}",
+                &self.current_step
+            ))),
+            None => self.store_error(GraphError::TraversalError(
+                "path needs path tracking to be turned on with track_paths".to_string(),
+            )),
         }
         self
     }
 
-    fn get_properties(&mut self, keys: &Vec<String>) -> &mut Self {
-        match &mut self.current_step {
+    fn simple_path(&mut self) -> &mut Self {
+        self.filter_paths(false, "simple_path")
+    }
+
+    fn cyclic_path(&mut self) -> &mut Self {
+        self.filter_paths(true, "cyclic_path")
+    }
+
+    fn dedup(&mut self) -> &mut Self {
+        let mut seen = HashSet::new();
+        let keep: Vec<bool> = match &self.current_step {
             TraversalValue::NodeArray(nodes) => {
-                let mut new_props = Vec::with_capacity(nodes.len() * keys.len());
-                for node in nodes {
-                    let vals = keys
-                        .iter()
-                        .map(|key| {
-                            if let Some(value) = node.check_property(key) {
-                                (key.clone(), value.clone())
-                            } else {
-                                (key.clone(), Value::Empty)
-                            }
-                        })
-                        .collect::<Vec<_>>();
-                    new_props.extend(vals);
-                }
-                self.current_step = TraversalValue::ValueArray(new_props);
+                nodes.iter().map(|n| seen.insert(n.id.clone())).collect()
+            }
+            TraversalValue::EdgeArray(edges) => {
+                edges.iter().map(|e| seen.insert(e.id.clone())).collect()
+            }
+            TraversalValue::Empty => return self,
+            _ => {
+                self.store_error(GraphError::TraversalError(format!(
+                    "Invalid traversal step for dedup {:?}",
+                    &self.current_step
+                )));
+                return self;
+            }
+        };
+        self.retain_traversers(&keep);
+        self
+    }
+
+    fn dedup_by(&mut self, property: &str) -> &mut Self {
+        if let Some(values) = self.property_values(property, "dedup_by") {
+            let mut seen = BTreeSet::new();
+            let keep: Vec<bool> = values.into_iter().map(|v| seen.insert(v)).collect();
+            self.retain_traversers(&keep);
+        }
+        self
+    }
+
+    fn order_by(&mut self, property: &str, order: Order) -> &mut Self {
+        let values = match self.property_values(property, "order_by") {
+            Some(values) => values,
+            None => return self,
+        };
+        let mut sorted: Vec<usize> = (0..values.len()).collect();
+        // stable, so traversers with equal values keep their order
+        sorted.sort_by(|&a, &b| match order {
+            Order::Asc => values[a].cmp(&values[b]),
+            Order::Desc => values[b].cmp(&values[a]),
+        });
+        self.current_step = match std::mem::replace(&mut self.current_step, TraversalValue::Empty) {
+            TraversalValue::NodeArray(nodes) => TraversalValue::NodeArray(reorder(nodes, &sorted)),
+            TraversalValue::EdgeArray(edges) => TraversalValue::EdgeArray(reorder(edges, &sorted)),
+            other => other,
+        };
+        if let Some(paths) = self.paths.take() {
+            self.paths = Some(reorder(paths, &sorted));
+        }
+        self
+    }
+
+    fn limit(&mut self, n: usize) -> &mut Self {
+        match &mut self.current_step {
+            TraversalValue::NodeArray(nodes) => nodes.truncate(n),
+            TraversalValue::EdgeArray(edges) => edges.truncate(n),
+            TraversalValue::Empty => return self,
+            _ => {
+                self.store_error(GraphError::TraversalError(format!(
+                    "Invalid traversal step for limit {:?}",
+                    &self.current_step
+                )));
+                return self;
+            }
+        }
+        if let Some(paths) = &mut self.paths {
+            paths.truncate(n);
+        }
+        if n == 0 {
+            self.current_step = TraversalValue::Empty;
+        }
+        self
+    }
+
+    fn group_by(&mut self, key: &str) -> &mut Self {
+        let keys = match self.property_values(key, "group_by") {
+            Some(keys) => keys,
+            None => return self,
+        };
+        self.current_step = match std::mem::replace(&mut self.current_step, TraversalValue::Empty) {
+            TraversalValue::NodeArray(nodes) => {
+                let mut groups: BTreeMap<Value, Vec<Node>> = BTreeMap::new();
+                for (key, node) in keys.into_iter().zip(nodes) {
+                    groups.entry(key).or_default().push(node);
+                }
+                TraversalValue::Groups(
+                    groups
+                        .into_iter()
+                        .map(|(k, g)| (k, TraversalValue::NodeArray(g)))
+                        .collect(),
+                )
+            }
+            TraversalValue::EdgeArray(edges) => {
+                let mut groups: BTreeMap<Value, Vec<Edge>> = BTreeMap::new();
+                for (key, edge) in keys.into_iter().zip(edges) {
+                    groups.entry(key).or_default().push(edge);
+                }
+                TraversalValue::Groups(
+                    groups
+                        .into_iter()
+                        .map(|(k, g)| (k, TraversalValue::EdgeArray(g)))
+                        .collect(),
+                )
+            }
+            other => other,
+        };
+        self.paths = None;
+        self
+    }
+
+    fn group_count(&mut self, key: &str) -> &mut Self {
+        if let Some(keys) = self.property_values(key, "group_count") {
+            let mut counts: BTreeMap<Value, usize> = BTreeMap::new();
+            for key in keys {
+                *counts.entry(key).or_default() += 1;
+            }
+            self.current_step = TraversalValue::Groups(
+                counts
+                    .into_iter()
+                    .map(|(key, count)| (key, TraversalValue::Count(Count::new(count))))
+                    .collect(),
+            );
+            self.paths = None;
+        }
+        self
+    }
+
+    fn sum(&mut self, property: &str) -> &mut Self {
+        self.aggregate(property, "sum", sum_values)
+    }
+
+    fn mean(&mut self, property: &str) -> &mut Self {
+        self.aggregate(property, "mean", mean_values)
+    }
+
+    fn min(&mut self, property: &str) -> &mut Self {
+        self.aggregate(property, "min", |values| {
+            Ok(values.into_iter().min().unwrap_or(Value::Empty))
+        })
+    }
+
+    fn max(&mut self, property: &str) -> &mut Self {
+        self.aggregate(property, "max", |values| {
+            Ok(values.into_iter().max().unwrap_or(Value::Empty))
+        })
+    }
+
+    fn collect_set(&mut self, property: &str) -> &mut Self {
+        self.aggregate(property, "collect_set", |values| {
+            let set: BTreeSet<Value> = values.into_iter().collect();
+            Ok(Value::Array(set.into_iter().collect()))
+        })
+    }
+
+    fn union(&mut self, traversals: Vec<AnonymousTraversal<'_>>) -> &mut Self {
+        self.branch("union", |t, traverser| {
+            traversals
+                .iter()
+                .map(|traversal| t.run_anonymous(traverser.clone(), traversal))
+                .collect()
+        })
+    }
+
+    fn coalesce(&mut self, traversals: Vec<AnonymousTraversal<'_>>) -> &mut Self {
+        self.branch("coalesce", |t, traverser| {
+            for traversal in &traversals {
+                let output = t.run_anonymous(traverser.clone(), traversal)?;
+                if !is_empty_output(&output) {
+                    return Ok(vec![output]);
+                }
+            }
+            Ok(vec![])
+        })
+    }
+
+    fn choose(
+        &mut self,
+        predicate: AnonymousTraversal<'_>,
+        then: AnonymousTraversal<'_>,
+        otherwise: Option<AnonymousTraversal<'_>>,
+    ) -> &mut Self {
+        self.branch("choose", |t, traverser| {
+            let chosen = is_truthy(&t.run_anonymous(traverser.clone(), &predicate)?);
+            Ok(vec![match (chosen, &otherwise) {
+                (true, _) => t.run_anonymous(traverser, &then)?,
+                (false, Some(otherwise)) => t.run_anonymous(traverser, otherwise)?,
+                (false, None) => traverser,
+            }])
+        })
+    }
+
+    fn optional(&mut self, traversal: AnonymousTraversal<'_>) -> &mut Self {
+        self.branch("optional", |t, traverser| {
+            let output = t.run_anonymous(traverser.clone(), &traversal)?;
+            Ok(vec![match is_empty_output(&output) {
+                true => traverser,
+                false => output,
+            }])
+        })
+    }
+
+    fn project(&mut self, fields: Vec<(&str, Projection<'_>)>) -> &mut Self {
+        // each traverser along with its id, label and properties
+        let traversers: Vec<(TraversalValue, &str, &str, &HashMap<String, Value>)> =
+            match &self.current_step {
+                TraversalValue::NodeArray(nodes) => nodes
+                    .iter()
+                    .map(|n| (TraversalValue::from(n), &*n.id, &*n.label, &n.properties))
+                    .collect(),
+                TraversalValue::EdgeArray(edges) => edges
+                    .iter()
+                    .map(|e| (TraversalValue::from(e), &*e.id, &*e.label, &e.properties))
+                    .collect(),
+                TraversalValue::Empty => return self,
+                _ => {
+                    self.store_error(GraphError::TraversalError(format!(
+                        "Invalid traversal step for project {:?}",
+                        &self.current_step
+                    )));
+                    return self;
+                }
+            };
+
+        let mut rows = Vec::with_capacity(traversers.len());
+        for (traverser, id, label, properties) in traversers {
+            let mut row = Vec::with_capacity(fields.len());
+            for (name, projection) in &fields {
+                let value = match projection {
+                    Projection::Id => Ok(TraversalValue::Value(Value::from(id))),
+                    Projection::Label => Ok(TraversalValue::Value(Value::from(label))),
+                    Projection::Property(property) => Ok(TraversalValue::Value(
+                        properties.get(property).cloned().unwrap_or(Value::Empty),
+                    )),
+                    Projection::Count(traversal) => self
+                        .run_anonymous(traverser.clone(), traversal)
+                        .map(|output| TraversalValue::Count(Count::new(output_count(&output)))),
+                    Projection::Traversal(traversal) => {
+                        self.run_anonymous(traverser.clone(), traversal)
+                    }
+                };
+                match value {
+                    Ok(value) => row.push((name.to_string(), value)),
+                    Err(err) => {
+                        self.store_error(err);
+                        return self;
+                    }
+                }
+            }
+            rows.push(Row(row));
+        }
+
+        self.current_step = TraversalValue::Rows(rows);
+        self.paths = None;
+        self
+    }
+
+    fn as_(&mut self, name: &str) -> &mut Self {
+        if self.paths.is_none() {
+            self.track_paths();
+        }
+        let tagged: Vec<TraversalValue> = match &self.current_step {
+            TraversalValue::NodeArray(nodes) => nodes.iter().map(TraversalValue::from).collect(),
+            TraversalValue::EdgeArray(edges) => edges.iter().map(TraversalValue::from).collect(),
+            TraversalValue::Empty => return self,
+            _ => {
+                self.store_error(GraphError::TraversalError(format!(
+                    "Invalid traversal step for as_ {:?}",
+                    &self.current_step
+                )));
+                return self;
+            }
+        };
+        // each traverser keeps what it is at now with its own path,
+        // so traversers that took different numbers of steps can be tagged together
+        if let Some(paths) = &mut self.paths {
+            for ((_, _, tags), value) in paths.iter_mut().zip(tagged) {
+                tags.retain(|(tag, _)| tag != name);
+                tags.push((name.to_string(), value));
+            }
+        }
+        self
+    }
+
+    fn select(&mut self, names: &[String]) -> &mut Self {
+        let count = match &self.current_step {
+            TraversalValue::NodeArray(nodes) => nodes.len(),
+            TraversalValue::EdgeArray(edges) => edges.len(),
+            TraversalValue::Empty => return self,
+            _ => usize::MAX,
+        };
+        let paths = match self.paths.take() {
+            Some(paths) if paths.len() == count => paths,
+            _ => {
+                self.store_error(GraphError::TraversalError(format!(
+                    "Invalid traversal step for select {:?}",
+                    &self.current_step
+                )));
+                return self;
+            }
+        };
+
+        let mut rows = Vec::with_capacity(paths.len());
+        for (_, _, tags) in paths {
+            let mut row = Vec::with_capacity(names.len());
+            for name in names {
+                match tags.iter().find(|(tag, _)| tag == name) {
+                    Some((_, value)) => row.push((name.clone(), value.clone())),
+                    None => {
+                        self.store_error(GraphError::TraversalError(format!(
+                            "No step tagged {} to select",
+                            name
+                        )));
+                        return self;
+                    }
+                }
+            }
+            rows.push(Row(row));
+        }
+        self.current_step = match rows.is_empty() {
+            true => TraversalValue::Empty,
+            false => TraversalValue::Rows(rows),
+        };
+        self
+    }
+
+    fn repeat(&mut self, repeat: Repeat<'_>) -> &mut Self {
+        let start = match &self.current_step {
+            TraversalValue::NodeArray(nodes) => nodes.clone(),
+            TraversalValue::Empty => return self,
+            _ => {
+                self.store_error(GraphError::TraversalError(format!(
+                    "Invalid traversal step for repeat {:?}",
+                    &self.current_step
+                )));
+                return self;
+            }
+        };
+
+        let mut visited: HashSet<String> = start.iter().map(|n| n.id.clone()).collect();
+        let mut frontier = start;
+        let mut result = Vec::new();
+        // paths of the frontier and of the result while path tracking is on
+        let mut frontier_paths = self.paths.take();
+        let mut result_paths = frontier_paths.as_ref().map(|_| Vec::new());
+        let mut depth = 0;
+        while !frontier.is_empty() && repeat.times.is_none_or(|times| depth < times) {
+            depth += 1;
+            let mut step = TraversalBuilder::new(
+                Arc::clone(&self.storage),
+                TraversalValue::NodeArray(frontier),
+            );
+            step.paths = frontier_paths.take();
+            (repeat.steps)(&mut step);
+            if let Some(err) = step.error.take() {
+                self.store_error(err);
+                return self;
+            }
+            let mut reached_paths = match step.paths.take() {
+                Some(paths) => Some(paths.into_iter()),
+                None => {
+                    result_paths = None;
+                    None
+                }
+            };
+            let reached = match step.current_step {
+                TraversalValue::NodeArray(nodes) => nodes,
+                TraversalValue::Empty => Vec::new(),
+                other => {
+                    self.store_error(GraphError::TraversalError(format!(
+                        "Repeated steps must end on nodes, got {:?}",
+                        other
+                    )));
+                    return self;
+                }
+            };
+
+            let emit = repeat.emit_from.is_some_and(|from| depth >= from);
+            frontier = Vec::with_capacity(reached.len());
+            frontier_paths = result_paths
+                .as_ref()
+                .map(|_| Vec::with_capacity(reached.len()));
+            for node in reached {
+                let path = reached_paths.as_mut().and_then(|paths| paths.next());
+                // each node is only reached once, which also keeps cycles from being followed
+                if !visited.insert(node.id.clone()) {
+                    continue;
+                }
+                let done = match &repeat.until {
+                    Some(until) => match until(&node) {
+                        Ok(done) => done,
+                        Err(err) => {
+                            self.store_error(err);
+                            return self;
+                        }
+                    },
+                    None => false,
+                };
+                if done {
+                    result.push(node);
+                    if let Some(result_paths) = &mut result_paths {
+                        result_paths.extend(path);
+                    }
+                    continue;
+                }
+                if emit {
+                    result.push(node.clone());
+                    if let Some(result_paths) = &mut result_paths {
+                        result_paths.extend(path.clone());
+                    }
+                }
+                frontier.push(node);
+                if let Some(frontier_paths) = &mut frontier_paths {
+                    frontier_paths.extend(path);
+                }
+            }
+        }
+        if repeat.emit_from.is_none() {
+            result.extend(frontier);
+            if let (Some(result_paths), Some(frontier_paths)) = (&mut result_paths, frontier_paths)
+            {
+                result_paths.extend(frontier_paths);
+            }
+        }
+        self.paths = result_paths;
+
+        self.current_step = match result.is_empty() {
+            true => TraversalValue::Empty,
+            false => TraversalValue::NodeArray(result),
+        };
+        self
+    }
+
+    // Then modify the filter function
+    fn filter_nodes<F>(&mut self, predicate: F) -> &mut Self
+    where
+        F: Fn(&Node) -> Result<bool, GraphError>,
+    {
+        if let TraversalValue::NodeArray(nodes) = &mut self.current_step {
+            let keep: Vec<bool> = nodes.iter().map(|node| predicate(node).unwrap()).collect();
+            let mut kept = keep.iter();
+            nodes.retain(|_| *kept.next().unwrap());
+            self.retain_paths(&keep);
+        }
+        self
+    }
+
+    fn filter_edges<F>(&mut self, predicate: F) -> &mut Self
+    where
+        F: Fn(&Edge) -> Result<bool, GraphError>,
+    {
+        if let TraversalValue::EdgeArray(edges) = &mut self.current_step {
+            let keep: Vec<bool> = edges.iter().map(|edge| predicate(edge).unwrap()).collect();
+            let mut kept = keep.iter();
+            edges.retain(|_| *kept.next().unwrap());
+            self.retain_paths(&keep);
+        }
+        self
+    }
+
+    fn get_properties(&mut self, keys: &Vec<String>) -> &mut Self {
+        match &mut self.current_step {
+            TraversalValue::NodeArray(nodes) => {
+                let mut new_props = Vec::with_capacity(nodes.len() * keys.len());
+                for node in nodes {
+                    let vals = keys
+                        .iter()
+                        .map(|key| {
+                            if let Some(value) = node.check_property(key) {
+                                (key.clone(), value.clone())
+                            } else {
+                                (key.clone(), Value::Empty)
+                            }
+                        })
+                        .collect::<Vec<_>>();
+                    new_props.extend(vals);
+                }
+                self.current_step = TraversalValue::ValueArray(new_props);
             }
             TraversalValue::EdgeArray(edges) => {
                 let mut new_props = Vec::with_capacity(edges.len() * keys.len());
@@ -626,8 +1720,7 @@ impl TraversalSearchMethods for TraversalBuilder {
         };
         let mut v: Vec<(Vec<Node>, Vec<Edge>)> = Vec::with_capacity(1);
         v.push(paths);
-        let new_current = TraversalValue::Paths(v);
-
+        self.current_step = unweighted_paths(v);
         self
     }
 
@@ -649,7 +1742,7 @@ impl TraversalSearchMethods for TraversalBuilder {
                 Err(e) => self.store_error(e),
             }
         }
-        self.current_step = TraversalValue::Paths(paths);
+        self.current_step = unweighted_paths(paths);
         self
     }
 
@@ -665,74 +1758,411 @@ impl TraversalSearchMethods for TraversalBuilder {
                 Err(e) => self.store_error(e),
             }
         }
-        self.current_step = TraversalValue::Paths(paths);
+        self.current_step = unweighted_paths(paths);
         self
     }
-}
 
-impl TraversalBuilderMethods for TraversalBuilder {
-    fn result(&self) -> &TraversalValue {
-        &self.current_step
+    fn shortest_path_between_with(
+        &mut self,
+        from_id: &str,
+        to_id: &str,
+        options: &PathOptions,
+    ) -> &mut Self {
+        let mut paths = Vec::with_capacity(1);
+        match self.storage.shortest_path_with(from_id, to_id, options) {
+            Ok(path) => paths.push(path),
+            Err(e) => self.store_error(e),
+        }
+        self.current_step = unweighted_paths(paths);
+        self
     }
-}
-
-#[cfg(test)]
-mod tests {
-    use std::sync::Arc;
-
-    use super::{TraversalBuilder, TraversalMethods, TraversalSteps};
-    use crate::{
-        graph_core::traversal_steps::SourceTraversalSteps,
-        props,
-        storage_core::{storage_core::HelixGraphStorage, storage_methods::StorageMethods},
-        types::GraphError,
-    };
-    use protocol::{
-        filterable::Filterable, traversal_value::TraversalValue, value::Value, Edge, Node,
-    };
-    use rayon::vec;
-    use tempfile::TempDir;
 
-    fn setup_test_db() -> (Arc<HelixGraphStorage>, TempDir) {
-        let temp_dir = TempDir::new().unwrap();
-        let db_path = temp_dir.path().to_str().unwrap();
-        let storage = HelixGraphStorage::new(db_path).unwrap();
-        (Arc::new(storage), temp_dir)
+    fn shortest_path_to_with(&mut self, to_id: &str, options: &PathOptions) -> &mut Self {
+        let nodes = match &self.current_step {
+            TraversalValue::NodeArray(nodes) => nodes.clone(),
+            _ => {
+                self.store_error(GraphError::TraversalError(format!(
+                    "Invalid traversal step for shortest_path_to_with {:?}",
+                    &self.current_step
+                )));
+                return self;
+            }
+        };
+        let mut paths = Vec::with_capacity(nodes.len());
+        for node in nodes {
+            match self.storage.shortest_path_with(&node.id, to_id, options) {
+                Ok(path) => paths.push(path),
+                Err(e) => self.store_error(e),
+            }
+        }
+        self.current_step = unweighted_paths(paths);
+        self
     }
 
-    #[test]
-    fn test_v() {
-        let (storage, _temp_dir) = setup_test_db();
-
-        let person1 = storage.create_node("person", props!()).unwrap();
-        let person2 = storage.create_node("person", props!()).unwrap();
-        let thing = storage.create_node("thing", props!()).unwrap();
-
-        let mut traversal = TraversalBuilder::new(storage, TraversalValue::Empty);
-        traversal.v();
-        // Check that the node array contains all nodes
-        match &traversal.current_step {
-            TraversalValue::NodeArray(nodes) => {
-                assert_eq!(nodes.len(), 3);
-
-                let node_ids: Vec<String> = nodes.iter().map(|n| n.id.clone()).collect();
-                let node_labels: Vec<String> = nodes.iter().map(|n| n.label.clone()).collect();
-
-                assert!(node_ids.contains(&person1.id));
-                assert!(node_ids.contains(&person2.id));
-                assert!(node_ids.contains(&thing.id));
+    fn all_shortest_paths_between(&mut self, from_id: &str, to_id: &str) -> &mut Self {
+        let paths = match self.storage.all_shortest_paths(from_id, to_id) {
+            Ok(paths) => paths,
+            Err(e) => {
+                self.store_error(e);
+                Vec::new()
+            }
+        };
+        self.current_step = unweighted_paths(paths);
+        self
+    }
 
-                assert_eq!(node_labels.iter().filter(|&l| l == "person").count(), 2);
-                assert_eq!(node_labels.iter().filter(|&l| l == "thing").count(), 1);
+    fn all_shortest_paths_to(&mut self, to_id: &str) -> &mut Self {
+        let nodes = match &self.current_step {
+            TraversalValue::NodeArray(nodes) => nodes.clone(),
+            _ => {
+                self.store_error(GraphError::TraversalError(format!(
+                    "Invalid traversal step for all_shortest_paths_to {:?}",
+                    &self.current_step
+                )));
+                return self;
+            }
+        };
+        let mut paths = Vec::with_capacity(nodes.len());
+        for node in nodes {
+            match self.storage.all_shortest_paths(&node.id, to_id) {
+                Ok(node_paths) => paths.extend(node_paths),
+                Err(e) => self.store_error(e),
             }
-            _ => panic!("Expected NodeArray value {:?}", &traversal.current_step),
         }
+        self.current_step = unweighted_paths(paths);
+        self
     }
 
-    #[test]
-    fn test_e() {
-        let (storage, _temp_dir) = setup_test_db();
-
+    fn k_shortest_paths_between(&mut self, from_id: &str, to_id: &str, k: usize) -> &mut Self {
+        let paths = match self.storage.k_shortest_paths(from_id, to_id, k) {
+            Ok(paths) => paths,
+            Err(e) => {
+                self.store_error(e);
+                Vec::new()
+            }
+        };
+        self.current_step = unweighted_paths(paths);
+        self
+    }
+
+    fn k_shortest_paths_to(&mut self, to_id: &str, k: usize) -> &mut Self {
+        let nodes = match &self.current_step {
+            TraversalValue::NodeArray(nodes) => nodes.clone(),
+            _ => {
+                self.store_error(GraphError::TraversalError(format!(
+                    "Invalid traversal step for k_shortest_paths_to {:?}",
+                    &self.current_step
+                )));
+                return self;
+            }
+        };
+        let mut paths = Vec::with_capacity(nodes.len() * k);
+        for node in nodes {
+            match self.storage.k_shortest_paths(&node.id, to_id, k) {
+                Ok(node_paths) => paths.extend(node_paths),
+                Err(e) => self.store_error(e),
+            }
+        }
+        self.current_step = unweighted_paths(paths);
+        self
+    }
+
+    fn weighted_shortest_path_between(
+        &mut self,
+        from_id: &str,
+        to_id: &str,
+        weight_property: &str,
+        direction: EdgeDirection,
+        edge_labels: &[String],
+    ) -> &mut Self {
+        let mut paths = Vec::with_capacity(1);
+        match self.storage.dijkstra_shortest_path(
+            from_id,
+            to_id,
+            weight_property,
+            direction,
+            edge_labels,
+        ) {
+            Ok((nodes, edges, cost)) => paths.push((nodes, edges, Some(cost))),
+            Err(e) => self.store_error(e),
+        }
+        self.current_step = TraversalValue::Paths(paths);
+        self
+    }
+
+    fn weighted_shortest_path_to(
+        &mut self,
+        to_id: &str,
+        weight_property: &str,
+        direction: EdgeDirection,
+        edge_labels: &[String],
+    ) -> &mut Self {
+        let nodes = match &self.current_step {
+            TraversalValue::NodeArray(nodes) => nodes.clone(),
+            _ => {
+                self.store_error(GraphError::TraversalError(format!(
+                    "Invalid traversal step for weighted_shortest_path_to {:?}",
+                    &self.current_step
+                )));
+                return self;
+            }
+        };
+        let mut paths = Vec::with_capacity(nodes.len());
+        for node in nodes {
+            match self.storage.dijkstra_shortest_path(
+                &node.id,
+                to_id,
+                weight_property,
+                direction,
+                edge_labels,
+            ) {
+                Ok((nodes, edges, cost)) => paths.push((nodes, edges, Some(cost))),
+                Err(e) => self.store_error(e),
+            }
+        }
+        self.current_step = TraversalValue::Paths(paths);
+        self
+    }
+
+    fn astar_shortest_path_between<H: Fn(NodeRef<'_>) -> f64>(
+        &mut self,
+        from_id: &str,
+        to_id: &str,
+        weight_property: &str,
+        direction: EdgeDirection,
+        edge_labels: &[String],
+        heuristic: H,
+    ) -> &mut Self {
+        let mut paths = Vec::with_capacity(1);
+        match self.storage.astar_shortest_path(
+            from_id,
+            to_id,
+            weight_property,
+            direction,
+            edge_labels,
+            heuristic,
+        ) {
+            Ok((nodes, edges, cost)) => paths.push((nodes, edges, Some(cost))),
+            Err(e) => self.store_error(e),
+        }
+        self.current_step = TraversalValue::Paths(paths);
+        self
+    }
+}
+
+impl TraversalBuilder {
+    fn label_components(
+        &mut self,
+        edge_labels: &[String],
+        find: fn(&GraphProjection) -> Components,
+    ) -> &mut Self {
+        let nodes = match &self.current_step {
+            TraversalValue::NodeArray(nodes) => nodes.clone(),
+            _ => {
+                self.store_error(GraphError::TraversalError(format!(
+                    "Invalid traversal step for components {:?}",
+                    &self.current_step
+                )));
+                return self;
+            }
+        };
+        let node_ids: Vec<String> = nodes.iter().map(|n| n.id.clone()).collect();
+        let graph = match GraphProjection::load_nodes(&self.storage, &node_ids, edge_labels) {
+            Ok(graph) => graph,
+            Err(e) => {
+                self.store_error(e);
+                return self;
+            }
+        };
+        let components = find(&graph);
+        let rows = nodes
+            .into_iter()
+            .map(|node| {
+                // duplicate nodes share the index of their first occurrence
+                let component = components.assignments[graph.index_of(&node.id).unwrap()].1;
+                node_row(
+                    node,
+                    [
+                        ("component", Value::U64(component as u64)),
+                        (
+                            "component_size",
+                            Value::U64(components.sizes[component] as u64),
+                        ),
+                    ],
+                )
+            })
+            .collect();
+        self.current_step = TraversalValue::Rows(rows);
+        self.paths = None;
+        self
+    }
+}
+
+impl TraversalAlgorithmMethods for TraversalBuilder {
+    fn connected_components(&mut self, edge_labels: &[String]) -> &mut Self {
+        self.label_components(edge_labels, components::weakly_connected_components_of)
+    }
+
+    fn strongly_connected_components(&mut self, edge_labels: &[String]) -> &mut Self {
+        self.label_components(edge_labels, components::strongly_connected_components_of)
+    }
+
+    fn centrality(&mut self, measure: CentralityMeasure, config: &CentralityConfig) -> &mut Self {
+        self.paths = None;
+        let nodes = match &self.current_step {
+            TraversalValue::NodeArray(nodes) => nodes,
+            _ => {
+                self.store_error(GraphError::TraversalError(format!(
+                    "Invalid traversal step for centrality {:?}",
+                    &self.current_step
+                )));
+                return self;
+            }
+        };
+        let node_ids: Vec<String> = nodes.iter().map(|n| n.id.clone()).collect();
+        let scores =
+            GraphProjection::load_nodes(&self.storage, &node_ids, &config.filter.edge_labels)
+                .and_then(|graph| {
+                    let scores = centrality::centrality_of(&graph, measure, config)?;
+                    Ok(node_ids
+                        .iter()
+                        .map(|id| scores[graph.index_of(id).unwrap()])
+                        .collect::<Vec<f64>>())
+                });
+        let scores = match scores {
+            Ok(scores) => scores,
+            Err(e) => {
+                self.store_error(e);
+                return self;
+            }
+        };
+
+        let mut scored: Vec<(f64, Node)> = scores.into_iter().zip(nodes.iter().cloned()).collect();
+        scored.sort_by(|a, b| b.0.total_cmp(&a.0));
+        if let Some(k) = config.top_k {
+            scored.truncate(k);
+        }
+        let rows = scored
+            .into_iter()
+            .map(|(score, node)| node_row(node, [("centrality", Value::Float(score))]))
+            .collect();
+        self.current_step = TraversalValue::Rows(rows);
+        self
+    }
+
+    fn similar(&mut self, edge_labels: &[String], metric: SimilarityMetric, k: usize) -> &mut Self {
+        self.paths = None;
+        let sources: Vec<String> = match &self.current_step {
+            TraversalValue::NodeArray(nodes) => nodes.iter().map(|n| n.id.clone()).collect(),
+            _ => {
+                self.store_error(GraphError::TraversalError(format!(
+                    "Invalid traversal step for similar {:?}",
+                    &self.current_step
+                )));
+                return self;
+            }
+        };
+        let config = SimilarityConfig {
+            edge_labels: edge_labels.to_vec(),
+            ..SimilarityConfig::default()
+        };
+
+        let ranked =
+            match similarity::most_similar_to_any(&self.storage, &sources, metric, k, &config) {
+                Ok(ranked) => ranked,
+                Err(e) => {
+                    self.store_error(e);
+                    return self;
+                }
+            };
+        let mut rows = Vec::with_capacity(ranked.len());
+        for (id, score) in ranked {
+            match self.storage.get_node(&id) {
+                Ok(node) => rows.push(node_row(node, [("similarity", Value::Float(score))])),
+                Err(e) => {
+                    self.store_error(e);
+                    return self;
+                }
+            }
+        }
+        self.current_step = TraversalValue::Rows(rows);
+        self
+    }
+}
+
+impl TraversalBuilderMethods for TraversalBuilder {
+    fn result(&self) -> &TraversalValue {
+        &self.current_step
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, sync::Arc};
+
+    use super::{Order, Projection, Repeat, TraversalBuilder, TraversalMethods, TraversalSteps};
+    use crate::{
+        graph_algorithms::{
+            centrality::{CentralityConfig, CentralityMeasure},
+            similarity::SimilarityMetric,
+        },
+        graph_core::traversal_steps::{
+            SourceTraversalSteps, TraversalAlgorithmMethods, TraversalSearchMethods,
+        },
+        props,
+        storage_core::{
+            record::{NodeRef, ValueRef},
+            storage_core::HelixGraphStorage,
+            storage_methods::{EdgeDirection, PathOptions, StorageMethods},
+        },
+        types::GraphError,
+    };
+    use protocol::{
+        filterable::Filterable, traversal_value::TraversalValue, value::Value, Edge, Node,
+    };
+    use rayon::vec;
+    use tempfile::TempDir;
+
+    fn setup_test_db() -> (Arc<HelixGraphStorage>, TempDir) {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().to_str().unwrap();
+        let storage = HelixGraphStorage::new(db_path).unwrap();
+        (Arc::new(storage), temp_dir)
+    }
+
+    #[test]
+    fn test_v() {
+        let (storage, _temp_dir) = setup_test_db();
+
+        let person1 = storage.create_node("person", props!()).unwrap();
+        let person2 = storage.create_node("person", props!()).unwrap();
+        let thing = storage.create_node("thing", props!()).unwrap();
+
+        let mut traversal = TraversalBuilder::new(storage, TraversalValue::Empty);
+        traversal.v();
+        // Check that the node array contains all nodes
+        match &traversal.current_step {
+            TraversalValue::NodeArray(nodes) => {
+                assert_eq!(nodes.len(), 3);
+
+                let node_ids: Vec<String> = nodes.iter().map(|n| n.id.clone()).collect();
+                let node_labels: Vec<String> = nodes.iter().map(|n| n.label.clone()).collect();
+
+                assert!(node_ids.contains(&person1.id));
+                assert!(node_ids.contains(&person2.id));
+                assert!(node_ids.contains(&thing.id));
+
+                assert_eq!(node_labels.iter().filter(|&l| l == "person").count(), 2);
+                assert_eq!(node_labels.iter().filter(|&l| l == "thing").count(), 1);
+            }
+            _ => panic!("Expected NodeArray value {:?}", &traversal.current_step),
+        }
+    }
+
+    #[test]
+    fn test_e() {
+        let (storage, _temp_dir) = setup_test_db();
+
         // Graph Structure:
         // (person1)-[knows]->(person2)
         //         \-[likes]->(person3)
@@ -1207,6 +2637,30 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_range_past_end() {
+        let (storage, _temp_dir) = setup_test_db();
+        for _ in 0..3 {
+            storage.create_node("person", props!()).unwrap();
+        }
+
+        let mut traversal = TraversalBuilder::new(Arc::clone(&storage), TraversalValue::Empty);
+        traversal.v().track_paths().range(1, 10);
+        // the last node is kept and the paths stay in step with the nodes
+        let nodes = match_node_array(&traversal.current_step);
+        let paths = traversal.paths.as_ref().unwrap();
+        assert_eq!(nodes.len(), 2);
+        assert_eq!(paths.len(), 2);
+        for (node, (path_nodes, _, _)) in nodes.iter().zip(paths) {
+            assert_eq!(path_nodes.last().unwrap().id, node.id);
+        }
+
+        let mut traversal = TraversalBuilder::new(storage, TraversalValue::Empty);
+        traversal.v().track_paths().range(5, 2);
+        assert!(match_node_array(&traversal.current_step).is_empty());
+        assert!(traversal.paths.as_ref().unwrap().is_empty());
+    }
+
     #[test]
     fn test_count_empty() {
         let (storage, _temp_dir) = setup_test_db();
@@ -1627,6 +3081,72 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_out_where_and_in_where() {
+        let (storage, _temp_dir) = setup_test_db();
+
+        let alice = storage
+            .create_node("person", props! { "age" => 25 })
+            .unwrap();
+        let bob = storage
+            .create_node("person", props! { "age" => 30 })
+            .unwrap();
+        let carol = storage
+            .create_node("person", props! { "age" => 35 })
+            .unwrap();
+        let dave = storage.create_node("person", props! {}).unwrap();
+        for (from, to) in [
+            (&alice, &bob),
+            (&alice, &carol),
+            (&alice, &dave),
+            (&bob, &carol),
+        ] {
+            storage
+                .create_edge("knows", &from.id, &to.id, props! {})
+                .unwrap();
+        }
+        storage
+            .create_edge("likes", &alice.id, &carol.id, props! {})
+            .unwrap();
+
+        fn older_than_30(node: NodeRef<'_>) -> bool {
+            matches!(node.check_property("age"), Some(ValueRef::Integer(age)) if age > 30)
+        }
+
+        let mut traversal =
+            TraversalBuilder::new(Arc::clone(&storage), TraversalValue::from(alice.clone()));
+        traversal.out_where("knows", older_than_30);
+        match &traversal.current_step {
+            TraversalValue::NodeArray(nodes) => {
+                assert_eq!(nodes.len(), 1);
+                assert_eq!(nodes[0].id, carol.id);
+            }
+            _ => panic!("Expected NodeArray value"),
+        }
+
+        // an empty label follows edges of any label
+        let mut traversal =
+            TraversalBuilder::new(Arc::clone(&storage), TraversalValue::from(alice.clone()));
+        traversal.out_where("", older_than_30);
+        match &traversal.current_step {
+            TraversalValue::NodeArray(nodes) => assert_eq!(nodes.len(), 2),
+            _ => panic!("Expected NodeArray value"),
+        }
+
+        let mut traversal = TraversalBuilder::new(storage, TraversalValue::from(carol));
+        traversal.in_where("knows", |node| node.check_property("age").is_some());
+        match &traversal.current_step {
+            TraversalValue::NodeArray(nodes) => {
+                let mut ids: Vec<&str> = nodes.iter().map(|n| n.id.as_str()).collect();
+                ids.sort();
+                let mut expected = vec![alice.id.as_str(), bob.id.as_str()];
+                expected.sort();
+                assert_eq!(ids, expected);
+            }
+            _ => panic!("Expected NodeArray value"),
+        }
+    }
+
     #[test]
     fn test_in_v() {
         let (storage, _temp_dir) = setup_test_db();
@@ -1764,6 +3284,1044 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_weighted_shortest_path_to() {
+        let (storage, _temp_dir) = setup_test_db();
+
+        let a = storage.create_node("city", props!()).unwrap();
+        let b = storage.create_node("city", props!()).unwrap();
+        let c = storage.create_node("city", props!()).unwrap();
+        storage
+            .create_edge("road", &a.id, &c.id, props! { "distance" => 10 })
+            .unwrap();
+        storage
+            .create_edge("road", &a.id, &b.id, props! { "distance" => 3 })
+            .unwrap();
+        storage
+            .create_edge("road", &b.id, &c.id, props! { "distance" => 4 })
+            .unwrap();
+
+        let mut traversal = TraversalBuilder::new(Arc::clone(&storage), TraversalValue::Empty);
+        traversal.v_from_id(&a.id).weighted_shortest_path_to(
+            &c.id,
+            "distance",
+            EdgeDirection::Out,
+            &[],
+        );
+
+        match &traversal.current_step {
+            TraversalValue::Paths(paths) => {
+                assert_eq!(paths.len(), 1);
+                let (nodes, edges, cost) = &paths[0];
+                assert_eq!(nodes.len(), 3);
+                assert_eq!(nodes[1].id, b.id);
+                assert_eq!(edges.len(), 2);
+                assert_eq!(*cost, Some(7.0));
+            }
+            _ => panic!("Expected Paths value"),
+        }
+
+        // weighted paths are written as [nodes, edges, cost]
+        let json = serde_json::to_value(&traversal.current_step).unwrap();
+        assert_eq!(json[0][2], serde_json::json!(7.0));
+        let read_back: TraversalValue = serde_json::from_value(json).unwrap();
+        assert!(matches!(&read_back, TraversalValue::Paths(paths) if paths[0].2 == Some(7.0)));
+
+        let mut traversal = TraversalBuilder::new(storage, TraversalValue::Empty);
+        traversal.astar_shortest_path_between(
+            &c.id,
+            &a.id,
+            "distance",
+            EdgeDirection::In,
+            &["road".to_string()],
+            |_| 0.0,
+        );
+        match &traversal.current_step {
+            TraversalValue::Paths(paths) => assert_eq!(paths[0].2, Some(7.0)),
+            _ => panic!("Expected Paths value"),
+        }
+        assert!(traversal.error.is_none());
+    }
+
+    #[test]
+    fn test_shortest_path_to_with_options() {
+        let (storage, _temp_dir) = setup_test_db();
+
+        let a = storage.create_node("person", props!()).unwrap();
+        let b = storage.create_node("person", props!()).unwrap();
+        let c = storage.create_node("person", props!()).unwrap();
+        storage
+            .create_edge("knows", &a.id, &b.id, props!())
+            .unwrap();
+        storage
+            .create_edge("knows", &c.id, &b.id, props!())
+            .unwrap();
+        storage
+            .create_edge("blocks", &a.id, &c.id, props!())
+            .unwrap();
+
+        let options = PathOptions {
+            direction: EdgeDirection::Both,
+            edge_labels: vec!["knows".to_string()],
+            max_depth: Some(2),
+        };
+        let mut traversal = TraversalBuilder::new(Arc::clone(&storage), TraversalValue::Empty);
+        traversal
+            .v_from_id(&a.id)
+            .shortest_path_to_with(&c.id, &options);
+
+        match &traversal.current_step {
+            TraversalValue::Paths(paths) => {
+                assert_eq!(paths.len(), 1);
+                let (nodes, edges, cost) = &paths[0];
+                assert!(cost.is_none());
+                assert_eq!(
+                    nodes.iter().map(|n| &n.id).collect::<Vec<_>>(),
+                    vec![&a.id, &b.id, &c.id]
+                );
+                assert!(edges.iter().all(|e| e.label == "knows"));
+            }
+            _ => panic!("Expected Paths value"),
+        }
+        assert!(traversal.error.is_none());
+
+        // unweighted paths are written as [nodes, edges] and read back without a cost
+        let json = serde_json::to_value(&traversal.current_step).unwrap();
+        assert_eq!(json[0].as_array().unwrap().len(), 2);
+        let read_back: TraversalValue = serde_json::from_value(json).unwrap();
+        assert!(matches!(&read_back, TraversalValue::Paths(paths) if paths[0].2.is_none()));
+
+        let options = PathOptions {
+            max_depth: Some(1),
+            ..options
+        };
+        let mut traversal = TraversalBuilder::new(storage, TraversalValue::Empty);
+        traversal.shortest_path_between_with(&a.id, &c.id, &options);
+        assert!(traversal.error.is_some());
+    }
+
+    #[test]
+    fn test_all_and_k_shortest_paths() {
+        let (storage, _temp_dir) = setup_test_db();
+
+        let a = storage.create_node("person", props!()).unwrap();
+        let b = storage.create_node("person", props!()).unwrap();
+        let c = storage.create_node("person", props!()).unwrap();
+        let d = storage.create_node("person", props!()).unwrap();
+        storage
+            .create_edge("knows", &a.id, &b.id, props!())
+            .unwrap();
+        storage
+            .create_edge("knows", &a.id, &c.id, props!())
+            .unwrap();
+        storage
+            .create_edge("knows", &b.id, &d.id, props!())
+            .unwrap();
+        storage
+            .create_edge("knows", &c.id, &d.id, props!())
+            .unwrap();
+        storage
+            .create_edge("knows", &a.id, &d.id, props!())
+            .unwrap();
+
+        let mut traversal = TraversalBuilder::new(Arc::clone(&storage), TraversalValue::Empty);
+        traversal.v_from_id(&a.id).all_shortest_paths_to(&d.id);
+        match &traversal.current_step {
+            TraversalValue::Paths(paths) => {
+                assert_eq!(paths.len(), 1);
+                assert_eq!(paths[0].1.len(), 1);
+            }
+            _ => panic!("Expected Paths value"),
+        }
+
+        let mut traversal = TraversalBuilder::new(Arc::clone(&storage), TraversalValue::Empty);
+        traversal.k_shortest_paths_between(&a.id, &d.id, 3);
+        match &traversal.current_step {
+            TraversalValue::Paths(paths) => {
+                let lengths: Vec<usize> = paths.iter().map(|(_, edges, _)| edges.len()).collect();
+                assert_eq!(lengths, vec![1, 2, 2]);
+            }
+            _ => panic!("Expected Paths value"),
+        }
+        assert!(traversal.error.is_none());
+
+        let mut traversal = TraversalBuilder::new(storage, TraversalValue::Empty);
+        traversal.all_shortest_paths_between(&d.id, &a.id);
+        assert!(traversal.error.is_some());
+    }
+
+    #[test]
+    fn test_components() {
+        let (storage, _temp_dir) = setup_test_db();
+
+        let a = storage
+            .create_node("package", props! { "component" => "core" })
+            .unwrap();
+        let b = storage.create_node("package", props!()).unwrap();
+        let c = storage.create_node("package", props!()).unwrap();
+        let d = storage.create_node("package", props!()).unwrap();
+        storage
+            .create_edge("depends_on", &a.id, &b.id, props!())
+            .unwrap();
+        storage
+            .create_edge("depends_on", &b.id, &a.id, props!())
+            .unwrap();
+        storage
+            .create_edge("depends_on", &c.id, &a.id, props!())
+            .unwrap();
+        storage
+            .create_edge("mentions", &d.id, &c.id, props!())
+            .unwrap();
+
+        let property = |rows: &[(Node, HashMap<String, Value>)], id: &str, name: &str| {
+            rows.iter().find(|(n, _)| n.id == id).unwrap().1[name].clone()
+        };
+
+        let mut traversal = TraversalBuilder::new(Arc::clone(&storage), TraversalValue::Empty);
+        traversal
+            .v()
+            .connected_components(&["depends_on".to_string()]);
+        let nodes = match_node_rows(&traversal.current_step);
+        assert_eq!(nodes.len(), 4);
+        assert_eq!(property(&nodes, &c.id, "component"), Value::U64(0));
+        assert_eq!(property(&nodes, &c.id, "component_size"), Value::U64(3));
+        assert_eq!(property(&nodes, &d.id, "component"), Value::U64(1));
+
+        let mut traversal = TraversalBuilder::new(Arc::clone(&storage), TraversalValue::Empty);
+        traversal.v().strongly_connected_components(&[]);
+        let nodes = match_node_rows(&traversal.current_step);
+        assert_eq!(
+            property(&nodes, &a.id, "component"),
+            property(&nodes, &b.id, "component")
+        );
+        assert_eq!(property(&nodes, &a.id, "component_size"), Value::U64(2));
+        assert_eq!(property(&nodes, &c.id, "component_size"), Value::U64(1));
+        // the node keeps its own property of the same name
+        let (node, _) = nodes.iter().find(|(n, _)| n.id == a.id).unwrap();
+        assert_eq!(node.properties["component"], Value::from("core"));
+
+        // only the current nodes are part of the subgraph
+        let mut traversal = TraversalBuilder::new(storage, TraversalValue::Empty);
+        traversal.v_from_id(&c.id).connected_components(&[]);
+        let nodes = match_node_rows(&traversal.current_step);
+        assert_eq!(property(&nodes, &c.id, "component_size"), Value::U64(1));
+        assert!(traversal.error.is_none());
+    }
+
+    #[test]
+    fn test_centrality() {
+        let (storage, _temp_dir) = setup_test_db();
+
+        // a star around the hub plus a follower of one of the spokes
+        let hub = storage.create_node("user", props!()).unwrap();
+        let spokes: Vec<Node> = (0..3)
+            .map(|_| storage.create_node("user", props!()).unwrap())
+            .collect();
+        for spoke in &spokes {
+            storage
+                .create_edge("follows", &spoke.id, &hub.id, props!())
+                .unwrap();
+        }
+        let follower = storage.create_node("user", props!()).unwrap();
+        storage
+            .create_edge("follows", &follower.id, &spokes[0].id, props!())
+            .unwrap();
+
+        let mut traversal = TraversalBuilder::new(Arc::clone(&storage), TraversalValue::Empty);
+        let config = CentralityConfig {
+            direction: EdgeDirection::In,
+            top_k: Some(2),
+            ..CentralityConfig::default()
+        };
+        traversal.v().centrality(CentralityMeasure::Degree, &config);
+        let nodes = match_node_rows(&traversal.current_step);
+        assert_eq!(nodes.len(), 2);
+        assert_eq!(nodes[0].0.id, hub.id);
+        assert_eq!(nodes[0].1["centrality"], Value::Float(3.0));
+        assert_eq!(nodes[1].0.id, spokes[0].id);
+
+        let mut traversal = TraversalBuilder::new(Arc::clone(&storage), TraversalValue::Empty);
+        traversal
+            .v()
+            .centrality(CentralityMeasure::Betweenness, &CentralityConfig::default());
+        let nodes = match_node_rows(&traversal.current_step);
+        assert_eq!(nodes.len(), 5);
+        assert_eq!(nodes[0].0.id, spokes[0].id);
+        assert_eq!(nodes[0].1["centrality"], Value::Float(1.0));
+
+        let mut traversal = TraversalBuilder::new(storage, TraversalValue::Empty);
+        traversal
+            .v()
+            .count()
+            .centrality(CentralityMeasure::Degree, &CentralityConfig::default());
+        assert!(traversal.error.is_some());
+    }
+
+    #[test]
+    fn test_similar() {
+        let (storage, _temp_dir) = setup_test_db();
+
+        // alice and bob share two friends, carol shares one friend and two liked people with alice
+        let people: Vec<Node> = (0..7)
+            .map(|_| storage.create_node("person", props!()).unwrap())
+            .collect();
+        let (alice, bob, carol) = (&people[0], &people[1], &people[2]);
+        let (friend1, friend2) = (&people[3], &people[4]);
+        for (from, to) in [
+            (alice, friend1),
+            (alice, friend2),
+            (bob, friend1),
+            (friend2, bob),
+            (carol, friend1),
+        ] {
+            storage
+                .create_edge("knows", &from.id, &to.id, props!())
+                .unwrap();
+        }
+        for liked in &people[5..] {
+            storage
+                .create_edge("likes", &alice.id, &liked.id, props!())
+                .unwrap();
+            storage
+                .create_edge("likes", &carol.id, &liked.id, props!())
+                .unwrap();
+        }
+
+        let mut traversal = TraversalBuilder::new(Arc::clone(&storage), TraversalValue::Empty);
+        traversal.v_from_id(&alice.id).similar(
+            &["knows".to_string()],
+            SimilarityMetric::CommonNeighbours,
+            5,
+        );
+        let nodes = match_node_rows(&traversal.current_step);
+        assert_eq!(nodes.len(), 2);
+        assert_eq!(nodes[0].0.id, bob.id);
+        assert_eq!(nodes[0].1["similarity"], Value::Float(2.0));
+        assert_eq!(nodes[1].0.id, carol.id);
+
+        let mut traversal = TraversalBuilder::new(Arc::clone(&storage), TraversalValue::Empty);
+        traversal
+            .v_from_id(&alice.id)
+            .similar(&[], SimilarityMetric::CommonNeighbours, 1);
+        let nodes = match_node_rows(&traversal.current_step);
+        assert_eq!(nodes.len(), 1);
+        assert_eq!(nodes[0].0.id, carol.id);
+        assert_eq!(nodes[0].1["similarity"], Value::Float(3.0));
+
+        let mut traversal = TraversalBuilder::new(storage, TraversalValue::Empty);
+        traversal
+            .v_from_id(&alice.id)
+            .count()
+            .similar(&[], SimilarityMetric::Jaccard, 1);
+        assert!(traversal.error.is_some());
+    }
+
+    #[test]
+    fn test_repeat() {
+        let (storage, _temp_dir) = setup_test_db();
+
+        // a chain 0 -> 1 -> 2 -> 3 -> 4 that loops back from 3 to 0
+        let people: Vec<Node> = (0..5)
+            .map(|i| {
+                storage
+                    .create_node("person", props! { "level" => i })
+                    .unwrap()
+            })
+            .collect();
+        for (from, to) in [(0, 1), (1, 2), (2, 3), (3, 4), (3, 0)] {
+            storage
+                .create_edge("follows", &people[from].id, &people[to].id, props!())
+                .unwrap();
+        }
+        let ids = |value: &TraversalValue| -> Vec<String> {
+            match_node_array(value).into_iter().map(|n| n.id).collect()
+        };
+        let follows = || {
+            Repeat::new(|t: &mut TraversalBuilder| {
+                t.out("follows");
+            })
+        };
+
+        let mut traversal = TraversalBuilder::new(Arc::clone(&storage), TraversalValue::Empty);
+        traversal
+            .v_from_id(&people[0].id)
+            .repeat(follows().times(2));
+        assert_eq!(ids(&traversal.current_step), vec![people[2].id.clone()]);
+
+        let mut traversal = TraversalBuilder::new(Arc::clone(&storage), TraversalValue::Empty);
+        traversal
+            .v_from_id(&people[0].id)
+            .repeat(follows().times(3).emit());
+        assert_eq!(
+            ids(&traversal.current_step),
+            vec![
+                people[1].id.clone(),
+                people[2].id.clone(),
+                people[3].id.clone()
+            ]
+        );
+
+        // the loop back to 0 is not followed, so everything is reached once
+        let mut traversal = TraversalBuilder::new(Arc::clone(&storage), TraversalValue::Empty);
+        traversal
+            .v_from_id(&people[0].id)
+            .repeat(follows().emit_from(2));
+        assert_eq!(
+            ids(&traversal.current_step),
+            vec![
+                people[2].id.clone(),
+                people[3].id.clone(),
+                people[4].id.clone()
+            ]
+        );
+
+        let mut traversal = TraversalBuilder::new(Arc::clone(&storage), TraversalValue::Empty);
+        traversal.v_from_id(&people[0].id).repeat(
+            follows().until(|node| Ok(node.check_property("level") == Some(&Value::Integer(3)))),
+        );
+        assert_eq!(ids(&traversal.current_step), vec![people[3].id.clone()]);
+
+        // traversers reached at different depths are tagged and selected along their own paths
+        let mut traversal = TraversalBuilder::new(Arc::clone(&storage), TraversalValue::Empty);
+        traversal
+            .v_from_id(&people[0].id)
+            .as_("start")
+            .repeat(follows().times(2).emit())
+            .as_("reached")
+            .select(&["start".to_string(), "reached".to_string()]);
+        assert!(traversal.error.is_none());
+        let pairs: Vec<(String, String)> = match &traversal.current_step {
+            TraversalValue::Rows(rows) => rows
+                .iter()
+                .map(|row| {
+                    let id = |name| match row.get(name) {
+                        Some(TraversalValue::NodeArray(nodes)) => nodes[0].id.clone(),
+                        other => panic!("Expected a node {:?}", other),
+                    };
+                    (id("start"), id("reached"))
+                })
+                .collect(),
+            other => panic!("Expected rows {:?}", other),
+        };
+        assert_eq!(
+            pairs,
+            vec![
+                (people[0].id.clone(), people[1].id.clone()),
+                (people[0].id.clone(), people[2].id.clone())
+            ]
+        );
+
+        let mut traversal = TraversalBuilder::new(storage, TraversalValue::Empty);
+        traversal
+            .v_from_id(&people[0].id)
+            .repeat(Repeat::new(|t: &mut TraversalBuilder| {
+                t.out_e("follows");
+            }));
+        assert!(traversal.error.is_some());
+    }
+
+    #[test]
+    fn test_path_tracking() {
+        let (storage, _temp_dir) = setup_test_db();
+
+        // a ring a -> b -> c -> a, with b also liking d
+        let a = storage.create_node("person", props!()).unwrap();
+        let b = storage.create_node("person", props!()).unwrap();
+        let c = storage.create_node("person", props!()).unwrap();
+        let d = storage.create_node("thing", props!()).unwrap();
+        let ab = storage
+            .create_edge("knows", &a.id, &b.id, props!())
+            .unwrap();
+        let bc = storage
+            .create_edge("knows", &b.id, &c.id, props!())
+            .unwrap();
+        storage
+            .create_edge("knows", &c.id, &a.id, props!())
+            .unwrap();
+        storage
+            .create_edge("likes", &b.id, &d.id, props!())
+            .unwrap();
+
+        let path_ids = |value: &TraversalValue| -> Vec<(Vec<String>, Vec<String>)> {
+            match value {
+                TraversalValue::Paths(paths) => paths
+                    .iter()
+                    .map(|(nodes, edges, _)| {
+                        (
+                            nodes.iter().map(|n| n.id.clone()).collect(),
+                            edges.iter().map(|e| e.id.clone()).collect(),
+                        )
+                    })
+                    .collect(),
+                _ => panic!("Expected paths {:?}", value),
+            }
+        };
+
+        let mut traversal = TraversalBuilder::new(Arc::clone(&storage), TraversalValue::Empty);
+        traversal
+            .v_from_id(&a.id)
+            .track_paths()
+            .out("knows")
+            .out("knows")
+            .path();
+        assert_eq!(
+            path_ids(&traversal.current_step),
+            vec![(
+                vec![a.id.clone(), b.id.clone(), c.id.clone()],
+                vec![ab.id.clone(), bc.id.clone()]
+            )]
+        );
+
+        let mut traversal = TraversalBuilder::new(Arc::clone(&storage), TraversalValue::Empty);
+        traversal
+            .v_from_id(&a.id)
+            .track_paths()
+            .out_e("knows")
+            .in_v()
+            .path();
+        assert_eq!(
+            path_ids(&traversal.current_step),
+            vec![(vec![a.id.clone(), b.id.clone()], vec![ab.id.clone()])]
+        );
+
+        // going round the ring comes back to a
+        let mut traversal = TraversalBuilder::new(Arc::clone(&storage), TraversalValue::Empty);
+        traversal
+            .v_from_id(&a.id)
+            .track_paths()
+            .out("knows")
+            .out("knows")
+            .out("knows")
+            .cyclic_path();
+        assert_eq!(match_node_array(&traversal.current_step)[0].id, a.id);
+        traversal.simple_path();
+        assert!(match_node_array(&traversal.current_step).is_empty());
+
+        let mut traversal = TraversalBuilder::new(Arc::clone(&storage), TraversalValue::Empty);
+        traversal
+            .v()
+            .track_paths()
+            .out("")
+            .filter_nodes(|node| Ok(node.label == "thing"))
+            .path();
+        assert_eq!(
+            path_ids(&traversal.current_step)[0].0,
+            vec![b.id.clone(), d.id.clone()]
+        );
+
+        let mut traversal = TraversalBuilder::new(Arc::clone(&storage), TraversalValue::Empty);
+        traversal
+            .v_from_id(&a.id)
+            .track_paths()
+            .repeat(
+                Repeat::new(|t: &mut TraversalBuilder| {
+                    t.out("knows");
+                })
+                .times(2)
+                .emit(),
+            )
+            .path();
+        let paths = path_ids(&traversal.current_step);
+        assert_eq!(paths.len(), 2);
+        assert_eq!(paths[1].0, vec![a.id.clone(), b.id.clone(), c.id.clone()]);
+
+        let mut traversal = TraversalBuilder::new(storage, TraversalValue::Empty);
+        traversal.v_from_id(&a.id).out("knows").path();
+        assert!(traversal.error.is_some());
+    }
+
+    #[test]
+    fn test_select() {
+        let (storage, _temp_dir) = setup_test_db();
+
+        // a user followed by two others
+        let user = storage.create_node("user", props!()).unwrap();
+        let f1 = storage.create_node("user", props!()).unwrap();
+        let f2 = storage.create_node("user", props!()).unwrap();
+        let e1 = storage
+            .create_edge("follows", &f1.id, &user.id, props!())
+            .unwrap();
+        let e2 = storage
+            .create_edge("follows", &f2.id, &user.id, props!())
+            .unwrap();
+
+        let names = vec![
+            "user".to_string(),
+            "follow".to_string(),
+            "follower".to_string(),
+        ];
+        let mut traversal = TraversalBuilder::new(Arc::clone(&storage), TraversalValue::Empty);
+        traversal
+            .v_from_id(&user.id)
+            .as_("user")
+            .in_e("follows")
+            .as_("follow")
+            .out_v()
+            .as_("follower")
+            .select(&names);
+        assert!(traversal.error.is_none());
+
+        let mut rows = match &traversal.current_step {
+            TraversalValue::Rows(rows) => rows
+                .iter()
+                .map(|row| {
+                    let fields: Vec<&str> = row.0.iter().map(|(name, _)| name.as_str()).collect();
+                    assert_eq!(fields, names);
+                    let id = |name: &str| match row.get(name) {
+                        Some(TraversalValue::NodeArray(nodes)) => nodes[0].id.clone(),
+                        Some(TraversalValue::EdgeArray(edges)) => edges[0].id.clone(),
+                        other => panic!("Expected a node or edge {:?}", other),
+                    };
+                    (id("user"), id("follow"), id("follower"))
+                })
+                .collect::<Vec<_>>(),
+            other => panic!("Expected rows {:?}", other),
+        };
+        rows.sort();
+        let mut expected = vec![
+            (user.id.clone(), e1.id.clone(), f1.id.clone()),
+            (user.id.clone(), e2.id.clone(), f2.id.clone()),
+        ];
+        expected.sort();
+        assert_eq!(rows, expected);
+
+        let mut traversal = TraversalBuilder::new(storage, TraversalValue::Empty);
+        traversal
+            .v_from_id(&user.id)
+            .as_("user")
+            .in_("follows")
+            .select(&names);
+        assert!(traversal.error.is_some());
+    }
+
+    #[test]
+    fn test_dedup_order_limit() {
+        let (storage, _temp_dir) = setup_test_db();
+
+        // a knows b and c, who both know d
+        let a = storage.create_node("person", props!("age" => 40)).unwrap();
+        let b = storage.create_node("person", props!("age" => 20)).unwrap();
+        let c = storage.create_node("person", props!("age" => 30)).unwrap();
+        let d = storage.create_node("person", props!("age" => 20)).unwrap();
+        storage
+            .create_edge("knows", &a.id, &b.id, props!())
+            .unwrap();
+        storage
+            .create_edge("knows", &a.id, &c.id, props!())
+            .unwrap();
+        storage
+            .create_edge("knows", &b.id, &d.id, props!())
+            .unwrap();
+        storage
+            .create_edge("knows", &c.id, &d.id, props!())
+            .unwrap();
+        let ids = |value: &TraversalValue| -> Vec<String> {
+            match_node_array(value).into_iter().map(|n| n.id).collect()
+        };
+
+        let mut traversal = TraversalBuilder::new(Arc::clone(&storage), TraversalValue::Empty);
+        traversal.v_from_id(&a.id).out("knows").out("knows");
+        assert_eq!(
+            ids(&traversal.current_step),
+            vec![d.id.clone(), d.id.clone()]
+        );
+        traversal.dedup();
+        assert_eq!(ids(&traversal.current_step), vec![d.id.clone()]);
+
+        let mut traversal = TraversalBuilder::new(Arc::clone(&storage), TraversalValue::Empty);
+        traversal.v().order_by("age", Order::Desc);
+        let ages: Vec<Value> = match_node_array(&traversal.current_step)
+            .iter()
+            .map(|n| n.check_property("age").unwrap().clone())
+            .collect();
+        assert_eq!(
+            ages,
+            vec![
+                Value::Integer(40),
+                Value::Integer(30),
+                Value::Integer(20),
+                Value::Integer(20)
+            ]
+        );
+        traversal.dedup_by("age");
+        assert_eq!(match_node_array(&traversal.current_step).len(), 3);
+        traversal.limit(2);
+        assert_eq!(
+            ids(&traversal.current_step),
+            vec![a.id.clone(), c.id.clone()]
+        );
+
+        // paths follow the traversers they belong to
+        let mut traversal = TraversalBuilder::new(Arc::clone(&storage), TraversalValue::Empty);
+        traversal
+            .v_from_id(&a.id)
+            .track_paths()
+            .out("knows")
+            .order_by("age", Order::Asc)
+            .limit(1)
+            .path();
+        match &traversal.current_step {
+            TraversalValue::Paths(paths) => {
+                assert_eq!(paths.len(), 1);
+                assert_eq!(paths[0].0[1].id, b.id);
+            }
+            other => panic!("Expected paths {:?}", other),
+        }
+
+        let mut traversal = TraversalBuilder::new(storage, TraversalValue::Empty);
+        traversal.v().limit(0);
+        assert!(matches!(traversal.current_step, TraversalValue::Empty));
+    }
+
+    #[test]
+    fn test_v_limit_e_limit() {
+        let (storage, _temp_dir) = setup_test_db();
+        let people: Vec<Node> = (0..3)
+            .map(|_| storage.create_node("person", props!()).unwrap())
+            .collect();
+        // everyone knows the next person round a cycle
+        for (i, person) in people.iter().enumerate() {
+            storage
+                .create_edge("knows", &person.id, &people[(i + 1) % 3].id, props!())
+                .unwrap();
+        }
+
+        let mut traversal = TraversalBuilder::new(Arc::clone(&storage), TraversalValue::Empty);
+        traversal.v_limit(2);
+        assert_eq!(match_node_array(&traversal.current_step).len(), 2);
+        traversal.v_limit(10);
+        assert_eq!(match_node_array(&traversal.current_step).len(), 3);
+
+        traversal.e_limit(1);
+        match &traversal.current_step {
+            TraversalValue::EdgeArray(edges) => assert_eq!(edges.len(), 1),
+            other => panic!("Expected EdgeArray {:?}", other),
+        }
+
+        // paths restart from the nodes that were read
+        traversal.v_limit(2).track_paths().out("knows").path();
+        match &traversal.current_step {
+            TraversalValue::Paths(paths) => assert_eq!(paths.len(), 2),
+            other => panic!("Expected paths {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_aggregations() {
+        let (storage, _temp_dir) = setup_test_db();
+        for (city, age) in [("london", 20), ("paris", 30), ("london", 40), ("paris", 31)] {
+            storage
+                .create_node("person", props!("city" => city, "age" => age))
+                .unwrap();
+        }
+        storage
+            .create_node("person", props!("city" => "rome"))
+            .unwrap();
+
+        let mut traversal = TraversalBuilder::new(Arc::clone(&storage), TraversalValue::Empty);
+        traversal.v().sum("age");
+        assert!(matches!(
+            traversal.current_step,
+            TraversalValue::Value(Value::I64(121))
+        ));
+        traversal.v().mean("age");
+        assert!(matches!(
+            traversal.current_step,
+            TraversalValue::Value(Value::Float(m)) if m == 30.25
+        ));
+        traversal.v().min("age");
+        assert!(matches!(
+            traversal.current_step,
+            TraversalValue::Value(Value::Integer(20))
+        ));
+        traversal.v().max("city");
+        assert!(matches!(
+            &traversal.current_step,
+            TraversalValue::Value(Value::String(c)) if c == "rome"
+        ));
+        traversal.v().collect_set("city");
+        assert_eq!(
+            serde_json::to_value(&traversal.current_step).unwrap(),
+            serde_json::json!(["london", "paris", "rome"])
+        );
+
+        traversal.v().group_count("city");
+        assert_eq!(
+            serde_json::to_value(&traversal.current_step).unwrap(),
+            serde_json::json!([
+                {"key": "london", "value": 2},
+                {"key": "paris", "value": 2},
+                {"key": "rome", "value": 1}
+            ])
+        );
+
+        // aggregations after group_by apply to each group
+        traversal.v().group_by("city");
+        match &traversal.current_step {
+            TraversalValue::Groups(groups) => {
+                assert_eq!(groups.len(), 3);
+                assert!(
+                    matches!(&groups[0].1, TraversalValue::NodeArray(nodes) if nodes.len() == 2)
+                );
+            }
+            other => panic!("Expected groups {:?}", other),
+        }
+        traversal.max("age");
+        assert_eq!(
+            serde_json::to_value(&traversal.current_step).unwrap(),
+            serde_json::json!([
+                {"key": "london", "value": 40},
+                {"key": "paris", "value": 31},
+                {"key": "rome", "value": null}
+            ])
+        );
+
+        // keys keep their types, so a string key never collides with a number or a missing value
+        storage
+            .create_node("person", props!("city" => "leeds", "age" => "20"))
+            .unwrap();
+        storage
+            .create_node("person", props!("city" => "york", "age" => "null"))
+            .unwrap();
+        traversal.v().group_count("age");
+        let json = serde_json::json!([
+            {"key": null, "value": 1},
+            {"key": 20, "value": 1},
+            {"key": 30, "value": 1},
+            {"key": 31, "value": 1},
+            {"key": 40, "value": 1},
+            {"key": "20", "value": 1},
+            {"key": "null", "value": 1}
+        ]);
+        assert_eq!(serde_json::to_value(&traversal.current_step).unwrap(), json);
+        let read_back: TraversalValue = serde_json::from_value(json.clone()).unwrap();
+        assert!(matches!(&read_back, TraversalValue::Groups(groups) if groups.len() == 7));
+        assert_eq!(serde_json::to_value(&read_back).unwrap(), json);
+
+        let mut traversal = TraversalBuilder::new(storage, TraversalValue::Empty);
+        traversal.v().sum("city");
+        assert!(traversal.error.is_some());
+    }
+
+    #[test]
+    fn test_branching() {
+        let (storage, _temp_dir) = setup_test_db();
+
+        // a follows b, c follows nobody but has a suggestion d
+        let a = storage.create_node("person", props!("age" => 40)).unwrap();
+        let b = storage.create_node("person", props!("age" => 20)).unwrap();
+        let c = storage.create_node("person", props!("age" => 30)).unwrap();
+        let d = storage.create_node("person", props!("age" => 50)).unwrap();
+        storage
+            .create_edge("follows", &a.id, &b.id, props!())
+            .unwrap();
+        storage
+            .create_edge("suggested", &a.id, &d.id, props!())
+            .unwrap();
+        storage
+            .create_edge("suggested", &c.id, &d.id, props!())
+            .unwrap();
+        let ids = |value: &TraversalValue| -> Vec<String> {
+            match_node_array(value).into_iter().map(|n| n.id).collect()
+        };
+        let start = TraversalValue::NodeArray(vec![a.clone(), c.clone()]);
+
+        let mut traversal = TraversalBuilder::new(Arc::clone(&storage), start.clone());
+        traversal.coalesce(vec![
+            Box::new(|t: &mut TraversalBuilder| {
+                t.out("follows");
+            }),
+            Box::new(|t: &mut TraversalBuilder| {
+                t.out("suggested");
+            }),
+        ]);
+        assert_eq!(
+            ids(&traversal.current_step),
+            vec![b.id.clone(), d.id.clone()]
+        );
+
+        let mut traversal = TraversalBuilder::new(Arc::clone(&storage), start.clone());
+        traversal.union(vec![
+            Box::new(|t: &mut TraversalBuilder| {
+                t.out("follows");
+            }),
+            Box::new(|t: &mut TraversalBuilder| {
+                t.out("suggested");
+            }),
+        ]);
+        assert_eq!(
+            ids(&traversal.current_step),
+            vec![b.id.clone(), d.id.clone(), d.id.clone()]
+        );
+
+        let mut traversal = TraversalBuilder::new(Arc::clone(&storage), start.clone());
+        traversal.optional(Box::new(|t: &mut TraversalBuilder| {
+            t.out("follows");
+        }));
+        assert_eq!(
+            ids(&traversal.current_step),
+            vec![b.id.clone(), c.id.clone()]
+        );
+
+        let older_than_35 = |t: &mut TraversalBuilder| {
+            t.filter_nodes(|node| {
+                Ok(node
+                    .check_property("age")
+                    .is_some_and(|age| *age > Value::Integer(35)))
+            });
+        };
+        let mut traversal = TraversalBuilder::new(Arc::clone(&storage), start.clone());
+        traversal.choose(
+            Box::new(older_than_35),
+            Box::new(|t: &mut TraversalBuilder| {
+                t.out("follows");
+            }),
+            None,
+        );
+        assert_eq!(
+            ids(&traversal.current_step),
+            vec![b.id.clone(), c.id.clone()]
+        );
+
+        let mut traversal = TraversalBuilder::new(Arc::clone(&storage), start.clone());
+        traversal.choose(
+            Box::new(older_than_35),
+            Box::new(|t: &mut TraversalBuilder| {
+                t.out("follows");
+            }),
+            Some(Box::new(|t: &mut TraversalBuilder| {
+                t.out("suggested");
+            })),
+        );
+        assert_eq!(
+            ids(&traversal.current_step),
+            vec![b.id.clone(), d.id.clone()]
+        );
+
+        // branches ending on nodes and on edges cannot be mixed
+        let mut traversal = TraversalBuilder::new(storage, start);
+        traversal.union(vec![
+            Box::new(|t: &mut TraversalBuilder| {
+                t.out("follows");
+            }),
+            Box::new(|t: &mut TraversalBuilder| {
+                t.out_e("follows");
+            }),
+        ]);
+        assert!(traversal.error.is_some());
+    }
+
+    #[test]
+    fn test_project() {
+        let (storage, _temp_dir) = setup_test_db();
+
+        // a and b both follow c
+        let a = storage.create_node("user", props!("name" => "a")).unwrap();
+        let b = storage.create_node("user", props!("name" => "b")).unwrap();
+        let c = storage.create_node("user", props!("name" => "c")).unwrap();
+        storage
+            .create_edge("follows", &a.id, &c.id, props!())
+            .unwrap();
+        storage
+            .create_edge("follows", &b.id, &c.id, props!())
+            .unwrap();
+
+        let mut traversal = TraversalBuilder::new(
+            Arc::clone(&storage),
+            TraversalValue::NodeArray(vec![c.clone(), a.clone()]),
+        );
+        traversal.project(vec![
+            ("name", Projection::Property("name".to_string())),
+            ("label", Projection::Label),
+            (
+                "followers",
+                Projection::Count(Box::new(|t: &mut TraversalBuilder| {
+                    t.in_("follows");
+                })),
+            ),
+            (
+                "follows",
+                Projection::Traversal(Box::new(|t: &mut TraversalBuilder| {
+                    t.out("follows")
+                        .project(vec![("name", Projection::Property("name".to_string()))]);
+                })),
+            ),
+            ("age", Projection::Property("age".to_string())),
+        ]);
+        assert!(traversal.error.is_none());
+        assert_eq!(
+            serde_json::to_value(&traversal.current_step).unwrap(),
+            serde_json::json!([
+                {"name": "c", "label": "user", "followers": 2, "follows": null, "age": null},
+                {
+                    "name": "a",
+                    "label": "user",
+                    "followers": 0,
+                    "follows": [{"name": "c"}],
+                    "age": null
+                },
+            ])
+        );
+        match &traversal.current_step {
+            TraversalValue::Rows(rows) => {
+                let fields: Vec<&str> = rows[0].0.iter().map(|(name, _)| name.as_str()).collect();
+                assert_eq!(fields, vec!["name", "label", "followers", "follows", "age"]);
+                assert!(matches!(
+                    rows[1].get("followers"),
+                    Some(TraversalValue::Count(c)) if c.value() == 0
+                ));
+            }
+            other => panic!("Expected rows {:?}", other),
+        }
+
+        // rows read back from JSON keep their fields in order
+        let json = serde_json::to_string(&traversal.current_step).unwrap();
+        let read_back: TraversalValue = serde_json::from_str(&json).unwrap();
+        match &read_back {
+            TraversalValue::Rows(rows) => {
+                let fields: Vec<&str> = rows[1].0.iter().map(|(name, _)| name.as_str()).collect();
+                assert_eq!(fields, vec!["name", "label", "followers", "follows", "age"]);
+                assert!(matches!(
+                    rows[1].get("follows"),
+                    Some(TraversalValue::Rows(_))
+                ));
+            }
+            other => panic!("Expected rows {:?}", other),
+        }
+        assert_eq!(serde_json::to_string(&read_back).unwrap(), json);
+
+        let mut traversal = TraversalBuilder::new(storage, TraversalValue::Empty);
+        traversal
+            .v_from_id(&a.id)
+            .count()
+            .project(vec![("id", Projection::Id)]);
+        assert!(traversal.error.is_some());
+    }
+
+    /// The node in each row along with the values beside it
+    fn match_node_rows(value: &TraversalValue) -> Vec<(Node, HashMap<String, Value>)> {
+        let TraversalValue::Rows(rows) = value else {
+            return vec![];
+        };
+        rows.iter()
+            .map(|row| {
+                let node = match row.get("node") {
+                    Some(TraversalValue::NodeArray(nodes)) => nodes[0].clone(),
+                    other => panic!("Expected a node {:?}", other),
+                };
+                let fields = row
+                    .0
+                    .iter()
+                    .filter_map(|(name, value)| match value {
+                        TraversalValue::Value(value) => Some((name.clone(), value.clone())),
+                        _ => None,
+                    })
+                    .collect();
+                (node, fields)
+            })
+            .collect()
+    }
+
     fn match_node_array(value: &TraversalValue) -> Vec<Node> {
         match value {
             TraversalValue::NodeArray(nodes) => nodes.clone(),
@@ -1,5 +1,12 @@
 use protocol::{traversal_value::TraversalValue, value::Value, Edge, Node};
 
+use crate::graph_algorithms::centrality::{CentralityConfig, CentralityMeasure};
+use crate::graph_algorithms::similarity::SimilarityMetric;
+use crate::graph_core::traversal::{AnonymousTraversal, Order, Projection, Repeat};
+use crate::storage_core::{
+    record::NodeRef,
+    storage_methods::{EdgeDirection, PathOptions},
+};
 use crate::types::GraphError;
 
 pub trait SourceTraversalSteps {
@@ -7,10 +14,19 @@ pub trait SourceTraversalSteps {
     ///
     /// Note: This can be a VERY expensive operation
     fn v(&mut self) -> &mut Self;
+    /// Adds the nodes in the graph for which `f` returns true to current traversal step.
+    /// Nodes are checked through borrowed views, so only the ones that are kept are decoded.
+    fn v_where<F: Fn(NodeRef<'_>) -> bool>(&mut self, f: F) -> &mut Self;
     /// Adds all edges in the graph to current traversal step
     ///  
     /// Note: This can be a VERY expensive operation
     fn e(&mut self) -> &mut Self;
+    /// Adds at most `n` nodes in the graph to current traversal step,
+    /// stopping the read as soon as enough nodes have been found
+    fn v_limit(&mut self, n: usize) -> &mut Self;
+    /// Adds at most `n` edges in the graph to current traversal step,
+    /// stopping the read as soon as enough edges have been found
+    fn e_limit(&mut self, n: usize) -> &mut Self;
 
     /// Creates a new node in the graph and adds it to current traversal step
     fn add_v(&mut self, node_label: &str, props: Vec<(String, Value)>) -> &mut Self;
@@ -33,11 +49,17 @@ pub trait SourceTraversalSteps {
 pub trait TraversalSteps {
     /// Adds the nodes at the end of an outgoing edge to the current traversal step that match a given edge label if given one
     fn out(&mut self, edge_label: &str) -> &mut Self;
+    /// Like `out`, but only adds the nodes for which `f` returns true.
+    /// Nodes are checked through borrowed views, so only the ones that are kept are decoded.
+    fn out_where<F: Fn(NodeRef<'_>) -> bool>(&mut self, edge_label: &str, f: F) -> &mut Self;
     /// Adds the outgoing edges from the current node to the current traversal step that match a given edge label if given one
     fn out_e(&mut self, edge_label: &str) -> &mut Self;
 
     /// Adds the nodes at the start of an incoming edge to the current traversal step that match a given edge label if given one
     fn in_(&mut self, edge_label: &str) -> &mut Self;
+    /// Like `in_`, but only adds the nodes for which `f` returns true.
+    /// Nodes are checked through borrowed views, so only the ones that are kept are decoded.
+    fn in_where<F: Fn(NodeRef<'_>) -> bool>(&mut self, edge_label: &str, f: F) -> &mut Self;
     /// Adds the incoming edges from the current node
     /// to the current traversal step that match a given edge label if given one
     fn in_e(&mut self, edge_label: &str) -> &mut Self;
@@ -84,6 +106,109 @@ pub trait TraversalMethods {
     /// Flattens everything in the current traversal step and updates the current traversal step to be a slice of itself.
     fn range(&mut self, start: usize, end: usize) -> &mut Self;
 
+    /// Turns on path tracking, starting a path at each current node or edge.
+    ///
+    /// Hop steps then add the edges and nodes they go through to the path of each traverser,
+    /// and filters, `range` and `repeat` keep the paths of the traversers they keep.
+    /// Steps that reorder or replace the traversers, such as `mutual`, `centrality` or `similar`, turn it off.
+    fn track_paths(&mut self) -> &mut Self;
+
+    /// Replaces the current nodes or edges with the path taken to reach each of them
+    /// as a `TraversalValue::Paths`. Needs path tracking to be on.
+    fn path(&mut self) -> &mut Self;
+
+    /// Keeps the current nodes or edges whose path does not visit any node twice.
+    /// Needs path tracking to be on.
+    fn simple_path(&mut self) -> &mut Self;
+
+    /// Keeps the current nodes or edges whose path visits some node twice.
+    /// Needs path tracking to be on.
+    fn cyclic_path(&mut self) -> &mut Self;
+
+    /// Removes the current nodes or edges that were already seen earlier in the step, by id.
+    fn dedup(&mut self) -> &mut Self;
+
+    /// Keeps only the first current node or edge for each value of `property`.
+    /// Nodes or edges without the property count as having the same empty value.
+    fn dedup_by(&mut self, property: &str) -> &mut Self;
+
+    /// Sorts the current nodes or edges by the value of `property`, using the ordering of `Value`.
+    /// Nodes or edges without the property come first when ascending.
+    fn order_by(&mut self, property: &str, order: Order) -> &mut Self;
+
+    /// Keeps the first `n` current nodes or edges.
+    ///
+    /// `v` and `e` read the whole graph before `limit` runs, so start with `v_limit` or `e_limit`
+    /// instead to stop reading storage early, as HelixQL does for a `LIMIT` straight after `V` or `E`.
+    fn limit(&mut self, n: usize) -> &mut Self;
+
+    /// Groups the current nodes or edges by the value of `key` into a `TraversalValue::Groups`,
+    /// ordered by key. Nodes or edges without the property are grouped under `Value::Empty`.
+    fn group_by(&mut self, key: &str) -> &mut Self;
+
+    /// Counts the current nodes or edges for each value of `key` into a `TraversalValue::Groups`
+    fn group_count(&mut self, key: &str) -> &mut Self;
+
+    /// Sums `property` over the current nodes or edges, as an `I64` if every value is an integer
+    /// and as a `Float` otherwise. After `group_by` the sum is taken per group, as are the other aggregations.
+    /// Nodes or edges without the property are skipped by every aggregation.
+    fn sum(&mut self, property: &str) -> &mut Self;
+
+    /// Mean of `property` over the current nodes or edges, `Value::Empty` if none have it
+    fn mean(&mut self, property: &str) -> &mut Self;
+
+    /// Smallest value of `property` using the ordering of `Value`, `Value::Empty` if none have it
+    fn min(&mut self, property: &str) -> &mut Self;
+
+    /// Largest value of `property` using the ordering of `Value`, `Value::Empty` if none have it
+    fn max(&mut self, property: &str) -> &mut Self;
+
+    /// The distinct values of `property` as a sorted `Value::Array`
+    fn collect_set(&mut self, property: &str) -> &mut Self;
+
+    /// Runs each of `traversals` from every current node or edge on its own
+    /// and replaces the current step with all of their outputs.
+    ///
+    /// Like the other branching steps, the outputs of a traverser come before those of the next one,
+    /// the branches must all end on nodes or all end on edges, and path tracking is turned off.
+    fn union(&mut self, traversals: Vec<AnonymousTraversal<'_>>) -> &mut Self;
+
+    /// Outputs, for every current node or edge, the first of `traversals` that outputs anything from it
+    fn coalesce(&mut self, traversals: Vec<AnonymousTraversal<'_>>) -> &mut Self;
+
+    /// Runs `then` from every current node or edge for which `predicate` outputs a true boolean,
+    /// a non-zero count or any nodes or edges, and `otherwise` from the rest.
+    /// Without `otherwise` the rest are kept as they are.
+    fn choose(
+        &mut self,
+        predicate: AnonymousTraversal<'_>,
+        then: AnonymousTraversal<'_>,
+        otherwise: Option<AnonymousTraversal<'_>>,
+    ) -> &mut Self;
+
+    /// Runs `traversal` from every current node or edge, keeping the node or edge itself if it outputs nothing
+    fn optional(&mut self, traversal: AnonymousTraversal<'_>) -> &mut Self;
+
+    /// Replaces the current nodes or edges with one row each as a `TraversalValue::Rows`,
+    /// holding the given fields in order, each computed by its `Projection`.
+    fn project(&mut self, fields: Vec<(&str, Projection<'_>)>) -> &mut Self;
+
+    /// Tags the current nodes or edges with `name` so later steps can `select` them.
+    /// Turns on path tracking if it is not on yet.
+    fn as_(&mut self, name: &str) -> &mut Self;
+
+    /// Replaces the current nodes or edges with one row per traverser as a `TraversalValue::Rows`,
+    /// holding the node or edge it was at when tagged with each of `names`, in the order given.
+    fn select(&mut self, names: &[String]) -> &mut Self;
+
+    /// Applies the steps of `repeat` to the current nodes over and over, each time to the nodes
+    /// reached by the previous iteration, until `repeat` says to stop or no new nodes are reached.
+    ///
+    /// Every node is reached at most once, so cycles are not followed and a node is output at the depth
+    /// of its shortest route. The current step becomes the nodes of the last iteration, or of every iteration
+    /// with `emit`, plus the nodes that matched `until`.
+    fn repeat(&mut self, repeat: Repeat<'_>) -> &mut Self;
+
     /// Filters the current traversal step
     ///
     /// ### Returns:
@@ -235,4 +360,87 @@ pub trait TraversalSearchMethods {
 
     /// Finds the shortes path between two given nodes using BFS
     fn shortest_path_between(&mut self, from_id: &str, to_id: &str) -> &mut Self;
+
+    /// Finds the shortest path between two given nodes with the given direction, edge labels and max depth
+    fn shortest_path_between_with(
+        &mut self,
+        from_id: &str,
+        to_id: &str,
+        options: &PathOptions,
+    ) -> &mut Self;
+
+    /// Finds the shortest path from each current node to a given node
+    /// with the given direction, edge labels and max depth
+    fn shortest_path_to_with(&mut self, to_id: &str, options: &PathOptions) -> &mut Self;
+
+    /// Finds every shortest path between two given nodes
+    fn all_shortest_paths_between(&mut self, from_id: &str, to_id: &str) -> &mut Self;
+
+    /// Finds every shortest path from each current node to a given node
+    fn all_shortest_paths_to(&mut self, to_id: &str) -> &mut Self;
+
+    /// Finds up to `k` shortest loopless paths between two given nodes using Yen's algorithm
+    fn k_shortest_paths_between(&mut self, from_id: &str, to_id: &str, k: usize) -> &mut Self;
+
+    /// Finds up to `k` shortest loopless paths from each current node to a given node
+    fn k_shortest_paths_to(&mut self, to_id: &str, k: usize) -> &mut Self;
+
+    /// Finds the cheapest path between two given nodes using Dijkstra's algorithm,
+    /// with edge costs read from `weight_property`
+    fn weighted_shortest_path_between(
+        &mut self,
+        from_id: &str,
+        to_id: &str,
+        weight_property: &str,
+        direction: EdgeDirection,
+        edge_labels: &[String],
+    ) -> &mut Self;
+
+    /// Finds the cheapest path from each current node to a given node using Dijkstra's algorithm,
+    /// with edge costs read from `weight_property`
+    fn weighted_shortest_path_to(
+        &mut self,
+        to_id: &str,
+        weight_property: &str,
+        direction: EdgeDirection,
+        edge_labels: &[String],
+    ) -> &mut Self;
+
+    /// Finds the cheapest path between two given nodes using A* with the given heuristic
+    fn astar_shortest_path_between<H: Fn(NodeRef<'_>) -> f64>(
+        &mut self,
+        from_id: &str,
+        to_id: &str,
+        weight_property: &str,
+        direction: EdgeDirection,
+        edge_labels: &[String],
+        heuristic: H,
+    ) -> &mut Self;
+}
+
+pub trait TraversalAlgorithmMethods {
+    /// Finds the weakly connected component of each current node within the subgraph of the current nodes
+    /// and edges with a label in `edge_labels`.
+    ///
+    /// Produces one row per node with the node under `node`, its component under `component`
+    /// and the number of nodes in it under `component_size`. Components are numbered from 0 by decreasing size.
+    fn connected_components(&mut self, edge_labels: &[String]) -> &mut Self;
+
+    /// Same as `connected_components` but with strongly connected components,
+    /// so nodes in the same component can reach each other along edge directions.
+    fn strongly_connected_components(&mut self, edge_labels: &[String]) -> &mut Self;
+
+    /// Scores each current node by `measure` within the subgraph of the current nodes
+    /// and edges with a label in `config.filter.edge_labels`.
+    ///
+    /// Produces one row per node with the node under `node` and its score under `centrality`,
+    /// ordered from the highest score and keeping only the `config.top_k` highest if set.
+    fn centrality(&mut self, measure: CentralityMeasure, config: &CentralityConfig) -> &mut Self;
+
+    /// Replaces the current nodes with the `k` nodes most similar to them by `metric`,
+    /// counting neighbours along edges with a label in `edge_labels` in both directions.
+    ///
+    /// Produces one row per node with the node under `node` and its highest score against any current node
+    /// under `similarity`, ordered from the highest score. Current nodes and their direct neighbours are left out.
+    fn similar(&mut self, edge_labels: &[String], metric: SimilarityMetric, k: usize) -> &mut Self;
 }
@@ -1,5 +1,6 @@
 pub mod storage_core;
 pub mod graph_core;
+pub mod graph_algorithms;
 pub mod types;
 pub mod macros;
 
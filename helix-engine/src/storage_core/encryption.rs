@@ -0,0 +1,209 @@
+use aes_gcm::{
+    aead::{Aead, AeadCore, KeyInit, OsRng, Payload},
+    Aes256Gcm, Key, Nonce,
+};
+use std::collections::HashMap;
+
+use crate::types::GraphError;
+
+/// Magic bytes written at the start of every encrypted record
+pub const ENCRYPTION_MAGIC: [u8; 4] = *b"HXEC";
+
+/// Version of the encrypted record layout that follows the magic bytes.
+///
+/// Layout: magic (4) | version (1) | key id (4, LE) | nonce (12) | ciphertext
+pub const ENCRYPTION_FORMAT_VERSION: u8 = 1;
+
+const MAGIC_LEN: usize = ENCRYPTION_MAGIC.len();
+const KEY_ID_LEN: usize = 4;
+const NONCE_LEN: usize = 12;
+const KEY_ID_OFFSET: usize = MAGIC_LEN + 1;
+const NONCE_OFFSET: usize = KEY_ID_OFFSET + KEY_ID_LEN;
+const HEADER_LEN: usize = NONCE_OFFSET + NONCE_LEN;
+
+/// Default environment variable the encryption key is read from
+pub const DEFAULT_KEY_ENV_VAR: &str = "HELIX_ENCRYPTION_KEY";
+
+/// A 256-bit AES-GCM key along with the id that is stored in each record header
+/// so that the correct key can be picked when decrypting.
+#[derive(Clone)]
+pub struct EncryptionKey {
+    pub id: u32,
+    cipher: Aes256Gcm,
+}
+
+impl EncryptionKey {
+    /// Creates a key from 32 raw bytes
+    pub fn new(id: u32, bytes: &[u8]) -> Result<EncryptionKey, GraphError> {
+        if bytes.len() != 32 {
+            return Err(GraphError::EncryptionError(format!(
+                "Encryption key must be 32 bytes, got {}",
+                bytes.len()
+            )));
+        }
+        Ok(EncryptionKey {
+            id,
+            cipher: Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(bytes)),
+        })
+    }
+
+    /// Creates a key from a 64 character hex string
+    pub fn from_hex(id: u32, hex_key: &str) -> Result<EncryptionKey, GraphError> {
+        let bytes = hex::decode(hex_key.trim())
+            .map_err(|e| GraphError::EncryptionError(format!("Invalid hex key: {}", e)))?;
+        Self::new(id, &bytes)
+    }
+
+    /// Reads a hex encoded key from the given environment variable
+    pub fn from_env(id: u32, var: &str) -> Result<EncryptionKey, GraphError> {
+        let hex_key = std::env::var(var).map_err(|_| {
+            GraphError::EncryptionError(format!("Environment variable {} not set", var))
+        })?;
+        Self::from_hex(id, &hex_key)
+    }
+
+    /// Reads a key from a file containing either 32 raw bytes or a hex encoded key
+    pub fn from_file(id: u32, path: &str) -> Result<EncryptionKey, GraphError> {
+        let contents = std::fs::read(path)?;
+        match contents.len() {
+            32 => Self::new(id, &contents),
+            _ => Self::from_hex(id, &String::from_utf8(contents)?),
+        }
+    }
+}
+
+/// Encrypts and decrypts serialized records stored in `HelixGraphStorage`.
+///
+/// New records are always encrypted with the active key. Previous keys are kept so that
+/// records written before a key rotation can still be read until they are re-encrypted.
+#[derive(Clone)]
+pub struct StorageEncryption {
+    active: EncryptionKey,
+    keys: HashMap<u32, EncryptionKey>,
+}
+
+impl StorageEncryption {
+    pub fn new(active: EncryptionKey) -> StorageEncryption {
+        let mut keys = HashMap::with_capacity(2);
+        keys.insert(active.id, active.clone());
+        StorageEncryption { active, keys }
+    }
+
+    /// Adds a retired key that is only used to decrypt existing records
+    pub fn with_previous_key(mut self, key: EncryptionKey) -> StorageEncryption {
+        if key.id != self.active.id {
+            self.keys.insert(key.id, key);
+        }
+        self
+    }
+
+    /// Id of the key used for new writes
+    #[inline]
+    pub fn active_key_id(&self) -> u32 {
+        self.active.id
+    }
+
+    /// Encrypts a serialized record.
+    /// The storage key is used as associated data so records cannot be swapped between keys.
+    pub fn encrypt(&self, storage_key: &[u8], plaintext: &[u8]) -> Result<Vec<u8>, GraphError> {
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let ciphertext = self
+            .active
+            .cipher
+            .encrypt(
+                &nonce,
+                Payload {
+                    msg: plaintext,
+                    aad: storage_key,
+                },
+            )
+            .map_err(|_| GraphError::EncryptionError("Failed to encrypt record".to_string()))?;
+
+        let mut data = Vec::with_capacity(HEADER_LEN + ciphertext.len());
+        data.extend_from_slice(&ENCRYPTION_MAGIC);
+        data.push(ENCRYPTION_FORMAT_VERSION);
+        data.extend_from_slice(&self.active.id.to_le_bytes());
+        data.extend_from_slice(&nonce);
+        data.extend_from_slice(&ciphertext);
+        Ok(data)
+    }
+
+    /// Decrypts a stored record.
+    ///
+    /// Records without the encryption header are rejected, so plaintext written to an
+    /// encrypted database can never be read back as if it were trusted data.
+    pub fn decrypt(&self, storage_key: &[u8], data: &[u8]) -> Result<Vec<u8>, GraphError> {
+        match Self::record_key_id(data)? {
+            Some(key_id) => self.decrypt_with(key_id, storage_key, data),
+            None => Err(GraphError::EncryptionError(
+                "Record is not encrypted".to_string(),
+            )),
+        }
+    }
+
+    /// Decrypts a stored record, returning records without the encryption header unchanged.
+    ///
+    /// Only used by migrations and `reencrypt_all`, which need to read databases
+    /// that were written in plaintext before encryption was enabled.
+    pub(crate) fn decrypt_or_plaintext(
+        &self,
+        storage_key: &[u8],
+        data: &[u8],
+    ) -> Result<Vec<u8>, GraphError> {
+        match Self::record_key_id(data)? {
+            Some(key_id) => self.decrypt_with(key_id, storage_key, data),
+            None => Ok(data.to_vec()),
+        }
+    }
+
+    /// Returns true if the record is not encrypted with the active key
+    #[inline]
+    pub fn needs_reencryption(&self, data: &[u8]) -> bool {
+        !matches!(Self::record_key_id(data), Ok(Some(id)) if id == self.active.id)
+    }
+
+    /// Returns true if the record starts with the encryption header
+    #[inline]
+    pub fn is_encrypted(data: &[u8]) -> bool {
+        data.len() >= HEADER_LEN && data[..MAGIC_LEN] == ENCRYPTION_MAGIC
+    }
+
+    fn decrypt_with(
+        &self,
+        key_id: u32,
+        storage_key: &[u8],
+        data: &[u8],
+    ) -> Result<Vec<u8>, GraphError> {
+        let key = self.keys.get(&key_id).ok_or_else(|| {
+            GraphError::EncryptionError(format!("No key available with id {}", key_id))
+        })?;
+        let nonce = Nonce::from_slice(&data[NONCE_OFFSET..HEADER_LEN]);
+        key.cipher
+            .decrypt(
+                nonce,
+                Payload {
+                    msg: &data[HEADER_LEN..],
+                    aad: storage_key,
+                },
+            )
+            .map_err(|_| GraphError::EncryptionError("Failed to decrypt record".to_string()))
+    }
+
+    /// Reads the key id from the record header, `None` if the record has no header
+    #[inline]
+    fn record_key_id(data: &[u8]) -> Result<Option<u32>, GraphError> {
+        if !Self::is_encrypted(data) {
+            return Ok(None);
+        }
+        let version = data[MAGIC_LEN];
+        if version != ENCRYPTION_FORMAT_VERSION {
+            return Err(GraphError::EncryptionError(format!(
+                "Unsupported encrypted record version {}",
+                version
+            )));
+        }
+        let mut id = [0u8; KEY_ID_LEN];
+        id.copy_from_slice(&data[KEY_ID_OFFSET..NONCE_OFFSET]);
+        Ok(Some(u32::from_le_bytes(id)))
+    }
+}
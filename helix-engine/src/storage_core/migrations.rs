@@ -0,0 +1,89 @@
+use protocol::{Edge, Node};
+
+use crate::storage_core::record::{self, Record, RecordKind};
+use crate::storage_core::storage_core::HelixGraphStorage;
+use crate::types::GraphError;
+
+/// On-disk format version written by this build.
+///
+/// - 1: node and edge records serialized with bincode, no format version key
+/// - 2: node and edge records in the versioned zero-copy layout
+pub const CURRENT_FORMAT_VERSION: u32 = 2;
+
+/// Version assumed for databases created before the format version key existed
+pub const LEGACY_FORMAT_VERSION: u32 = 1;
+
+/// Upgrades a database from format version `from` to `from + 1`
+pub struct Migration {
+    pub from: u32,
+    pub description: &'static str,
+    run: fn(&HelixGraphStorage) -> Result<usize, GraphError>,
+}
+
+/// All migrations in the order they are applied.
+/// Adding a new format version means bumping `CURRENT_FORMAT_VERSION` and appending a migration here.
+pub const MIGRATIONS: &[Migration] = &[Migration {
+    from: 1,
+    description: "Rewrite bincode node and edge records in the zero-copy layout",
+    run: rewrite_legacy_records,
+}];
+
+/// Result of a single applied migration
+#[derive(Debug, Clone, PartialEq)]
+pub struct MigrationReport {
+    pub from: u32,
+    pub to: u32,
+    pub description: &'static str,
+    pub records_rewritten: usize,
+}
+
+/// Returns an error if the database was written by a newer build than this one
+pub fn check_supported(version: u32) -> Result<(), GraphError> {
+    if version > CURRENT_FORMAT_VERSION {
+        return Err(GraphError::MigrationError(format!(
+            "Database format version {} is newer than the latest supported version {}",
+            version, CURRENT_FORMAT_VERSION
+        )));
+    }
+    Ok(())
+}
+
+/// Applies every pending migration in order, updating the format version after each one
+/// so an interrupted run resumes from the last completed migration.
+pub fn migrate(storage: &HelixGraphStorage) -> Result<Vec<MigrationReport>, GraphError> {
+    let mut version = storage.format_version()?;
+    check_supported(version)?;
+
+    let mut reports = Vec::new();
+    while version < CURRENT_FORMAT_VERSION {
+        let migration = MIGRATIONS
+            .iter()
+            .find(|m| m.from == version)
+            .ok_or_else(|| {
+                GraphError::MigrationError(format!("No migration from format version {}", version))
+            })?;
+        let records_rewritten = (migration.run)(storage)?;
+        storage.set_format_version(version + 1)?;
+        reports.push(MigrationReport {
+            from: version,
+            to: version + 1,
+            description: migration.description,
+            records_rewritten,
+        });
+        version += 1;
+    }
+    Ok(reports)
+}
+
+/// 1 -> 2: rewrites records that are still serialized with bincode
+fn rewrite_legacy_records(storage: &HelixGraphStorage) -> Result<usize, GraphError> {
+    storage.rewrite_records(|kind, data| {
+        if !record::is_legacy(data) {
+            return Ok(None);
+        }
+        Ok(Some(match kind {
+            RecordKind::Node => Node::decode(data)?.encode(),
+            RecordKind::Edge => Edge::decode(data)?.encode(),
+        }))
+    })
+}
@@ -1,2 +1,5 @@
 pub mod storage_core;
-pub mod storage_methods;
\ No newline at end of file
+pub mod storage_methods;
+pub mod encryption;
+pub mod migrations;
+pub mod record;
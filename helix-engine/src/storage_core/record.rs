@@ -0,0 +1,655 @@
+use bincode::deserialize;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+use crate::types::GraphError;
+use protocol::{value::Value, Edge, Node};
+
+/// Marker byte written at the start of every record in the zero-copy layout.
+/// Legacy bincode records start with the length of the id string (36 for uuids),
+/// so records without this marker are decoded with bincode.
+pub const RECORD_MAGIC: u8 = 0xFD;
+
+/// Version of the zero-copy record layout written by this build
+pub const RECORD_VERSION: u8 = 1;
+
+const KIND_NODE: u8 = b'n';
+const KIND_EDGE: u8 = b'e';
+
+const HEADER_LEN: usize = 3;
+
+// Value tags
+const TAG_EMPTY: u8 = 0;
+const TAG_NULL: u8 = 1;
+const TAG_BOOLEAN: u8 = 2;
+const TAG_INTEGER: u8 = 3;
+const TAG_I64: u8 = 4;
+const TAG_U64: u8 = 5;
+const TAG_FLOAT: u8 = 6;
+const TAG_TIMESTAMP: u8 = 7;
+const TAG_STRING: u8 = 8;
+const TAG_BYTES: u8 = 9;
+const TAG_ARRAY: u8 = 10;
+const TAG_MAP: u8 = 11;
+
+/// Node and edge records that can be written to and read from storage.
+///
+/// Records are written in the zero-copy layout:
+///
+/// ```text
+/// node: [RECORD_MAGIC][RECORD_VERSION]['n'][id][label][properties]
+/// edge: [RECORD_MAGIC][RECORD_VERSION]['e'][id][label][from_node][to_node][properties]
+///
+/// string:     [len u32][utf8 bytes]
+/// properties: [count u32][offset u32; count][entry; count]  entries sorted by key
+/// entry:      [key string][value]
+/// value:      [tag u8][payload]
+/// ```
+///
+/// All integers are little endian. Property offsets are relative to the first entry
+/// so a property can be found with a binary search without decoding the others.
+pub trait Record: Sized {
+    fn encode(&self) -> Vec<u8>;
+    fn decode(data: &[u8]) -> Result<Self, GraphError>;
+}
+
+impl Record for Node {
+    fn encode(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(64 + self.properties.len() * 32);
+        out.extend_from_slice(&[RECORD_MAGIC, RECORD_VERSION, KIND_NODE]);
+        write_str(&mut out, &self.id);
+        write_str(&mut out, &self.label);
+        write_properties(&mut out, &self.properties);
+        out
+    }
+
+    fn decode(data: &[u8]) -> Result<Node, GraphError> {
+        if is_legacy(data) {
+            return Ok(deserialize(data)?);
+        }
+        Ok(NodeRef::parse(data)?.to_node())
+    }
+}
+
+impl Record for Edge {
+    fn encode(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(128 + self.properties.len() * 32);
+        out.extend_from_slice(&[RECORD_MAGIC, RECORD_VERSION, KIND_EDGE]);
+        write_str(&mut out, &self.id);
+        write_str(&mut out, &self.label);
+        write_str(&mut out, &self.from_node);
+        write_str(&mut out, &self.to_node);
+        write_properties(&mut out, &self.properties);
+        out
+    }
+
+    fn decode(data: &[u8]) -> Result<Edge, GraphError> {
+        if is_legacy(data) {
+            return Ok(deserialize(data)?);
+        }
+        Ok(EdgeRef::parse(data)?.to_edge())
+    }
+}
+
+/// Returns true if the record was written with bincode before the zero-copy layout existed
+#[inline]
+pub fn is_legacy(data: &[u8]) -> bool {
+    data.first() != Some(&RECORD_MAGIC)
+}
+
+/// Version tag of a record, legacy bincode records are version 0
+#[inline]
+pub fn record_version(data: &[u8]) -> u8 {
+    match is_legacy(data) {
+        true => 0,
+        false => data.get(1).copied().unwrap_or(0),
+    }
+}
+
+/// The type of record stored under a key
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecordKind {
+    Node,
+    Edge,
+}
+
+/// Borrowed view of a node record. Accessors read directly from the underlying bytes.
+#[derive(Clone, Copy, Debug)]
+pub struct NodeRef<'a> {
+    id: &'a str,
+    label: &'a str,
+    properties: PropertiesRef<'a>,
+}
+
+impl<'a> NodeRef<'a> {
+    /// Validates a node record in the zero-copy layout and returns a view over it
+    pub fn parse(data: &'a [u8]) -> Result<NodeRef<'a>, GraphError> {
+        let mut reader = Reader::new(check_header(data, KIND_NODE)?);
+        let node = (|| {
+            Some(NodeRef {
+                id: reader.str()?,
+                label: reader.str()?,
+                properties: PropertiesRef::parse(&mut reader)?,
+            })
+        })();
+        node.ok_or_else(corrupt)
+    }
+
+    #[inline]
+    pub fn id(&self) -> &'a str {
+        self.id
+    }
+
+    #[inline]
+    pub fn label(&self) -> &'a str {
+        self.label
+    }
+
+    /// Looks up a property without decoding the rest of the record
+    #[inline]
+    pub fn check_property(&self, key: &str) -> Option<ValueRef<'a>> {
+        self.properties.get(key)
+    }
+
+    /// Iterates over the properties in key order
+    #[inline]
+    pub fn properties(&self) -> PropertiesIter<'a> {
+        self.properties.iter()
+    }
+
+    /// Copies the record into an owned `Node`
+    pub fn to_node(&self) -> Node {
+        Node {
+            id: self.id.to_string(),
+            label: self.label.to_string(),
+            properties: self.properties.to_map(),
+        }
+    }
+}
+
+/// Borrowed view of an edge record. Accessors read directly from the underlying bytes.
+#[derive(Clone, Copy, Debug)]
+pub struct EdgeRef<'a> {
+    id: &'a str,
+    label: &'a str,
+    from_node: &'a str,
+    to_node: &'a str,
+    properties: PropertiesRef<'a>,
+}
+
+impl<'a> EdgeRef<'a> {
+    /// Validates an edge record in the zero-copy layout and returns a view over it
+    pub fn parse(data: &'a [u8]) -> Result<EdgeRef<'a>, GraphError> {
+        let mut reader = Reader::new(check_header(data, KIND_EDGE)?);
+        let edge = (|| {
+            Some(EdgeRef {
+                id: reader.str()?,
+                label: reader.str()?,
+                from_node: reader.str()?,
+                to_node: reader.str()?,
+                properties: PropertiesRef::parse(&mut reader)?,
+            })
+        })();
+        edge.ok_or_else(corrupt)
+    }
+
+    #[inline]
+    pub fn id(&self) -> &'a str {
+        self.id
+    }
+
+    #[inline]
+    pub fn label(&self) -> &'a str {
+        self.label
+    }
+
+    #[inline]
+    pub fn from_node(&self) -> &'a str {
+        self.from_node
+    }
+
+    #[inline]
+    pub fn to_node(&self) -> &'a str {
+        self.to_node
+    }
+
+    /// Looks up a property without decoding the rest of the record
+    #[inline]
+    pub fn check_property(&self, key: &str) -> Option<ValueRef<'a>> {
+        self.properties.get(key)
+    }
+
+    /// Iterates over the properties in key order
+    #[inline]
+    pub fn properties(&self) -> PropertiesIter<'a> {
+        self.properties.iter()
+    }
+
+    /// Copies the record into an owned `Edge`
+    pub fn to_edge(&self) -> Edge {
+        Edge {
+            id: self.id.to_string(),
+            label: self.label.to_string(),
+            from_node: self.from_node.to_string(),
+            to_node: self.to_node.to_string(),
+            properties: self.properties.to_map(),
+        }
+    }
+}
+
+/// Borrowed view of a property value
+#[derive(Clone, Copy, Debug)]
+pub enum ValueRef<'a> {
+    Empty,
+    Null,
+    Boolean(bool),
+    Integer(i32),
+    I64(i64),
+    U64(u64),
+    Float(f64),
+    Timestamp(i64),
+    String(&'a str),
+    Bytes(&'a [u8]),
+    Array(ArrayRef<'a>),
+    Map(MapRef<'a>),
+}
+
+impl<'a> ValueRef<'a> {
+    /// Copies the value into an owned `Value`
+    pub fn to_value(&self) -> Value {
+        match *self {
+            ValueRef::Empty => Value::Empty,
+            ValueRef::Null => Value::Null,
+            ValueRef::Boolean(b) => Value::Boolean(b),
+            ValueRef::Integer(i) => Value::Integer(i),
+            ValueRef::I64(i) => Value::I64(i),
+            ValueRef::U64(u) => Value::U64(u),
+            ValueRef::Float(f) => Value::Float(f),
+            ValueRef::Timestamp(t) => Value::Timestamp(t),
+            ValueRef::String(s) => Value::String(s.to_string()),
+            ValueRef::Bytes(b) => Value::Bytes(b.to_vec()),
+            ValueRef::Array(arr) => Value::Array(arr.iter().map(|v| v.to_value()).collect()),
+            ValueRef::Map(map) => Value::Map(
+                map.iter()
+                    .map(|(k, v)| (k.to_string(), v.to_value()))
+                    .collect(),
+            ),
+        }
+    }
+
+    /// Returns the value as an f64 if it is a number
+    #[inline]
+    pub fn as_f64(&self) -> Option<f64> {
+        match *self {
+            ValueRef::Integer(i) => Some(i as f64),
+            ValueRef::I64(i) => Some(i as f64),
+            ValueRef::U64(u) => Some(u as f64),
+            ValueRef::Float(f) => Some(f),
+            _ => None,
+        }
+    }
+
+    #[inline]
+    pub fn as_str(&self) -> Option<&'a str> {
+        match *self {
+            ValueRef::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// Compares with an owned value using the total ordering of `Value`.
+    /// Scalars and strings are compared without allocating.
+    pub fn cmp_value(&self, other: &Value) -> Ordering {
+        match (*self, other) {
+            (ValueRef::String(a), Value::String(b)) => a.cmp(b.as_str()),
+            (ValueRef::Bytes(a), Value::Bytes(b)) => a.cmp(b.as_slice()),
+            (ValueRef::Array(_), _) | (ValueRef::Map(_), _) => self.to_value().cmp(other),
+            // different types only compare by type rank, so the contents are irrelevant
+            (ValueRef::String(_), _) => Value::String(String::new()).cmp(other),
+            (ValueRef::Bytes(_), _) => Value::Bytes(Vec::new()).cmp(other),
+            (scalar, _) => scalar.to_value().cmp(other),
+        }
+    }
+}
+
+impl PartialEq<Value> for ValueRef<'_> {
+    #[inline]
+    fn eq(&self, other: &Value) -> bool {
+        self.cmp_value(other) == Ordering::Equal
+    }
+}
+
+/// Borrowed view of an array value
+#[derive(Clone, Copy, Debug)]
+pub struct ArrayRef<'a> {
+    len: u32,
+    data: &'a [u8],
+}
+
+impl<'a> ArrayRef<'a> {
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len as usize
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = ValueRef<'a>> {
+        let mut reader = Reader::new(self.data);
+        (0..self.len).map_while(move |_| reader.value())
+    }
+}
+
+/// Borrowed view of a map value
+#[derive(Clone, Copy, Debug)]
+pub struct MapRef<'a> {
+    len: u32,
+    data: &'a [u8],
+}
+
+impl<'a> MapRef<'a> {
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len as usize
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn get(&self, key: &str) -> Option<ValueRef<'a>> {
+        self.iter().find(|(k, _)| *k == key).map(|(_, v)| v)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&'a str, ValueRef<'a>)> {
+        let mut reader = Reader::new(self.data);
+        (0..self.len).map_while(move |_| Some((reader.str()?, reader.value()?)))
+    }
+}
+
+/// Sorted property table of a record
+#[derive(Clone, Copy, Debug)]
+struct PropertiesRef<'a> {
+    offsets: &'a [u8],
+    entries: &'a [u8],
+}
+
+impl<'a> PropertiesRef<'a> {
+    /// Reads the property table and validates every entry
+    fn parse(reader: &mut Reader<'a>) -> Option<PropertiesRef<'a>> {
+        let count = reader.u32()? as usize;
+        let offsets = reader.take(count.checked_mul(4)?)?;
+        let entries = reader.rest();
+        let properties = PropertiesRef { offsets, entries };
+
+        let mut previous: Option<&str> = None;
+        for i in 0..count {
+            let mut entry = Reader::new(entries.get(properties.offset(i)?..)?);
+            let key = entry.str()?;
+            entry.value()?;
+            if previous.is_some_and(|p| p >= key) {
+                return None;
+            }
+            previous = Some(key);
+        }
+        Some(properties)
+    }
+
+    #[inline]
+    fn len(&self) -> usize {
+        self.offsets.len() / 4
+    }
+
+    #[inline]
+    fn offset(&self, i: usize) -> Option<usize> {
+        let bytes = self.offsets.get(i * 4..i * 4 + 4)?;
+        Some(u32::from_le_bytes(bytes.try_into().ok()?) as usize)
+    }
+
+    #[inline]
+    fn entry(&self, i: usize) -> Option<(&'a str, ValueRef<'a>)> {
+        let mut reader = Reader::new(self.entries.get(self.offset(i)?..)?);
+        Some((reader.str()?, reader.value()?))
+    }
+
+    fn get(&self, key: &str) -> Option<ValueRef<'a>> {
+        let (mut low, mut high) = (0, self.len());
+        while low < high {
+            let mid = low + (high - low) / 2;
+            let mut reader = Reader::new(self.entries.get(self.offset(mid)?..)?);
+            match reader.str()?.cmp(key) {
+                Ordering::Equal => return reader.value(),
+                Ordering::Less => low = mid + 1,
+                Ordering::Greater => high = mid,
+            }
+        }
+        None
+    }
+
+    #[inline]
+    fn iter(&self) -> PropertiesIter<'a> {
+        PropertiesIter {
+            properties: *self,
+            index: 0,
+        }
+    }
+
+    fn to_map(self) -> HashMap<String, Value> {
+        let mut map = HashMap::with_capacity(self.len());
+        for (key, value) in self.iter() {
+            map.insert(key.to_string(), value.to_value());
+        }
+        map
+    }
+}
+
+/// Iterator over the properties of a record in key order
+pub struct PropertiesIter<'a> {
+    properties: PropertiesRef<'a>,
+    index: usize,
+}
+
+impl<'a> Iterator for PropertiesIter<'a> {
+    type Item = (&'a str, ValueRef<'a>);
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.index >= self.properties.len() {
+            return None;
+        }
+        self.index += 1;
+        self.properties.entry(self.index - 1)
+    }
+}
+
+#[inline]
+fn corrupt() -> GraphError {
+    GraphError::ConversionError("Corrupt record".to_string())
+}
+
+fn check_header(data: &[u8], kind: u8) -> Result<&[u8], GraphError> {
+    if data.len() < HEADER_LEN || data[0] != RECORD_MAGIC {
+        return Err(GraphError::ConversionError(
+            "Record is not in the zero-copy layout".to_string(),
+        ));
+    }
+    if data[1] != RECORD_VERSION {
+        return Err(GraphError::ConversionError(format!(
+            "Unsupported record version {}",
+            data[1]
+        )));
+    }
+    if data[2] != kind {
+        return Err(GraphError::ConversionError(format!(
+            "Expected a {} record",
+            if kind == KIND_NODE { "node" } else { "edge" }
+        )));
+    }
+    Ok(&data[HEADER_LEN..])
+}
+
+/// Bounds checked cursor over a record
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    #[inline]
+    fn new(data: &'a [u8]) -> Reader<'a> {
+        Reader { data, pos: 0 }
+    }
+
+    #[inline]
+    fn take(&mut self, n: usize) -> Option<&'a [u8]> {
+        let bytes = self.data.get(self.pos..self.pos.checked_add(n)?)?;
+        self.pos += n;
+        Some(bytes)
+    }
+
+    #[inline]
+    fn rest(&self) -> &'a [u8] {
+        &self.data[self.pos..]
+    }
+
+    #[inline]
+    fn array<const N: usize>(&mut self) -> Option<[u8; N]> {
+        self.take(N)?.try_into().ok()
+    }
+
+    #[inline]
+    fn u32(&mut self) -> Option<u32> {
+        Some(u32::from_le_bytes(self.array()?))
+    }
+
+    #[inline]
+    fn str(&mut self) -> Option<&'a str> {
+        let len = self.u32()? as usize;
+        std::str::from_utf8(self.take(len)?).ok()
+    }
+
+    /// Reads a value, skipping over nested arrays and maps
+    fn value(&mut self) -> Option<ValueRef<'a>> {
+        let value = match self.take(1)?[0] {
+            TAG_EMPTY => ValueRef::Empty,
+            TAG_NULL => ValueRef::Null,
+            TAG_BOOLEAN => ValueRef::Boolean(self.take(1)?[0] != 0),
+            TAG_INTEGER => ValueRef::Integer(i32::from_le_bytes(self.array()?)),
+            TAG_I64 => ValueRef::I64(i64::from_le_bytes(self.array()?)),
+            TAG_U64 => ValueRef::U64(u64::from_le_bytes(self.array()?)),
+            TAG_FLOAT => ValueRef::Float(f64::from_le_bytes(self.array()?)),
+            TAG_TIMESTAMP => ValueRef::Timestamp(i64::from_le_bytes(self.array()?)),
+            TAG_STRING => ValueRef::String(self.str()?),
+            TAG_BYTES => {
+                let len = self.u32()? as usize;
+                ValueRef::Bytes(self.take(len)?)
+            }
+            TAG_ARRAY => {
+                let len = self.u32()?;
+                let start = self.pos;
+                for _ in 0..len {
+                    self.value()?;
+                }
+                ValueRef::Array(ArrayRef {
+                    len,
+                    data: &self.data[start..self.pos],
+                })
+            }
+            TAG_MAP => {
+                let len = self.u32()?;
+                let start = self.pos;
+                for _ in 0..len {
+                    self.str()?;
+                    self.value()?;
+                }
+                ValueRef::Map(MapRef {
+                    len,
+                    data: &self.data[start..self.pos],
+                })
+            }
+            _ => return None,
+        };
+        Some(value)
+    }
+}
+
+#[inline]
+fn write_str(out: &mut Vec<u8>, s: &str) {
+    out.extend_from_slice(&(s.len() as u32).to_le_bytes());
+    out.extend_from_slice(s.as_bytes());
+}
+
+fn write_properties(out: &mut Vec<u8>, properties: &HashMap<String, Value>) {
+    let mut entries: Vec<(&String, &Value)> = properties.iter().collect();
+    entries.sort_unstable_by(|a, b| a.0.cmp(b.0));
+
+    out.extend_from_slice(&(entries.len() as u32).to_le_bytes());
+    let offsets_start = out.len();
+    out.resize(offsets_start + entries.len() * 4, 0);
+    let entries_start = out.len();
+
+    for (i, (key, value)) in entries.into_iter().enumerate() {
+        let offset = ((out.len() - entries_start) as u32).to_le_bytes();
+        out[offsets_start + i * 4..offsets_start + i * 4 + 4].copy_from_slice(&offset);
+        write_str(out, key);
+        write_value(out, value);
+    }
+}
+
+fn write_value(out: &mut Vec<u8>, value: &Value) {
+    match value {
+        Value::Empty => out.push(TAG_EMPTY),
+        Value::Null => out.push(TAG_NULL),
+        Value::Boolean(b) => out.extend_from_slice(&[TAG_BOOLEAN, *b as u8]),
+        Value::Integer(i) => {
+            out.push(TAG_INTEGER);
+            out.extend_from_slice(&i.to_le_bytes());
+        }
+        Value::I64(i) => {
+            out.push(TAG_I64);
+            out.extend_from_slice(&i.to_le_bytes());
+        }
+        Value::U64(u) => {
+            out.push(TAG_U64);
+            out.extend_from_slice(&u.to_le_bytes());
+        }
+        Value::Float(f) => {
+            out.push(TAG_FLOAT);
+            out.extend_from_slice(&f.to_le_bytes());
+        }
+        Value::Timestamp(t) => {
+            out.push(TAG_TIMESTAMP);
+            out.extend_from_slice(&t.to_le_bytes());
+        }
+        Value::String(s) => {
+            out.push(TAG_STRING);
+            write_str(out, s);
+        }
+        Value::Bytes(b) => {
+            out.push(TAG_BYTES);
+            out.extend_from_slice(&(b.len() as u32).to_le_bytes());
+            out.extend_from_slice(b);
+        }
+        Value::Array(arr) => {
+            out.push(TAG_ARRAY);
+            out.extend_from_slice(&(arr.len() as u32).to_le_bytes());
+            for v in arr {
+                write_value(out, v);
+            }
+        }
+        Value::Map(map) => {
+            out.push(TAG_MAP);
+            out.extend_from_slice(&(map.len() as u32).to_le_bytes());
+            let mut entries: Vec<(&String, &Value)> = map.iter().collect();
+            entries.sort_unstable_by(|a, b| a.0.cmp(b.0));
+            for (k, v) in entries {
+                write_str(out, k);
+                write_value(out, v);
+            }
+        }
+    }
+}
@@ -1,16 +1,24 @@
-use bincode::{deserialize, serialize};
 use rocksdb::{
     BlockBasedOptions, Cache, ColumnFamilyDescriptor, DBCompactionStyle, DBCompressionType,
     IteratorMode, Options, ReadOptions, WriteBatch, WriteBatchWithTransaction, WriteOptions, DB,
 };
 
 use std::borrow::Cow;
-use std::collections::{HashMap, HashSet, VecDeque};
-use std::ops::Deref;
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
 
 use uuid::Uuid;
 
-use crate::storage_core::storage_methods::{SearchMethods, StorageMethods};
+use crate::storage_core::encryption::StorageEncryption;
+use crate::storage_core::migrations::{
+    self, MigrationReport, CURRENT_FORMAT_VERSION, LEGACY_FORMAT_VERSION,
+};
+use crate::storage_core::record::{self, EdgeRef, NodeRef, Record, RecordKind};
+use crate::storage_core::storage_methods::{
+    EdgeDirection, PathOptions, SearchMethods, StorageMethods,
+};
 use crate::types::GraphError;
 use protocol::{value::Value, Edge, Node};
 use rayon::*;
@@ -38,13 +46,51 @@ const RAH_MEDIUM: usize = 4 * 1024 * 1024;
 const RAH_LARGE: usize = 8 * 1024 * 1024;
 const RAH_XLARGE: usize = 24 * 1024 * 1024;
 
+const REWRITE_BATCH_SIZE: usize = 10000;
+
+// Key in the default column family holding the on-disk format version
+const FORMAT_VERSION_KEY: &[u8] = b"helix:format_version";
+
 pub struct HelixGraphStorage {
     db: DB,
+    encryption: Option<StorageEncryption>,
 }
 
 impl HelixGraphStorage {
-    /// HelixGraphStorage struct constructor
+    /// HelixGraphStorage struct constructor.
+    ///
+    /// Databases written in an older on-disk format are migrated when opened.
     pub fn new(path: &str) -> Result<HelixGraphStorage, GraphError> {
+        let storage = Self::open(path, None)?;
+        storage.migrate()?;
+        Ok(storage)
+    }
+
+    /// Creates a HelixGraphStorage that encrypts node and edge records at rest
+    pub fn new_encrypted(
+        path: &str,
+        encryption: StorageEncryption,
+    ) -> Result<HelixGraphStorage, GraphError> {
+        let storage = Self::open(path, Some(encryption))?;
+        storage.migrate()?;
+        Ok(storage)
+    }
+
+    /// Opens a database without applying pending migrations,
+    /// so tools can inspect the format version and call `migrate` themselves.
+    pub fn open_without_migrating(
+        path: &str,
+        encryption: Option<StorageEncryption>,
+    ) -> Result<HelixGraphStorage, GraphError> {
+        let storage = Self::open(path, encryption)?;
+        migrations::check_supported(storage.format_version()?)?;
+        Ok(storage)
+    }
+
+    fn open(
+        path: &str,
+        encryption: Option<StorageEncryption>,
+    ) -> Result<HelixGraphStorage, GraphError> {
         let mut opts = Options::default();
         opts.create_if_missing(true);
         let mut opts = Options::default();
@@ -139,7 +185,282 @@ impl HelixGraphStorage {
         )?;
 
         drop(cf_edges);
-        Ok(Self { db })
+        let storage = Self { db, encryption };
+        storage.init_format_version()?;
+        Ok(storage)
+    }
+
+    /// Writes the current format version to new databases.
+    /// Existing databases without the key were written before it existed.
+    fn init_format_version(&self) -> Result<(), GraphError> {
+        if self.db.get(FORMAT_VERSION_KEY)?.is_some() {
+            return Ok(());
+        }
+        if self.has_records()? {
+            return Ok(());
+        }
+        self.set_format_version(CURRENT_FORMAT_VERSION)
+    }
+
+    fn has_records(&self) -> Result<bool, GraphError> {
+        for cf_name in [CF_NODES, CF_EDGES] {
+            let cf = self
+                .db
+                .cf_handle(cf_name)
+                .ok_or(GraphError::from("Column Family not found"))?;
+            if self
+                .db
+                .iterator_cf(&cf, IteratorMode::Start)
+                .next()
+                .is_some()
+            {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    /// On-disk format version of the database
+    pub fn format_version(&self) -> Result<u32, GraphError> {
+        match self.db.get(FORMAT_VERSION_KEY)? {
+            Some(data) => {
+                let bytes: [u8; 4] = data.as_slice().try_into().map_err(|_| {
+                    GraphError::MigrationError("Invalid format version".to_string())
+                })?;
+                Ok(u32::from_le_bytes(bytes))
+            }
+            None => Ok(LEGACY_FORMAT_VERSION),
+        }
+    }
+
+    pub(crate) fn set_format_version(&self, version: u32) -> Result<(), GraphError> {
+        Ok(self.db.put(FORMAT_VERSION_KEY, version.to_le_bytes())?)
+    }
+
+    /// Applies any pending on-disk format migrations
+    pub fn migrate(&self) -> Result<Vec<MigrationReport>, GraphError> {
+        migrations::migrate(self)
+    }
+
+    /// Rewrites node and edge records in place.
+    ///
+    /// `f` is called with the decrypted contents of each record and returns the new contents,
+    /// or `None` to leave the record unchanged. Returns the number of records that were rewritten.
+    pub(crate) fn rewrite_records<F>(&self, f: F) -> Result<usize, GraphError>
+    where
+        F: Fn(RecordKind, &[u8]) -> Result<Option<Vec<u8>>, GraphError>,
+    {
+        let mut rewritten = 0;
+        for (cf_name, prefix, kind) in [
+            (CF_NODES, NODE_PREFIX, RecordKind::Node),
+            (CF_EDGES, EDGE_PREFIX, RecordKind::Edge),
+        ] {
+            let cf = self
+                .db
+                .cf_handle(cf_name)
+                .ok_or(GraphError::from("Column Family not found"))?;
+            let iter = self.db.iterator_cf_opt(
+                &cf,
+                Self::get_optimized_read_options(RAH_LARGE),
+                IteratorMode::From(prefix, rocksdb::Direction::Forward),
+            );
+
+            let mut batch = WriteBatch::default();
+            for result in iter {
+                let (key, value) = result?;
+                if !key.starts_with(prefix) {
+                    break;
+                }
+                if value.is_empty() {
+                    continue;
+                }
+                let data = match &self.encryption {
+                    Some(encryption) => Cow::Owned(encryption.decrypt_or_plaintext(&key, &value)?),
+                    None => Cow::Borrowed(&value[..]),
+                };
+                let Some(new_data) = f(kind, &data)? else {
+                    continue;
+                };
+                let new_data = match &self.encryption {
+                    Some(encryption) => encryption.encrypt(&key, &new_data)?,
+                    None => new_data,
+                };
+                batch.put_cf(&cf, &key, new_data);
+                rewritten += 1;
+
+                if batch.len() >= REWRITE_BATCH_SIZE {
+                    self.db.write(std::mem::take(&mut batch))?;
+                }
+            }
+            if !batch.is_empty() {
+                self.db.write(batch)?;
+            }
+        }
+        Ok(rewritten)
+    }
+
+    /// Calls `f` with a borrowed view of every node in the graph
+    pub(crate) fn for_each_node_ref<F>(&self, mut f: F) -> Result<(), GraphError>
+    where
+        F: FnMut(NodeRef<'_>) -> Result<(), GraphError>,
+    {
+        self.scan_records::<Node>(CF_NODES, NODE_PREFIX, |data| f(NodeRef::parse(data)?))
+    }
+
+    fn scan_records<T: Record>(
+        &self,
+        cf_name: &str,
+        prefix: &[u8],
+        mut f: impl FnMut(&[u8]) -> Result<(), GraphError>,
+    ) -> Result<(), GraphError> {
+        let cf = self
+            .db
+            .cf_handle(cf_name)
+            .ok_or(GraphError::from("Column Family not found"))?;
+        let iter = self.db.iterator_cf_opt(
+            &cf,
+            Self::get_optimized_read_options(RAH_LARGE),
+            IteratorMode::From(prefix, rocksdb::Direction::Forward),
+        );
+        for result in iter {
+            let (key, value) = result?;
+            if !key.starts_with(prefix) {
+                break;
+            }
+            if value.is_empty() {
+                continue;
+            }
+            f(&self.view_record::<T>(&key, &value)?)?;
+        }
+        Ok(())
+    }
+
+    /// Sets `property` to the given value on each node, writing in batches.
+    ///
+    /// Used to write algorithm results back to the graph. Nodes that no longer exist are skipped.
+    /// Returns the number of nodes that were updated.
+    pub fn set_node_property<I>(&self, property: &str, values: I) -> Result<usize, GraphError>
+    where
+        I: IntoIterator<Item = (String, Value)>,
+    {
+        let cf_nodes = self
+            .db
+            .cf_handle(CF_NODES)
+            .ok_or(GraphError::from("Column Family not found"))?;
+
+        let mut updated = 0;
+        let mut batch = WriteBatch::default();
+        for (node_id, value) in values {
+            let node_key = Self::node_key(&node_id);
+            let mut node = match self.db.get_pinned_cf(&cf_nodes, &node_key)? {
+                Some(data) => self.decode_record::<Node>(&node_key, &data)?,
+                None => continue,
+            };
+            node.properties.insert(property.to_string(), value);
+            batch.put_cf(&cf_nodes, &node_key, self.encode_record(&node_key, &node)?);
+            updated += 1;
+
+            if batch.len() >= REWRITE_BATCH_SIZE {
+                self.db.write(std::mem::take(&mut batch))?;
+            }
+        }
+        if !batch.is_empty() {
+            self.db.write(batch)?;
+        }
+        Ok(updated)
+    }
+
+    /// Serializes a record and encrypts it if encryption is enabled
+    #[inline(always)]
+    fn encode_record<T: Record>(&self, key: &[u8], value: &T) -> Result<Vec<u8>, GraphError> {
+        let data = value.encode();
+        match &self.encryption {
+            Some(encryption) => encryption.encrypt(key, &data),
+            None => Ok(data),
+        }
+    }
+
+    /// Decrypts a record if encryption is enabled and deserializes it
+    #[inline(always)]
+    fn decode_record<T: Record>(&self, key: &[u8], data: &[u8]) -> Result<T, GraphError> {
+        T::decode(&self.decrypt_record(key, data)?)
+    }
+
+    /// Decrypts a record if encryption is enabled, otherwise borrows the stored bytes
+    #[inline(always)]
+    fn decrypt_record<'a>(&self, key: &[u8], data: &'a [u8]) -> Result<Cow<'a, [u8]>, GraphError> {
+        match &self.encryption {
+            Some(encryption) => Ok(Cow::Owned(encryption.decrypt(key, data)?)),
+            None => Ok(Cow::Borrowed(data)),
+        }
+    }
+
+    /// Returns the record bytes in the zero-copy layout so they can be viewed without decoding.
+    /// Legacy bincode records are converted, which copies them.
+    #[inline(always)]
+    fn view_record<'a, T: Record>(
+        &self,
+        key: &[u8],
+        data: &'a [u8],
+    ) -> Result<Cow<'a, [u8]>, GraphError> {
+        let data = self.decrypt_record(key, data)?;
+        match record::is_legacy(&data) {
+            true => Ok(Cow::Owned(T::decode(&data)?.encode())),
+            false => Ok(data),
+        }
+    }
+
+    /// Re-encrypts every node and edge record that is not encrypted with the active key.
+    ///
+    /// Used after a key rotation, or to encrypt a database that was previously stored in plaintext.
+    /// Returns the number of records that were rewritten.
+    pub fn reencrypt_all(&self) -> Result<usize, GraphError> {
+        let encryption = self
+            .encryption
+            .as_ref()
+            .ok_or_else(|| GraphError::EncryptionError("Encryption is not enabled".to_string()))?;
+
+        let mut rewritten = 0;
+        for (cf_name, prefix) in [(CF_NODES, NODE_PREFIX), (CF_EDGES, EDGE_PREFIX)] {
+            let cf = self
+                .db
+                .cf_handle(cf_name)
+                .ok_or(GraphError::from("Column Family not found"))?;
+            let iter = self.db.iterator_cf_opt(
+                &cf,
+                Self::get_optimized_read_options(RAH_LARGE),
+                IteratorMode::From(prefix, rocksdb::Direction::Forward),
+            );
+
+            let mut batch = WriteBatch::default();
+            for result in iter {
+                let (key, value) = result?;
+                if !key.starts_with(prefix) {
+                    break;
+                }
+                if value.is_empty() || !encryption.needs_reencryption(&value) {
+                    continue;
+                }
+                let plaintext = encryption.decrypt_or_plaintext(&key, &value)?;
+                batch.put_cf(&cf, &key, encryption.encrypt(&key, &plaintext)?);
+                rewritten += 1;
+
+                if batch.len() >= REWRITE_BATCH_SIZE {
+                    self.db.write(std::mem::take(&mut batch))?;
+                }
+            }
+            if !batch.is_empty() {
+                self.db.write(batch)?;
+            }
+        }
+        Ok(rewritten)
+    }
+
+    /// Runs `reencrypt_all` on a background thread
+    pub fn spawn_reencryption(
+        storage: Arc<HelixGraphStorage>,
+    ) -> JoinHandle<Result<usize, GraphError>> {
+        thread::spawn(move || storage.reencrypt_all())
     }
     #[inline]
     fn get_optimized_read_options(rah_size: usize) -> ReadOptions {
@@ -206,6 +527,388 @@ impl HelixGraphStorage {
     }
 }
 
+impl HelixGraphStorage {
+    /// Calls `f` for every edge adjacent to `node_id` in the given direction with a label in `edge_labels`
+    /// (or any label if it is empty), along with the id of the node at the other end of the edge.
+    pub(crate) fn for_each_adjacent_edge<F>(
+        &self,
+        node_id: &str,
+        direction: EdgeDirection,
+        edge_labels: &[String],
+        mut f: F,
+    ) -> Result<(), GraphError>
+    where
+        F: FnMut(&str, EdgeRef<'_>) -> Result<(), GraphError>,
+    {
+        let cf_indices = self
+            .db
+            .cf_handle(CF_INDICES)
+            .ok_or(GraphError::from("Column Family not found"))?;
+        let cf_edges = self
+            .db
+            .cf_handle(CF_EDGES)
+            .ok_or(GraphError::from("Column Family not found"))?;
+
+        let prefixes = match direction {
+            EdgeDirection::Out => vec![(Self::out_edge_key(node_id, ""), true)],
+            EdgeDirection::In => vec![(Self::in_edge_key(node_id, ""), false)],
+            EdgeDirection::Both => vec![
+                (Self::out_edge_key(node_id, ""), true),
+                (Self::in_edge_key(node_id, ""), false),
+            ],
+        };
+
+        for (prefix, outgoing) in prefixes {
+            let iter = self.db.iterator_cf_opt(
+                &cf_indices,
+                Self::get_optimized_read_options(RAH_SMALL),
+                IteratorMode::From(&prefix, rocksdb::Direction::Forward),
+            );
+            for result in iter {
+                let (key, _) = result?;
+                if !key.starts_with(&prefix) {
+                    break;
+                }
+                let edge_key = [EDGE_PREFIX, &key[prefix.len()..]].concat();
+                let data = match self.db.get_pinned_cf(&cf_edges, &edge_key)? {
+                    Some(data) => data,
+                    None => continue,
+                };
+                let data = self.view_record::<Edge>(&edge_key, &data)?;
+                let edge = EdgeRef::parse(&data)?;
+                if !edge_labels.is_empty() && !edge_labels.iter().any(|l| l == edge.label()) {
+                    continue;
+                }
+                let other = if outgoing {
+                    edge.to_node()
+                } else {
+                    edge.from_node()
+                };
+                f(other, edge)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Decodes the edges adjacent to `node_id` with the given label, or any label if it is empty.
+    /// Edges with other labels are skipped without being decoded.
+    fn adjacent_edges(
+        &self,
+        node_id: &str,
+        direction: EdgeDirection,
+        edge_label: &str,
+    ) -> Result<Vec<Edge>, GraphError> {
+        let mut edges = Vec::with_capacity(20);
+        self.for_each_adjacent_edge(
+            node_id,
+            direction,
+            &Self::label_filter(edge_label),
+            |_, edge| {
+                edges.push(edge.to_edge());
+                Ok(())
+            },
+        )?;
+        Ok(edges)
+    }
+
+    /// Returns the nodes at the other end of the edges adjacent to `node_id` for which `f` returns true.
+    ///
+    /// Edges and nodes are read as borrowed views, so only the nodes that pass `f` are decoded.
+    /// Nodes that no longer exist are skipped; any other error reading a node is returned.
+    fn filter_adjacent_nodes<F: Fn(NodeRef<'_>) -> bool>(
+        &self,
+        node_id: &str,
+        direction: EdgeDirection,
+        edge_label: &str,
+        f: F,
+    ) -> Result<Vec<Node>, GraphError> {
+        let mut nodes = Vec::with_capacity(20);
+        self.for_each_adjacent_edge(
+            node_id,
+            direction,
+            &Self::label_filter(edge_label),
+            |other, _| {
+                if let Some(Some(node)) =
+                    self.with_existing_node(other, |node| f(node).then(|| node.to_node()))?
+                {
+                    nodes.push(node);
+                }
+                Ok(())
+            },
+        )?;
+        Ok(nodes)
+    }
+
+    /// `with_temp_node` that returns `None` instead of an error when the node does not exist
+    fn with_existing_node<R, F: FnOnce(NodeRef<'_>) -> R>(
+        &self,
+        id: &str,
+        f: F,
+    ) -> Result<Option<R>, GraphError> {
+        let cf_nodes = self
+            .db
+            .cf_handle(CF_NODES)
+            .ok_or(GraphError::from("Column Family not found"))?;
+        let key = Self::node_key(id);
+        match self.db.get_pinned_cf(&cf_nodes, &key) {
+            Ok(Some(data)) => {
+                let data = self.view_record::<Node>(&key, &data)?;
+                Ok(Some(f(NodeRef::parse(&data)?)))
+            }
+            Ok(None) => Ok(None),
+            Err(err) => Err(GraphError::from(err)),
+        }
+    }
+
+    /// Edge labels in the form taken by `for_each_adjacent_edge`, where an empty label matches any edge
+    #[inline]
+    fn label_filter(edge_label: &str) -> Vec<String> {
+        match edge_label {
+            "" => vec![],
+            label => vec![label.to_string()],
+        }
+    }
+
+    /// Builds a path from `from_id` to `to_id` out of a map of each node to its predecessor and the connecting edge id
+    fn build_path(
+        &self,
+        parent: &HashMap<String, (String, String)>,
+        from_id: &str,
+        to_id: &str,
+    ) -> Result<(Vec<Node>, Vec<Edge>), GraphError> {
+        let mut nodes = Vec::new();
+        let mut edges = Vec::new();
+        let mut current = to_id;
+        while current != from_id {
+            let (prev, edge_id) = parent.get(current).ok_or_else(|| {
+                GraphError::TraversalError(format!("Broken path at node {}", current))
+            })?;
+            nodes.push(self.get_temp_node(current)?);
+            edges.push(self.get_temp_edge(edge_id)?);
+            current = prev;
+        }
+        nodes.push(self.get_temp_node(from_id)?);
+        nodes.reverse();
+        edges.reverse();
+        Ok((nodes, edges))
+    }
+
+    /// Best-first search used by Dijkstra (no heuristic) and A*
+    fn weighted_search(
+        &self,
+        from_id: &str,
+        to_id: &str,
+        weight_property: &str,
+        direction: EdgeDirection,
+        edge_labels: &[String],
+        heuristic: Option<&dyn Fn(NodeRef<'_>) -> f64>,
+    ) -> Result<(Vec<Node>, Vec<Edge>, f64), GraphError> {
+        let estimate = |node_id: &str| -> Result<f64, GraphError> {
+            match heuristic {
+                Some(h) => self.with_temp_node(node_id, h),
+                None => Ok(0.0),
+            }
+        };
+
+        let mut heap = BinaryHeap::new();
+        let mut costs: HashMap<String, f64> = HashMap::from([(from_id.to_string(), 0.0)]);
+        let mut parent: HashMap<String, (String, String)> = HashMap::new();
+        let mut settled: HashSet<String> = HashSet::new();
+
+        heap.push(SearchEntry {
+            priority: estimate(from_id)?,
+            cost: 0.0,
+            node_id: from_id.to_string(),
+        });
+
+        while let Some(SearchEntry { cost, node_id, .. }) = heap.pop() {
+            if node_id == to_id {
+                let (nodes, edges) = self.build_path(&parent, from_id, to_id)?;
+                return Ok((nodes, edges, cost));
+            }
+            if !settled.insert(node_id.clone()) {
+                continue;
+            }
+
+            self.for_each_adjacent_edge(&node_id, direction, edge_labels, |other, edge| {
+                let weight = edge
+                    .check_property(weight_property)
+                    .and_then(|v| v.as_f64())
+                    .ok_or_else(|| {
+                        GraphError::TraversalError(format!(
+                            "Edge {} has no numeric {} property",
+                            edge.id(),
+                            weight_property
+                        ))
+                    })?;
+                if weight.is_nan() || weight < 0.0 {
+                    return Err(GraphError::TraversalError(format!(
+                        "Edge {} has a negative {} of {}",
+                        edge.id(),
+                        weight_property,
+                        weight
+                    )));
+                }
+                if settled.contains(other) {
+                    return Ok(());
+                }
+                let new_cost = cost + weight;
+                if costs.get(other).is_some_and(|&c| c <= new_cost) {
+                    return Ok(());
+                }
+                costs.insert(other.to_string(), new_cost);
+                parent.insert(other.to_string(), (node_id.clone(), edge.id().to_string()));
+                heap.push(SearchEntry {
+                    priority: new_cost + estimate(other)?,
+                    cost: new_cost,
+                    node_id: other.to_string(),
+                });
+                Ok(())
+            })?;
+        }
+
+        Err(GraphError::from(format!(
+            "No path found between {} and {}",
+            from_id, to_id
+        )))
+    }
+
+    /// BFS over outgoing edges that never enters `excluded_nodes` or follows `excluded_edges`
+    fn bfs_id_path(
+        &self,
+        from_id: &str,
+        to_id: &str,
+        excluded_nodes: &HashSet<String>,
+        excluded_edges: &HashSet<String>,
+    ) -> Result<Option<IdPath>, GraphError> {
+        let mut parent: HashMap<String, (String, String)> = HashMap::new();
+        let mut frontier = vec![from_id.to_string()];
+        let mut found = from_id == to_id;
+
+        while !found && !frontier.is_empty() {
+            let mut next = Vec::new();
+            for node_id in frontier {
+                self.for_each_adjacent_edge(
+                    &node_id,
+                    EdgeDirection::Out,
+                    &[],
+                    |other_id, edge| {
+                        if found
+                            || other_id == from_id
+                            || parent.contains_key(other_id)
+                            || excluded_nodes.contains(other_id)
+                            || excluded_edges.contains(edge.id())
+                        {
+                            return Ok(());
+                        }
+                        parent.insert(
+                            other_id.to_string(),
+                            (node_id.clone(), edge.id().to_string()),
+                        );
+                        found = other_id == to_id;
+                        next.push(other_id.to_string());
+                        Ok(())
+                    },
+                )?;
+                if found {
+                    break;
+                }
+            }
+            frontier = next;
+        }
+        if !found {
+            return Ok(None);
+        }
+
+        let mut path = IdPath {
+            nodes: vec![to_id.to_string()],
+            edges: Vec::new(),
+        };
+        let mut current = to_id;
+        while current != from_id {
+            let (prev, edge_id) = &parent[current];
+            path.nodes.push(prev.clone());
+            path.edges.push(edge_id.clone());
+            current = prev;
+        }
+        path.nodes.reverse();
+        path.edges.reverse();
+        Ok(Some(path))
+    }
+
+    /// Loads the nodes and edges of a path of ids
+    fn load_path(&self, path: &IdPath) -> Result<(Vec<Node>, Vec<Edge>), GraphError> {
+        let nodes = path
+            .nodes
+            .iter()
+            .map(|id| self.get_temp_node(id))
+            .collect::<Result<Vec<_>, _>>()?;
+        let edges = path
+            .edges
+            .iter()
+            .map(|id| self.get_temp_edge(id))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok((nodes, edges))
+    }
+}
+
+/// A path as the ids of its nodes and edges, so candidate paths can be compared without loading them
+#[derive(Debug, Clone)]
+struct IdPath {
+    nodes: Vec<String>,
+    edges: Vec<String>,
+}
+
+/// One side of a bidirectional BFS
+struct BfsSide<'a> {
+    start: &'a str,
+    direction: EdgeDirection,
+    frontier: Vec<String>,
+    depth: usize,
+    depths: HashMap<String, usize>,
+    parent: HashMap<String, (String, String)>,
+}
+
+impl<'a> BfsSide<'a> {
+    fn new(start: &'a str, direction: EdgeDirection) -> BfsSide<'a> {
+        BfsSide {
+            start,
+            direction,
+            frontier: vec![start.to_string()],
+            depth: 0,
+            depths: HashMap::from([(start.to_string(), 0)]),
+            parent: HashMap::new(),
+        }
+    }
+}
+
+/// Entry in the priority queue of a weighted search, ordered so the lowest priority is popped first
+struct SearchEntry {
+    priority: f64,
+    cost: f64,
+    node_id: String,
+}
+
+impl Ord for SearchEntry {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.priority.total_cmp(&self.priority)
+    }
+}
+
+impl PartialOrd for SearchEntry {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for SearchEntry {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for SearchEntry {}
+
 impl StorageMethods for HelixGraphStorage {
     #[inline]
     fn check_exists(&self, id: &str) -> Result<bool, GraphError> {
@@ -228,11 +931,9 @@ impl StorageMethods for HelixGraphStorage {
             .db
             .cf_handle(CF_NODES)
             .ok_or(GraphError::from("Column Family not found"))?;
-        match self
-            .db
-            .get_pinned_cf(&cf_nodes, [NODE_PREFIX, id.as_bytes()].concat())
-        {
-            Ok(Some(data)) => Ok(deserialize(&data).unwrap()),
+        let key = Self::node_key(id);
+        match self.db.get_pinned_cf(&cf_nodes, &key) {
+            Ok(Some(data)) => self.decode_record(&key, &data),
             Ok(None) => Err(GraphError::New(format!("Node not found: {}", id))),
             Err(err) => Err(GraphError::from(err)),
         }
@@ -243,252 +944,103 @@ impl StorageMethods for HelixGraphStorage {
             .db
             .cf_handle(CF_EDGES)
             .ok_or(GraphError::from("Column Family not found"))?;
-        match self
-            .db
-            .get_pinned_cf(&cf_edges, [EDGE_PREFIX, id.as_bytes()].concat())
-        {
-            Ok(Some(data)) => Ok(deserialize(&data).unwrap()),
+        let key = Self::edge_key(id);
+        match self.db.get_pinned_cf(&cf_edges, &key) {
+            Ok(Some(data)) => self.decode_record(&key, &data),
             Ok(None) => Err(GraphError::New(format!("Edge not found: {}", id))),
             Err(err) => Err(GraphError::from(err)),
         }
     }
 
     #[inline]
-    fn get_node(&self, id: &str) -> Result<Node, GraphError> {
-        let cf_nodes = self
-            .db
-            .cf_handle(CF_NODES)
-            .ok_or(GraphError::from("Column Family not found"))?;
-        match self
-            .db
-            .get_cf(&cf_nodes, [NODE_PREFIX, id.as_bytes()].concat())
-        {
-            Ok(Some(data)) => deserialize::<Node>(&data).map_err(GraphError::from),
-            Ok(None) => Err(GraphError::New(format!("Item not found: {}", id))),
-            Err(err) => Err(GraphError::from(err)),
-        }
+    fn with_temp_node<R, F: FnOnce(NodeRef<'_>) -> R>(
+        &self,
+        id: &str,
+        f: F,
+    ) -> Result<R, GraphError> {
+        self.with_existing_node(id, f)?
+            .ok_or_else(|| GraphError::New(format!("Node not found: {}", id)))
     }
     #[inline]
-    fn get_edge(&self, id: &str) -> Result<Edge, GraphError> {
+    fn with_temp_edge<R, F: FnOnce(EdgeRef<'_>) -> R>(
+        &self,
+        id: &str,
+        f: F,
+    ) -> Result<R, GraphError> {
         let cf_edges = self
             .db
             .cf_handle(CF_EDGES)
             .ok_or(GraphError::from("Column Family not found"))?;
-        match self
-            .db
-            .get_cf(&cf_edges, [EDGE_PREFIX, id.as_bytes()].concat())
-        {
-            Ok(Some(data)) => deserialize::<Edge>(&data).map_err(GraphError::from),
-            Ok(None) => Err(GraphError::New(format!("Item not found: {}", id))),
+        let key = Self::edge_key(id);
+        match self.db.get_pinned_cf(&cf_edges, &key) {
+            Ok(Some(data)) => {
+                let data = self.view_record::<Edge>(&key, &data)?;
+                Ok(f(EdgeRef::parse(&data)?))
+            }
+            Ok(None) => Err(GraphError::New(format!("Edge not found: {}", id))),
             Err(err) => Err(GraphError::from(err)),
         }
     }
 
-    fn get_out_edges(&self, node_id: &str, edge_label: &str) -> Result<Vec<Edge>, GraphError> {
-        let cf_edge_index = self
+    #[inline]
+    fn get_node(&self, id: &str) -> Result<Node, GraphError> {
+        let cf_nodes = self
             .db
-            .cf_handle(CF_INDICES)
+            .cf_handle(CF_NODES)
             .ok_or(GraphError::from("Column Family not found"))?;
-
-        let mut edges = Vec::new();
-
-        let read_opts = Self::get_optimized_read_options(RAH_MEDIUM);
-
-        let out_prefix = Self::out_edge_key(node_id, "");
-        let iter = self.db.iterator_cf_opt(
-            &cf_edge_index,
-            read_opts,
-            IteratorMode::From(&out_prefix, rocksdb::Direction::Forward),
-        );
-
-        // get edge values
-        match edge_label {
-            "" => {
-                for result in iter {
-                    let (key, _) = result?;
-                    if !key.starts_with(&out_prefix) {
-                        break;
-                    }
-
-                    let edge_id = String::from_utf8(key[out_prefix.len()..].to_vec())?;
-                    let edge = self.get_temp_edge(&edge_id)?;
-
-                    edges.push(edge);
-                }
-            }
-            _ => {
-                for result in iter {
-                    let (key, _) = result?;
-                    if !key.starts_with(&out_prefix) {
-                        break;
-                    }
-
-                    let edge_id = String::from_utf8(key[out_prefix.len()..].to_vec())?;
-                    let edge = self.get_temp_edge(&edge_id)?;
-
-                    if edge.label.deref() == edge_label {
-                        edges.push(edge);
-                    }
-                }
-            }
+        let key = Self::node_key(id);
+        match self.db.get_cf(&cf_nodes, &key) {
+            Ok(Some(data)) => self.decode_record::<Node>(&key, &data),
+            Ok(None) => Err(GraphError::New(format!("Item not found: {}", id))),
+            Err(err) => Err(GraphError::from(err)),
         }
-        Ok(edges)
     }
-
-    fn get_in_edges(&self, node_id: &str, edge_label: &str) -> Result<Vec<Edge>, GraphError> {
-        let cf_indices = self
+    #[inline]
+    fn get_edge(&self, id: &str) -> Result<Edge, GraphError> {
+        let cf_edges = self
             .db
-            .cf_handle(CF_INDICES)
+            .cf_handle(CF_EDGES)
             .ok_or(GraphError::from("Column Family not found"))?;
-        let mut edges = Vec::with_capacity(20);
-        // get in edges
-        let in_prefix = Self::in_edge_key(node_id, "");
-        let read_opts = Self::get_optimized_read_options(RAH_MEDIUM);
-        let iter = self.db.iterator_cf_opt(
-            &cf_indices,
-            read_opts,
-            IteratorMode::From(&in_prefix, rocksdb::Direction::Forward),
-        );
-
-        // get edge values
-        match edge_label {
-            "" => {
-                for result in iter {
-                    let (key, _) = result?;
-                    if !key.starts_with(&in_prefix) {
-                        break;
-                    }
-
-                    let edge_id = String::from_utf8(key[in_prefix.len()..].to_vec())?;
-                    let edge = self.get_temp_edge(&edge_id)?;
-
-                    edges.push(edge);
-                }
-            }
-            _ => {
-                for result in iter {
-                    let (key, _) = result?;
-                    if !key.starts_with(&in_prefix) {
-                        break;
-                    }
-
-                    let edge_id = String::from_utf8(key[in_prefix.len()..].to_vec())?;
-                    let edge = self.get_temp_edge(&edge_id)?;
-
-                    if edge.label.deref() == edge_label {
-                        edges.push(edge);
-                    }
-                }
-            }
+        let key = Self::edge_key(id);
+        match self.db.get_cf(&cf_edges, &key) {
+            Ok(Some(data)) => self.decode_record::<Edge>(&key, &data),
+            Ok(None) => Err(GraphError::New(format!("Item not found: {}", id))),
+            Err(err) => Err(GraphError::from(err)),
         }
-
-        Ok(edges)
     }
 
-    fn get_out_nodes(&self, node_id: &str, edge_label: &str) -> Result<Vec<Node>, GraphError> {
-        let cf_indices = self
-            .db
-            .cf_handle(CF_INDICES)
-            .ok_or(GraphError::from("Column Family not found"))?;
-        let mut nodes = Vec::with_capacity(20);
-
-        //
-
-        // // Prefetch out edges
-        let out_prefix = Self::out_edge_key(node_id, "");
-        let read_opts = Self::get_optimized_read_options(RAH_SMALL);
-        let iter = self.db.iterator_cf_opt(
-            &cf_indices,
-            read_opts,
-            IteratorMode::From(&out_prefix, rocksdb::Direction::Forward),
-        );
-
-        match edge_label {
-            "" => {
-                for result in iter {
-                    let (key, _) = result?;
-                    if !key.starts_with(&out_prefix) {
-                        break;
-                    }
-                    let edge =
-                        &self.get_temp_edge(&std::str::from_utf8(&key[out_prefix.len()..]).unwrap())?;
-
-                    if let Ok(node) = self.get_temp_node(&edge.to_node) {
-                        nodes.push(node);
-                    }
-                }
-            }
-            _ => {
-                for result in iter {
-                    let (key, _) = result?;
-                    if !key.starts_with(&out_prefix) {
-                        break;
-                    }
-                    let edge =
-                        &self.get_temp_edge(&std::str::from_utf8(&key[out_prefix.len()..]).unwrap())?;
+    fn get_out_edges(&self, node_id: &str, edge_label: &str) -> Result<Vec<Edge>, GraphError> {
+        self.adjacent_edges(node_id, EdgeDirection::Out, edge_label)
+    }
 
-                    if edge.label == edge_label {
-                        if let Ok(node) = self.get_temp_node(&edge.to_node) {
-                            nodes.push(node);
-                        }
-                    }
-                }
-            }
-        }
+    fn get_in_edges(&self, node_id: &str, edge_label: &str) -> Result<Vec<Edge>, GraphError> {
+        self.adjacent_edges(node_id, EdgeDirection::In, edge_label)
+    }
 
-        Ok(nodes)
+    fn get_out_nodes(&self, node_id: &str, edge_label: &str) -> Result<Vec<Node>, GraphError> {
+        self.filter_adjacent_nodes(node_id, EdgeDirection::Out, edge_label, |_| true)
     }
 
     fn get_in_nodes(&self, node_id: &str, edge_label: &str) -> Result<Vec<Node>, GraphError> {
-        let cf_indices = self
-            .db
-            .cf_handle(CF_INDICES)
-            .ok_or(GraphError::from("Column Family not found"))?;
-        let mut nodes = Vec::with_capacity(20);
-
-        // Prefetch in edges
-        let in_prefix = Self::in_edge_key(node_id, "");
-        let read_opts = Self::get_optimized_read_options(RAH_SMALL);
-        let iter = self.db.iterator_cf_opt(
-            &cf_indices,
-            read_opts,
-            IteratorMode::From(&in_prefix, rocksdb::Direction::Forward),
-        );
-
-        match edge_label {
-            "" => {
-                for result in iter {
-                    let (key, _) = result?;
-                    if !key.starts_with(&in_prefix) {
-                        break;
-                    }
-                    let edge =
-                        &self.get_temp_edge(&std::str::from_utf8(&key[in_prefix.len()..]).unwrap())?;
-
-                    if let Ok(node) = self.get_temp_node(&edge.from_node) {
-                        nodes.push(node);
-                    }
-                }
-            }
-            _ => {
-                for result in iter {
-                    let (key, _) = result?;
-                    if !key.starts_with(&in_prefix) {
-                        break;
-                    }
-                    let edge =
-                        &self.get_temp_edge(&std::str::from_utf8(&key[in_prefix.len()..]).unwrap())?;
+        self.filter_adjacent_nodes(node_id, EdgeDirection::In, edge_label, |_| true)
+    }
 
-                    if edge.label == edge_label {
-                        if let Ok(node) = self.get_temp_node(&edge.from_node) {
-                            nodes.push(node);
-                        }
-                    }
-                }
-            }
-        }
+    fn filter_out_nodes<F: Fn(NodeRef<'_>) -> bool>(
+        &self,
+        node_id: &str,
+        edge_label: &str,
+        f: F,
+    ) -> Result<Vec<Node>, GraphError> {
+        self.filter_adjacent_nodes(node_id, EdgeDirection::Out, edge_label, f)
+    }
 
-        Ok(nodes)
+    fn filter_in_nodes<F: Fn(NodeRef<'_>) -> bool>(
+        &self,
+        node_id: &str,
+        edge_label: &str,
+        f: F,
+    ) -> Result<Vec<Node>, GraphError> {
+        self.filter_adjacent_nodes(node_id, EdgeDirection::In, edge_label, f)
     }
 
     fn get_all_nodes(&self) -> Result<Vec<Node>, GraphError> {
@@ -516,11 +1068,11 @@ impl StorageMethods for HelixGraphStorage {
         for result in iter.take_while(
             |r| matches!(r, Ok((k, _)) if memchr::memmem::find(k, &node_prefix).is_some()),
         ) {
-            let (_, value) = result?;
+            let (key, value) = result?;
             if value.is_empty() {
                 continue;
             }
-            match deserialize::<Node>(&value) {
+            match self.decode_record::<Node>(&key, &value) {
                 Ok(node) => {
                     nodes.push(node);
                 }
@@ -533,19 +1085,51 @@ impl StorageMethods for HelixGraphStorage {
         Ok(nodes)
     }
 
-    fn get_nodes_by_types(&self, types: &[String]) -> Result<Vec<Node>, GraphError> {
+    fn filter_all_nodes<F: Fn(NodeRef<'_>) -> bool>(&self, f: F) -> Result<Vec<Node>, GraphError> {
         let cf_nodes = self
             .db
             .cf_handle(CF_NODES)
             .ok_or_else(|| GraphError::from("Column Family not found"))?;
 
-        let approx_size = self
+        let mut nodes = Vec::new();
+        let iter = self.db.iterator_cf_opt(
+            &cf_nodes,
+            Self::get_optimized_read_options(RAH_LARGE),
+            IteratorMode::From(NODE_PREFIX, rocksdb::Direction::Forward),
+        );
+
+        for result in iter {
+            let (key, value) = result?;
+            if !key.starts_with(NODE_PREFIX) {
+                break;
+            }
+            if value.is_empty() {
+                continue;
+            }
+            let data = self.view_record::<Node>(&key, &value)?;
+            let node = NodeRef::parse(&data)?;
+            if f(node) {
+                nodes.push(node.to_node());
+            }
+        }
+        Ok(nodes)
+    }
+
+    fn get_nodes_by_types(&self, types: &[String]) -> Result<Vec<Node>, GraphError> {
+        self.filter_nodes_by_types(types, |_| true)
+    }
+
+    fn filter_nodes_by_types<F: Fn(NodeRef<'_>) -> bool>(
+        &self,
+        types: &[String],
+        f: F,
+    ) -> Result<Vec<Node>, GraphError> {
+        let cf_nodes = self
             .db
-            .property_int_value_cf(&cf_nodes, "rocksdb.estimate-num-keys")
-            .unwrap_or(Some(2000))
-            .unwrap_or(2000);
-        let mut nodes = Vec::with_capacity(approx_size as usize);
+            .cf_handle(CF_NODES)
+            .ok_or_else(|| GraphError::from("Column Family not found"))?;
 
+        let mut nodes = Vec::new();
         for label in types {
             let node_label_key = [NODE_LABEL_PREFIX, label.as_bytes(), b":"].concat();
             let read_opts = Self::get_optimized_read_options(RAH_SMALL);
@@ -555,19 +1139,138 @@ impl StorageMethods for HelixGraphStorage {
                 IteratorMode::From(&node_label_key, rocksdb::Direction::Forward),
             );
 
-            for result in iter.take_while(
-                |r| matches!(r, Ok((k, _)) if memchr::memmem::find(k, &node_label_key).is_some()),
-            ) {
+            for result in iter {
                 let (key, _) = result?;
-                let node_id = String::from_utf8(key[node_label_key.len()..].to_vec())?;
-                let node = self.get_temp_node(&node_id)?;
-                nodes.push(node);
+                if !key.starts_with(&node_label_key) {
+                    break;
+                }
+                let node_id = std::str::from_utf8(&key[node_label_key.len()..])?;
+                if let Some(node) =
+                    self.with_temp_node(node_id, |node| f(node).then(|| node.to_node()))?
+                {
+                    nodes.push(node);
+                }
+            }
+        }
+
+        Ok(nodes)
+    }
+
+    fn get_nodes_limit(&self, types: &[String], limit: usize) -> Result<Vec<Node>, GraphError> {
+        let mut nodes = Vec::with_capacity(limit.min(2000));
+        if limit == 0 {
+            return Ok(nodes);
+        }
+        let cf_nodes = self
+            .db
+            .cf_handle(CF_NODES)
+            .ok_or_else(|| GraphError::from("Column Family not found"))?;
+        if types.is_empty() {
+            let iter = self.db.iterator_cf_opt(
+                &cf_nodes,
+                Self::get_optimized_read_options(RAH_SMALL),
+                IteratorMode::From(NODE_PREFIX, rocksdb::Direction::Forward),
+            );
+            for result in iter {
+                let (key, value) = result?;
+                if !key.starts_with(NODE_PREFIX) {
+                    break;
+                }
+                if value.is_empty() {
+                    continue;
+                }
+                nodes.push(self.decode_record::<Node>(&key, &value)?);
+                if nodes.len() == limit {
+                    break;
+                }
             }
+            return Ok(nodes);
         }
 
+        for label in types {
+            let node_label_key = [NODE_LABEL_PREFIX, label.as_bytes(), b":"].concat();
+            let iter = self.db.iterator_cf_opt(
+                &cf_nodes,
+                Self::get_optimized_read_options(RAH_SMALL),
+                IteratorMode::From(&node_label_key, rocksdb::Direction::Forward),
+            );
+            for result in iter {
+                let (key, _) = result?;
+                if !key.starts_with(&node_label_key) {
+                    break;
+                }
+                let node_id = String::from_utf8(key[node_label_key.len()..].to_vec())?;
+                nodes.push(self.get_temp_node(&node_id)?);
+                if nodes.len() == limit {
+                    return Ok(nodes);
+                }
+            }
+        }
         Ok(nodes)
     }
 
+    fn get_edges_limit(&self, labels: &[String], limit: usize) -> Result<Vec<Edge>, GraphError> {
+        let mut edges = Vec::with_capacity(limit.min(2000));
+        if limit == 0 {
+            return Ok(edges);
+        }
+        let cf_edges = self
+            .db
+            .cf_handle(CF_EDGES)
+            .ok_or_else(|| GraphError::from("Column Family not found"))?;
+        if labels.is_empty() {
+            let iter = self.db.iterator_cf_opt(
+                &cf_edges,
+                Self::get_optimized_read_options(RAH_SMALL),
+                IteratorMode::From(EDGE_PREFIX, rocksdb::Direction::Forward),
+            );
+            for result in iter {
+                let (key, value) = result?;
+                if !key.starts_with(EDGE_PREFIX) {
+                    break;
+                }
+                if value.is_empty() {
+                    continue;
+                }
+                edges.push(self.decode_record::<Edge>(&key, &value)?);
+                if edges.len() == limit {
+                    break;
+                }
+            }
+            return Ok(edges);
+        }
+
+        let cf_indices = self
+            .db
+            .cf_handle(CF_INDICES)
+            .ok_or_else(|| GraphError::from("Column Family not found"))?;
+        for label in labels {
+            let edge_label_key = Self::edge_label_key(label, "");
+            let iter = self.db.iterator_cf_opt(
+                &cf_indices,
+                Self::get_optimized_read_options(RAH_SMALL),
+                IteratorMode::From(&edge_label_key, rocksdb::Direction::Forward),
+            );
+            for result in iter {
+                let (key, _) = result?;
+                if !key.starts_with(&edge_label_key) {
+                    break;
+                }
+                // the label index is not cleared when an edge is dropped
+                let edge_key = [EDGE_PREFIX, &key[edge_label_key.len()..]].concat();
+                let data = match self.db.get_pinned_cf(&cf_edges, &edge_key)? {
+                    Some(data) => data,
+                    None => continue,
+                };
+                edges.push(self.decode_record::<Edge>(&edge_key, &data)?);
+                if edges.len() == limit {
+                    return Ok(edges);
+                }
+            }
+        }
+        Ok(edges)
+    }
+
     fn get_all_edges(&self) -> Result<Vec<Edge>, GraphError> {
         let cf_edges = self
             .db
@@ -606,14 +1309,14 @@ impl StorageMethods for HelixGraphStorage {
 
             if let Some(value) = iter.value() {
                 if !value.is_empty() {
-                    batch.push(value.to_vec());
+                    batch.push((key.to_vec(), value.to_vec()));
                 }
             }
 
             if batch.len() >= BATCH_SIZE {
                 // Process batch
-                for value in batch {
-                    match deserialize::<Edge>(&value) {
+                for (key, value) in batch {
+                    match self.decode_record::<Edge>(&key, &value) {
                         Ok(edge) => edges.push(edge),
                         Err(e) => {
                             return Err(GraphError::from(format!("Deserialization error: {:?}", e)))
@@ -628,8 +1331,8 @@ impl StorageMethods for HelixGraphStorage {
 
         // Process remaining batch
         if !batch.is_empty() {
-            for value in batch {
-                match deserialize::<Edge>(&value) {
+            for (key, value) in batch {
+                match self.decode_record::<Edge>(&key, &value) {
                     Ok(edge) => edges.push(edge),
                     Err(e) => {
                         return Err(GraphError::from(format!("Deserialization error: {:?}", e)))
@@ -657,11 +1360,9 @@ impl StorageMethods for HelixGraphStorage {
             .ok_or(GraphError::from("Column Family not found"))?;
         let mut new_batch = WriteBatchWithTransaction::default();
 
-        new_batch.put_cf(
-            &cf_nodes,
-            Self::node_key(&node.id),
-            serialize(&node).unwrap(),
-        );
+        let node_key = Self::node_key(&node.id);
+        let node_data = self.encode_record(&node_key, &node)?;
+        new_batch.put_cf(&cf_nodes, node_key, node_data);
         new_batch.put_cf(&cf_nodes, Self::node_label_key(label, &node.id), vec![]);
 
         self.db.write(new_batch)?;
@@ -714,11 +1415,9 @@ impl StorageMethods for HelixGraphStorage {
         let mut batch = WriteBatch::default();
 
         // new edge
-        batch.put_cf(
-            &cf_edges,
-            Self::edge_key(&edge.id),
-            serialize(&edge).unwrap(),
-        );
+        let edge_key = Self::edge_key(&edge.id);
+        let edge_data = self.encode_record(&edge_key, &edge)?;
+        batch.put_cf(&cf_edges, edge_key, edge_data);
         // edge label
         batch.put_cf(&cf_indices, Self::edge_label_key(label, &edge.id), vec![]);
 
@@ -774,7 +1473,7 @@ impl StorageMethods for HelixGraphStorage {
                 .cf_handle(CF_INDICES)
                 .ok_or(GraphError::from("Column Family not found"))?;
 
-            let edge: Edge = deserialize::<Edge>(&edge_data).unwrap();
+            let edge: Edge = self.decode_record(&Self::edge_key(&edge_id), &edge_data)?;
 
             batch.delete_cf(&cf_indices, Self::out_edge_key(&edge.from_node, &edge_id));
             batch.delete_cf(&cf_indices, Self::in_edge_key(&edge.to_node, &edge_id));
@@ -818,7 +1517,7 @@ impl StorageMethods for HelixGraphStorage {
                 .cf_handle(CF_INDICES)
                 .ok_or(GraphError::from("Column Family not found"))?;
 
-            let edge = deserialize::<Edge>(&edge_data).unwrap();
+            let edge: Edge = self.decode_record(&Self::edge_key(&edge_id), &edge_data)?;
 
             batch.delete_cf(&cf_indices, Self::out_edge_key(&edge.from_node, &edge_id));
             batch.delete_cf(&cf_indices, Self::in_edge_key(&edge.to_node, &edge_id));
@@ -846,7 +1545,7 @@ impl StorageMethods for HelixGraphStorage {
             .cf_handle(CF_INDICES)
             .ok_or(GraphError::from("Column Family not found"))?;
 
-        let edge = deserialize::<Edge>(&edge_data).unwrap();
+        let edge: Edge = self.decode_record(&Self::edge_key(edge_id), &edge_data)?;
 
         let mut batch = WriteBatch::default();
 
@@ -867,77 +1566,76 @@ impl SearchMethods for HelixGraphStorage {
         from_id: &str,
         to_id: &str,
     ) -> Result<(Vec<Node>, Vec<Edge>), GraphError> {
-        let cf_indices = self
-            .db
-            .cf_handle(CF_INDICES)
-            .ok_or(GraphError::from("Column Family not found"))?;
-
-        let mut queue = VecDeque::new();
-        let mut visited = HashSet::with_capacity(48);
-        let mut parent: HashMap<String, (String, Edge)> = HashMap::with_capacity(8);
-
-        queue.push_back(from_id.to_string());
-        visited.insert(from_id.to_string());
-
-        let reconstruct_path = move |parent: &HashMap<String, (String, Edge)>,
-                                     start_id: &str,
-                                     end_id: &str|
-              -> Result<(Vec<Node>, Vec<Edge>), GraphError> {
-            let mut nodes = Vec::with_capacity(parent.len());
-            let mut edges = Vec::with_capacity(parent.len() - 1);
-            let mut current = end_id.to_string();
-
-            while current != start_id {
-                nodes.push(self.get_temp_node(&current)?);
-                let (prev_node, edge) = &parent[current.deref()];
-                edges.push(edge.clone());
-                current = prev_node.clone();
-            }
-            nodes.push(self.get_temp_node(start_id)?);
+        self.shortest_path_with(from_id, to_id, &PathOptions::default())
+    }
 
-            Ok((nodes, edges))
-        };
+    fn shortest_path_with(
+        &self,
+        from_id: &str,
+        to_id: &str,
+        options: &PathOptions,
+    ) -> Result<(Vec<Node>, Vec<Edge>), GraphError> {
+        if from_id == to_id {
+            return Ok((vec![self.get_temp_node(from_id)?], vec![]));
+        }
+        let max_depth = options.max_depth.unwrap_or(usize::MAX);
 
-        while let Some(current_id) = queue.pop_front() {
-            let mut read_opts = ReadOptions::default();
-            read_opts.set_verify_checksums(false);
-            read_opts.set_readahead_size(2 * 1024 * 1024);
-            read_opts.set_prefix_same_as_start(true);
-            read_opts.set_async_io(true);
-            read_opts.set_tailing(true);
-            read_opts.fill_cache(false);
+        // each side maps a visited node to the neighbouring node towards its start and the connecting edge
+        let mut forward = BfsSide::new(from_id, options.direction);
+        let mut backward = BfsSide::new(to_id, options.direction.reverse());
 
-            let out_prefix = Self::out_edge_key(&current_id, "");
-            let iter = self.db.iterator_cf_opt(
-                &cf_indices,
-                read_opts,
-                IteratorMode::From(&out_prefix, rocksdb::Direction::Forward),
-            );
-            for result in iter.take_while(
-                |r| matches!(r, Ok((k, _)) if memchr::memmem::find(k, &out_prefix).is_some()),
-            ) {
-                let (key, _) = result?;
-                if !key.starts_with(&out_prefix) {
-                    break;
+        while !forward.frontier.is_empty()
+            && !backward.frontier.is_empty()
+            && forward.depth + backward.depth < max_depth
+        {
+            let (side, other) = match forward.frontier.len() <= backward.frontier.len() {
+                true => (&mut forward, &backward),
+                false => (&mut backward, &forward),
+            };
+
+            // expand a whole level so the meeting point with the fewest remaining hops is picked
+            let mut next = Vec::new();
+            let mut meeting: Option<(String, usize)> = None;
+            for node_id in std::mem::take(&mut side.frontier) {
+                self.for_each_adjacent_edge(
+                    &node_id,
+                    side.direction,
+                    &options.edge_labels,
+                    |other_id, edge| {
+                        if side.parent.contains_key(other_id) || other_id == side.start {
+                            return Ok(());
+                        }
+                        side.parent.insert(
+                            other_id.to_string(),
+                            (node_id.clone(), edge.id().to_string()),
+                        );
+                        if let Some(&remaining) = other.depths.get(other_id) {
+                            if meeting.as_ref().is_none_or(|(_, d)| remaining < *d) {
+                                meeting = Some((other_id.to_string(), remaining));
+                            }
+                        }
+                        next.push(other_id.to_string());
+                        Ok(())
+                    },
+                )?;
+            }
+            side.depth += 1;
+            for node_id in &next {
+                side.depths.insert(node_id.clone(), side.depth);
+            }
+            side.frontier = next;
+
+            if let Some((meeting_id, _)) = meeting {
+                let (mut nodes, mut edges) =
+                    self.build_path(&forward.parent, from_id, &meeting_id)?;
+                let mut current = meeting_id;
+                while current != to_id {
+                    let (next_id, edge_id) = &backward.parent[&current];
+                    edges.push(self.get_temp_edge(edge_id)?);
+                    nodes.push(self.get_temp_node(next_id)?);
+                    current = next_id.clone();
                 }
-                let edge_id = String::from_utf8(key[out_prefix.len()..].to_vec())?;
-                let edge = self.get_temp_edge(&edge_id)?;
-                let in_v_id = edge.to_node.clone();
-                let out_v_id = edge.from_node.clone();
-                if !visited.insert(in_v_id.deref().to_string().clone()) {
-                    continue;
-                }
-
-                parent.insert(
-                    in_v_id.deref().to_string().clone(),
-                    (out_v_id.deref().to_string(), edge),
-                );
-
-                if in_v_id == to_id {
-                    return reconstruct_path(&parent, from_id, to_id);
-                }
-
-                queue.push_back(in_v_id.deref().to_string());
+                return Ok((nodes, edges));
             }
         }
 
@@ -946,14 +1644,189 @@ impl SearchMethods for HelixGraphStorage {
             from_id, to_id
         )))
     }
+
+    fn all_shortest_paths(
+        &self,
+        from_id: &str,
+        to_id: &str,
+    ) -> Result<Vec<(Vec<Node>, Vec<Edge>)>, GraphError> {
+        // BFS that keeps every predecessor on the level before a node instead of only the first
+        let mut depths: HashMap<String, usize> = HashMap::from([(from_id.to_string(), 0)]);
+        let mut predecessors: HashMap<String, Vec<(String, String)>> = HashMap::new();
+        let mut frontier = vec![from_id.to_string()];
+        let mut depth = 0;
+
+        while !frontier.is_empty() && !depths.contains_key(to_id) {
+            depth += 1;
+            let mut next = Vec::new();
+            for node_id in frontier {
+                self.for_each_adjacent_edge(
+                    &node_id,
+                    EdgeDirection::Out,
+                    &[],
+                    |other_id, edge| {
+                        match depths.get(other_id) {
+                            Some(&d) if d < depth => return Ok(()),
+                            Some(_) => {}
+                            None => {
+                                depths.insert(other_id.to_string(), depth);
+                                next.push(other_id.to_string());
+                            }
+                        }
+                        predecessors
+                            .entry(other_id.to_string())
+                            .or_default()
+                            .push((node_id.clone(), edge.id().to_string()));
+                        Ok(())
+                    },
+                )?;
+            }
+            frontier = next;
+        }
+        if !depths.contains_key(to_id) {
+            return Err(GraphError::from(format!(
+                "No path found between {} and {}",
+                from_id, to_id
+            )));
+        }
+
+        // walk every combination of predecessors back from the target
+        let mut paths = Vec::new();
+        let mut stack = vec![IdPath {
+            nodes: vec![to_id.to_string()],
+            edges: Vec::new(),
+        }];
+        while let Some(mut partial) = stack.pop() {
+            let head = partial.nodes.last().unwrap();
+            if head == from_id {
+                partial.nodes.reverse();
+                partial.edges.reverse();
+                paths.push(self.load_path(&partial)?);
+                continue;
+            }
+            for (prev, edge_id) in &predecessors[head] {
+                let mut extended = partial.clone();
+                extended.nodes.push(prev.clone());
+                extended.edges.push(edge_id.clone());
+                stack.push(extended);
+            }
+        }
+        Ok(paths)
+    }
+
+    fn k_shortest_paths(
+        &self,
+        from_id: &str,
+        to_id: &str,
+        k: usize,
+    ) -> Result<Vec<(Vec<Node>, Vec<Edge>)>, GraphError> {
+        if k == 0 {
+            return Ok(Vec::new());
+        }
+        let shortest = self
+            .bfs_id_path(from_id, to_id, &HashSet::new(), &HashSet::new())?
+            .ok_or_else(|| {
+                GraphError::from(format!("No path found between {} and {}", from_id, to_id))
+            })?;
+
+        // a path is identified by its edges since they also determine its nodes
+        let mut seen: HashSet<Vec<String>> = HashSet::from([shortest.edges.clone()]);
+        let mut accepted = vec![shortest];
+        let mut candidates: Vec<IdPath> = Vec::new();
+
+        while accepted.len() < k {
+            let last = accepted.last().unwrap().clone();
+            for i in 0..last.edges.len() {
+                let root_nodes = &last.nodes[..=i];
+                let root_edges = &last.edges[..i];
+
+                // force the spur path to leave the root differently from every accepted path sharing it
+                let excluded_edges: HashSet<String> = accepted
+                    .iter()
+                    .filter(|p| {
+                        p.edges.len() > i
+                            && p.nodes[..=i] == *root_nodes
+                            && p.edges[..i] == *root_edges
+                    })
+                    .map(|p| p.edges[i].clone())
+                    .collect();
+                let excluded_nodes: HashSet<String> = root_nodes[..i].iter().cloned().collect();
+
+                if let Some(spur) =
+                    self.bfs_id_path(&last.nodes[i], to_id, &excluded_nodes, &excluded_edges)?
+                {
+                    let mut nodes = root_nodes[..i].to_vec();
+                    nodes.extend(spur.nodes);
+                    let mut edges = root_edges.to_vec();
+                    edges.extend(spur.edges);
+                    if seen.insert(edges.clone()) {
+                        candidates.push(IdPath { nodes, edges });
+                    }
+                }
+            }
+
+            // min_by_key keeps the earliest candidate on ties so results are stable
+            let best = match candidates
+                .iter()
+                .enumerate()
+                .min_by_key(|(_, p)| p.edges.len())
+            {
+                Some((i, _)) => i,
+                None => break,
+            };
+            accepted.push(candidates.remove(best));
+        }
+
+        accepted.iter().map(|p| self.load_path(p)).collect()
+    }
+
+    fn dijkstra_shortest_path(
+        &self,
+        from_id: &str,
+        to_id: &str,
+        weight_property: &str,
+        direction: EdgeDirection,
+        edge_labels: &[String],
+    ) -> Result<(Vec<Node>, Vec<Edge>, f64), GraphError> {
+        self.weighted_search(
+            from_id,
+            to_id,
+            weight_property,
+            direction,
+            edge_labels,
+            None,
+        )
+    }
+
+    fn astar_shortest_path<H: Fn(NodeRef<'_>) -> f64>(
+        &self,
+        from_id: &str,
+        to_id: &str,
+        weight_property: &str,
+        direction: EdgeDirection,
+        edge_labels: &[String],
+        heuristic: H,
+    ) -> Result<(Vec<Node>, Vec<Edge>, f64), GraphError> {
+        self.weighted_search(
+            from_id,
+            to_id,
+            weight_property,
+            direction,
+            edge_labels,
+            Some(&heuristic),
+        )
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::props;
+    use crate::storage_core::encryption::{EncryptionKey, ENCRYPTION_MAGIC};
+    use crate::storage_core::record::ValueRef;
     use crate::storage_core::storage_methods::StorageMethods;
     use protocol::value::Value;
+    use std::cell::RefCell;
     use tempfile::TempDir;
 
     fn setup_temp_db() -> (HelixGraphStorage, TempDir) {
@@ -1069,6 +1942,254 @@ mod tests {
         assert_eq!(node.label, temp_node.label);
     }
 
+    #[test]
+    fn test_with_temp_node_reads_without_decoding() {
+        let (storage, _temp_dir) = setup_temp_db();
+
+        let node = storage
+            .create_node(
+                "person",
+                props! {
+                    "name" => "Alice",
+                    "age" => 30,
+                    "tags" => vec![Value::from("a"), Value::from("b")],
+                },
+            )
+            .unwrap();
+
+        let (label, name, age, tags) = storage
+            .with_temp_node(&node.id, |node_ref| {
+                let tags = match node_ref.check_property("tags") {
+                    Some(ValueRef::Array(arr)) => arr
+                        .iter()
+                        .filter_map(|v| v.as_str())
+                        .collect::<Vec<_>>()
+                        .join(","),
+                    _ => String::new(),
+                };
+                (
+                    node_ref.label().to_string(),
+                    node_ref
+                        .check_property("name")
+                        .and_then(|v| v.as_str())
+                        .map(str::to_string),
+                    node_ref
+                        .check_property("age")
+                        .map(|v| v == Value::Integer(30)),
+                    tags,
+                )
+            })
+            .unwrap();
+
+        assert_eq!(label, "person");
+        assert_eq!(name.as_deref(), Some("Alice"));
+        assert_eq!(age, Some(true));
+        assert_eq!(tags, "a,b");
+
+        let keys = storage
+            .with_temp_node(&node.id, |node_ref| {
+                assert!(node_ref.check_property("missing").is_none());
+                node_ref
+                    .properties()
+                    .map(|(k, _)| k.to_string())
+                    .collect::<Vec<_>>()
+            })
+            .unwrap();
+        assert_eq!(keys, vec!["age", "name", "tags"]);
+
+        let edge_node = storage.create_node("person", props!()).unwrap();
+        let edge = storage
+            .create_edge("knows", &node.id, &edge_node.id, props! { "since" => 2020 })
+            .unwrap();
+        let (from, since) = storage
+            .with_temp_edge(&edge.id, |edge_ref| {
+                (
+                    edge_ref.from_node().to_string(),
+                    edge_ref.check_property("since").map(|v| v.to_value()),
+                )
+            })
+            .unwrap();
+        assert_eq!(from, node.id);
+        assert_eq!(since, Some(Value::Integer(2020)));
+    }
+
+    #[test]
+    fn test_legacy_bincode_records_are_readable() {
+        let (storage, _temp_dir) = setup_temp_db();
+
+        let node = Node {
+            id: Uuid::new_v4().to_string(),
+            label: "person".to_string(),
+            properties: props! { "name" => "Legacy" }.into_iter().collect(),
+        };
+        let cf_nodes = storage.db.cf_handle(CF_NODES).unwrap();
+        storage
+            .db
+            .put_cf(
+                &cf_nodes,
+                HelixGraphStorage::node_key(&node.id),
+                bincode::serialize(&node).unwrap(),
+            )
+            .unwrap();
+        drop(cf_nodes);
+
+        let retrieved = storage.get_node(&node.id).unwrap();
+        assert_eq!(retrieved.properties, node.properties);
+
+        let name = storage
+            .with_temp_node(&node.id, |node_ref| {
+                node_ref.check_property("name").map(|v| v.to_value())
+            })
+            .unwrap();
+        assert_eq!(name, Some(Value::from("Legacy")));
+
+        let encoded = retrieved.encode();
+        for len in 0..encoded.len() {
+            assert!(NodeRef::parse(&encoded[..len]).is_err());
+        }
+        assert!(EdgeRef::parse(&encoded).is_err());
+    }
+
+    #[test]
+    fn test_adjacent_nodes_with_corrupt_record_fail() {
+        let (storage, _temp_dir) = setup_temp_db();
+
+        let node1 = storage.create_node("person", props!()).unwrap();
+        let node2 = storage.create_node("person", props!()).unwrap();
+        storage
+            .create_edge("knows", &node1.id, &node2.id, props!())
+            .unwrap();
+
+        let cf_nodes = storage.db.cf_handle(CF_NODES).unwrap();
+        storage
+            .db
+            .put_cf(&cf_nodes, HelixGraphStorage::node_key(&node2.id), [0xff; 3])
+            .unwrap();
+        drop(cf_nodes);
+
+        assert!(storage.get_out_nodes(&node1.id, "knows").is_err());
+        assert!(storage
+            .filter_out_nodes(&node1.id, "knows", |_| true)
+            .is_err());
+    }
+
+    #[test]
+    fn test_new_database_has_current_format_version() {
+        let (storage, _temp_dir) = setup_temp_db();
+        assert_eq!(storage.format_version().unwrap(), CURRENT_FORMAT_VERSION);
+        assert!(storage.migrate().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_legacy_database_is_migrated_on_open() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().to_str().unwrap();
+        let storage = HelixGraphStorage::new(db_path).unwrap();
+
+        let node1 = storage
+            .create_node("person", props! { "name" => "Alice" })
+            .unwrap();
+        let node2 = storage.create_node("person", props!()).unwrap();
+        let edge = storage
+            .create_edge("knows", &node1.id, &node2.id, props! { "since" => 2020 })
+            .unwrap();
+
+        // rewrite the records as an older build would have stored them
+        let cf_nodes = storage.db.cf_handle(CF_NODES).unwrap();
+        let cf_edges = storage.db.cf_handle(CF_EDGES).unwrap();
+        for node in [&node1, &node2] {
+            storage
+                .db
+                .put_cf(
+                    &cf_nodes,
+                    HelixGraphStorage::node_key(&node.id),
+                    bincode::serialize(node).unwrap(),
+                )
+                .unwrap();
+        }
+        storage
+            .db
+            .put_cf(
+                &cf_edges,
+                HelixGraphStorage::edge_key(&edge.id),
+                bincode::serialize(&edge).unwrap(),
+            )
+            .unwrap();
+        drop((cf_nodes, cf_edges));
+        storage.set_format_version(LEGACY_FORMAT_VERSION).unwrap();
+        drop(storage);
+
+        let storage = HelixGraphStorage::new(db_path).unwrap();
+        assert_eq!(storage.format_version().unwrap(), CURRENT_FORMAT_VERSION);
+
+        let cf_nodes = storage.db.cf_handle(CF_NODES).unwrap();
+        let cf_edges = storage.db.cf_handle(CF_EDGES).unwrap();
+        let node_data = storage
+            .db
+            .get_cf(&cf_nodes, HelixGraphStorage::node_key(&node1.id))
+            .unwrap()
+            .unwrap();
+        let edge_data = storage
+            .db
+            .get_cf(&cf_edges, HelixGraphStorage::edge_key(&edge.id))
+            .unwrap()
+            .unwrap();
+        assert_eq!(record::record_version(&node_data), record::RECORD_VERSION);
+        assert_eq!(record::record_version(&edge_data), record::RECORD_VERSION);
+        drop((cf_nodes, cf_edges));
+
+        let retrieved = storage.get_node(&node1.id).unwrap();
+        assert_eq!(
+            retrieved.properties.get("name").unwrap(),
+            &Value::from("Alice")
+        );
+        let retrieved_edge = storage.get_edge(&edge.id).unwrap();
+        assert_eq!(retrieved_edge.from_node, node1.id);
+        assert_eq!(storage.get_out_nodes(&node1.id, "knows").unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_newer_format_version_is_rejected() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().to_str().unwrap();
+        let storage = HelixGraphStorage::new(db_path).unwrap();
+        storage
+            .set_format_version(CURRENT_FORMAT_VERSION + 1)
+            .unwrap();
+        drop(storage);
+
+        assert!(matches!(
+            HelixGraphStorage::new(db_path),
+            Err(GraphError::MigrationError(_))
+        ));
+    }
+
+    #[test]
+    fn test_filter_all_nodes() {
+        let (storage, _temp_dir) = setup_temp_db();
+
+        for age in [20, 35, 50] {
+            storage
+                .create_node("person", props! { "age" => age })
+                .unwrap();
+        }
+        storage.create_node("thing", props!()).unwrap();
+
+        let nodes = storage
+            .filter_all_nodes(|node_ref| {
+                node_ref.label() == "person"
+                    && node_ref
+                        .check_property("age")
+                        .is_some_and(|v| v.cmp_value(&Value::Integer(30)).is_gt())
+            })
+            .unwrap();
+
+        assert_eq!(nodes.len(), 2);
+        assert!(nodes
+            .iter()
+            .all(|n| n.properties.get("age").unwrap() > &Value::Integer(30)));
+    }
+
     #[test]
     fn test_multiple_edges_between_nodes() {
         let (storage, _temp_dir) = setup_temp_db();
@@ -1114,6 +2235,68 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_node_with_extended_value_types() {
+        let (storage, _temp_dir) = setup_temp_db();
+
+        let settings: HashMap<String, Value> = HashMap::from([
+            ("theme".to_string(), Value::from("dark")),
+            ("retries".to_string(), Value::from(3)),
+        ]);
+        let properties = props! {
+            "id" => u64::MAX,
+            "balance" => -5_000_000_000i64,
+            "avatar" => vec![0u8, 1, 255],
+            "settings" => settings.clone(),
+            "nickname" => Option::<String>::None,
+            "created_at" => Value::Timestamp(1_706_702_400_250),
+        };
+
+        let node = storage.create_node("person", properties).unwrap();
+        let retrieved_node = storage.get_node(&node.id).unwrap();
+
+        assert_eq!(
+            retrieved_node.properties.get("id").unwrap(),
+            &Value::U64(u64::MAX)
+        );
+        assert_eq!(
+            retrieved_node.properties.get("balance").unwrap(),
+            &Value::I64(-5_000_000_000)
+        );
+        assert_eq!(
+            retrieved_node.properties.get("avatar").unwrap(),
+            &Value::Bytes(vec![0, 1, 255])
+        );
+        assert_eq!(
+            retrieved_node.properties.get("settings").unwrap(),
+            &Value::Map(settings)
+        );
+        assert_eq!(
+            retrieved_node.properties.get("nickname").unwrap(),
+            &Value::Null
+        );
+        assert_eq!(
+            retrieved_node.properties.get("created_at").unwrap(),
+            &Value::Timestamp(1_706_702_400_250)
+        );
+
+        let json = serde_json::to_string(&retrieved_node).unwrap();
+        let from_json: Node = serde_json::from_str(&json).unwrap();
+        assert_eq!(
+            from_json.properties.get("id").unwrap(),
+            &Value::U64(u64::MAX)
+        );
+        assert_eq!(
+            from_json.properties.get("balance").unwrap(),
+            &Value::I64(-5_000_000_000)
+        );
+        assert_eq!(from_json.properties.get("nickname").unwrap(), &Value::Null);
+        assert_eq!(
+            from_json.properties.get("settings").unwrap(),
+            retrieved_node.properties.get("settings").unwrap()
+        );
+    }
+
     #[test]
     fn test_get_all_nodes() {
         let (storage, _temp_dir) = setup_temp_db();
@@ -1156,6 +2339,46 @@ mod tests {
         assert!(node_ids.contains(&node3.id));
     }
 
+    #[test]
+    fn test_get_nodes_and_edges_limit() {
+        let (storage, _temp_dir) = setup_temp_db();
+        let people: Vec<Node> = (0..4)
+            .map(|_| storage.create_node("person", props!()).unwrap())
+            .collect();
+        storage.create_node("thing", props!()).unwrap();
+        for pair in people.windows(2) {
+            storage
+                .create_edge("knows", &pair[0].id, &pair[1].id, props!())
+                .unwrap();
+        }
+
+        assert_eq!(storage.get_nodes_limit(&[], 2).unwrap().len(), 2);
+        assert_eq!(storage.get_nodes_limit(&[], 10).unwrap().len(), 5);
+        assert!(storage.get_nodes_limit(&[], 0).unwrap().is_empty());
+
+        let things = storage.get_nodes_limit(&["thing".to_string()], 3).unwrap();
+        assert_eq!(things.len(), 1);
+        assert_eq!(things[0].label, "thing");
+        let people = storage.get_nodes_limit(&["person".to_string()], 3).unwrap();
+        assert_eq!(people.len(), 3);
+        assert!(people.iter().all(|n| n.label == "person"));
+
+        assert_eq!(storage.get_edges_limit(&[], 2).unwrap().len(), 2);
+        assert_eq!(storage.get_edges_limit(&[], 10).unwrap().len(), 3);
+
+        let likes = storage
+            .create_edge("likes", &people[0].id, &people[2].id, props!())
+            .unwrap();
+        let knows = storage.get_edges_limit(&["knows".to_string()], 2).unwrap();
+        assert_eq!(knows.len(), 2);
+        assert!(knows.iter().all(|e| e.label == "knows"));
+        storage.drop_edge(&likes.id).unwrap();
+        assert!(storage
+            .get_edges_limit(&["likes".to_string()], 1)
+            .unwrap()
+            .is_empty());
+    }
+
     #[test]
     fn test_get_all_edges() {
         let (storage, _temp_dir) = setup_temp_db();
@@ -1200,6 +2423,155 @@ mod tests {
         assert!(connections.contains(&(node1.id.clone(), node3.id.clone())));
     }
 
+    fn setup_encrypted_db(encryption: StorageEncryption) -> (HelixGraphStorage, TempDir) {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().to_str().unwrap();
+        let storage = HelixGraphStorage::new_encrypted(db_path, encryption).unwrap();
+        (storage, temp_dir)
+    }
+
+    #[test]
+    fn test_encrypted_node_and_edge_roundtrip() {
+        let key = EncryptionKey::new(1, &[7u8; 32]).unwrap();
+        let (storage, _temp_dir) = setup_encrypted_db(StorageEncryption::new(key));
+
+        let node1 = storage
+            .create_node("person", props! { "email" => "alice@example.com" })
+            .unwrap();
+        let node2 = storage.create_node("person", props!()).unwrap();
+        let edge = storage
+            .create_edge("knows", &node1.id, &node2.id, props! { "since" => 2020 })
+            .unwrap();
+
+        let cf_nodes = storage.db.cf_handle(CF_NODES).unwrap();
+        let raw = storage
+            .db
+            .get_cf(&cf_nodes, HelixGraphStorage::node_key(&node1.id))
+            .unwrap()
+            .unwrap();
+        assert_eq!(raw[..ENCRYPTION_MAGIC.len()], ENCRYPTION_MAGIC);
+        assert!(memchr::memmem::find(&raw, b"alice@example.com").is_none());
+
+        let retrieved = storage.get_node(&node1.id).unwrap();
+        assert_eq!(
+            retrieved.properties.get("email").unwrap(),
+            &Value::String("alice@example.com".to_string())
+        );
+        assert_eq!(storage.get_edge(&edge.id).unwrap().from_node, node1.id);
+        assert_eq!(storage.get_all_nodes().unwrap().len(), 2);
+        assert_eq!(storage.get_all_edges().unwrap().len(), 1);
+        assert_eq!(storage.get_out_nodes(&node1.id, "knows").unwrap().len(), 1);
+
+        storage.drop_edge(&edge.id).unwrap();
+        assert!(storage.get_edge(&edge.id).is_err());
+    }
+
+    #[test]
+    fn test_encrypted_read_with_wrong_key_fails() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().to_str().unwrap();
+        let node_id = {
+            let key = EncryptionKey::new(1, &[1u8; 32]).unwrap();
+            let storage =
+                HelixGraphStorage::new_encrypted(db_path, StorageEncryption::new(key)).unwrap();
+            storage.create_node("person", props!()).unwrap().id
+        };
+
+        let other_key = EncryptionKey::new(2, &[2u8; 32]).unwrap();
+        let storage =
+            HelixGraphStorage::new_encrypted(db_path, StorageEncryption::new(other_key)).unwrap();
+        assert!(matches!(
+            storage.get_node(&node_id),
+            Err(GraphError::EncryptionError(_))
+        ));
+    }
+
+    #[test]
+    fn test_plaintext_record_rejected_until_reencrypted() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().to_str().unwrap();
+        let node_id = {
+            let storage = HelixGraphStorage::new(db_path).unwrap();
+            storage
+                .create_node("person", props! { "name" => "Alice" })
+                .unwrap()
+                .id
+        };
+
+        let key = EncryptionKey::new(1, &[1u8; 32]).unwrap();
+        let storage =
+            HelixGraphStorage::new_encrypted(db_path, StorageEncryption::new(key)).unwrap();
+        assert!(matches!(
+            storage.get_node(&node_id),
+            Err(GraphError::EncryptionError(_))
+        ));
+
+        assert_eq!(storage.reencrypt_all().unwrap(), 1);
+        assert_eq!(
+            storage
+                .get_node(&node_id)
+                .unwrap()
+                .properties
+                .get("name")
+                .unwrap(),
+            &Value::String("Alice".to_string())
+        );
+    }
+
+    #[test]
+    fn test_reencrypt_after_key_rotation() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().to_str().unwrap();
+        let old_key = EncryptionKey::new(1, &[1u8; 32]).unwrap();
+
+        let (node1, node2) = {
+            let storage =
+                HelixGraphStorage::new_encrypted(db_path, StorageEncryption::new(old_key.clone()))
+                    .unwrap();
+            let node1 = storage
+                .create_node("person", props! { "name" => "Alice" })
+                .unwrap();
+            let node2 = storage.create_node("person", props!()).unwrap();
+            storage
+                .create_edge("knows", &node1.id, &node2.id, props!())
+                .unwrap();
+            (node1, node2)
+        };
+
+        let new_key = EncryptionKey::new(2, &[2u8; 32]).unwrap();
+        let storage = Arc::new(
+            HelixGraphStorage::new_encrypted(
+                db_path,
+                StorageEncryption::new(new_key.clone()).with_previous_key(old_key),
+            )
+            .unwrap(),
+        );
+
+        let rewritten = HelixGraphStorage::spawn_reencryption(Arc::clone(&storage))
+            .join()
+            .unwrap()
+            .unwrap();
+        assert_eq!(rewritten, 3);
+        assert_eq!(storage.reencrypt_all().unwrap(), 0);
+        drop(storage);
+
+        let storage =
+            HelixGraphStorage::new_encrypted(db_path, StorageEncryption::new(new_key)).unwrap();
+        assert_eq!(
+            storage
+                .get_node(&node1.id)
+                .unwrap()
+                .properties
+                .get("name")
+                .unwrap(),
+            &Value::String("Alice".to_string())
+        );
+        assert_eq!(
+            storage.get_out_nodes(&node1.id, "").unwrap()[0].id,
+            node2.id
+        );
+    }
+
     #[test]
     fn test_shortest_path() {
         let (storage, _temp_dir) = setup_temp_db();
@@ -1253,4 +2625,376 @@ mod tests {
         assert_eq!(shortest_path1, 3);
         assert_eq!(shortest_path2, 3);
     }
+
+    #[test]
+    fn test_shortest_path_with_options() {
+        let (storage, _temp_dir) = setup_temp_db();
+        let nodes = setup_road_network(&storage);
+        let ids = |path: &[Node]| path.iter().map(|n| n.id.clone()).collect::<Vec<_>>();
+
+        let roads_both = PathOptions {
+            direction: EdgeDirection::Both,
+            edge_labels: vec!["road".to_string()],
+            max_depth: None,
+        };
+        assert!(storage
+            .shortest_path_with(&nodes[0].id, &nodes[2].id, &PathOptions::default())
+            .is_err());
+        let (path_nodes, path_edges) = storage
+            .shortest_path_with(&nodes[1].id, &nodes[2].id, &roads_both)
+            .unwrap();
+        // b reaches c against the direction of its roads, through either a or d
+        assert_eq!(path_edges.len(), 2);
+        assert_eq!(path_nodes[0].id, nodes[1].id);
+        assert_eq!(path_nodes[2].id, nodes[2].id);
+        assert!(path_edges.iter().all(|e| e.label == "road"));
+
+        // following incoming edges walks the roads backwards
+        let incoming = PathOptions {
+            direction: EdgeDirection::In,
+            ..PathOptions::default()
+        };
+        let (path_nodes, _) = storage
+            .shortest_path_with(&nodes[3].id, &nodes[2].id, &incoming)
+            .unwrap();
+        assert_eq!(
+            ids(&path_nodes),
+            vec![nodes[3].id.clone(), nodes[2].id.clone()]
+        );
+
+        let ferry_only = PathOptions {
+            edge_labels: vec!["ferry".to_string()],
+            ..PathOptions::default()
+        };
+        let (_, path_edges) = storage
+            .shortest_path_with(&nodes[0].id, &nodes[3].id, &ferry_only)
+            .unwrap();
+        assert_eq!(path_edges.len(), 1);
+        assert_eq!(path_edges[0].label, "ferry");
+        assert!(storage
+            .shortest_path_with(&nodes[0].id, &nodes[1].id, &ferry_only)
+            .is_err());
+    }
+
+    #[test]
+    fn test_shortest_path_max_depth() {
+        let (storage, _temp_dir) = setup_temp_db();
+        let chain: Vec<Node> = (0..5)
+            .map(|_| storage.create_node("person", props!()).unwrap())
+            .collect();
+        for pair in chain.windows(2) {
+            storage
+                .create_edge("knows", &pair[0].id, &pair[1].id, props!())
+                .unwrap();
+        }
+
+        let depth = |max_depth| PathOptions {
+            max_depth: Some(max_depth),
+            ..PathOptions::default()
+        };
+        assert!(storage
+            .shortest_path_with(&chain[0].id, &chain[4].id, &depth(3))
+            .is_err());
+        let (path_nodes, path_edges) = storage
+            .shortest_path_with(&chain[0].id, &chain[4].id, &depth(4))
+            .unwrap();
+        assert_eq!(
+            path_nodes.iter().map(|n| &n.id).collect::<Vec<_>>(),
+            chain.iter().map(|n| &n.id).collect::<Vec<_>>()
+        );
+        for (edge, pair) in path_edges.iter().zip(chain.windows(2)) {
+            assert_eq!(edge.from_node, pair[0].id);
+            assert_eq!(edge.to_node, pair[1].id);
+        }
+
+        let (path_nodes, path_edges) = storage
+            .shortest_path_with(&chain[2].id, &chain[2].id, &depth(0))
+            .unwrap();
+        assert_eq!(path_nodes.len(), 1);
+        assert!(path_edges.is_empty());
+    }
+
+    fn setup_diamond(storage: &HelixGraphStorage) -> Vec<Node> {
+        // a has two parallel edges to b, and b can shortcut to c on the way to d
+        let nodes: Vec<Node> = (0..5)
+            .map(|_| storage.create_node("person", props!()).unwrap())
+            .collect();
+        for (from, to) in [(0, 1), (0, 1), (0, 2), (1, 3), (2, 3), (1, 2), (3, 4)] {
+            storage
+                .create_edge("knows", &nodes[from].id, &nodes[to].id, props!())
+                .unwrap();
+        }
+        nodes
+    }
+
+    #[test]
+    fn test_all_shortest_paths() {
+        let (storage, _temp_dir) = setup_temp_db();
+        let nodes = setup_diamond(&storage);
+
+        let paths = storage
+            .all_shortest_paths(&nodes[0].id, &nodes[3].id)
+            .unwrap();
+        assert_eq!(paths.len(), 3);
+        let mut edge_sets: Vec<Vec<String>> = paths
+            .iter()
+            .map(|(path_nodes, path_edges)| {
+                assert_eq!(path_nodes.len(), 3);
+                assert_eq!(path_nodes[0].id, nodes[0].id);
+                assert_eq!(path_nodes[2].id, nodes[3].id);
+                path_edges.iter().map(|e| e.id.clone()).collect()
+            })
+            .collect();
+        edge_sets.sort();
+        edge_sets.dedup();
+        assert_eq!(edge_sets.len(), 3);
+
+        assert_eq!(
+            storage
+                .all_shortest_paths(&nodes[0].id, &nodes[0].id)
+                .unwrap()
+                .len(),
+            1
+        );
+        assert!(storage
+            .all_shortest_paths(&nodes[4].id, &nodes[0].id)
+            .is_err());
+    }
+
+    #[test]
+    fn test_k_shortest_paths() {
+        let (storage, _temp_dir) = setup_temp_db();
+        let nodes = setup_diamond(&storage);
+
+        let paths = storage
+            .k_shortest_paths(&nodes[0].id, &nodes[4].id, 4)
+            .unwrap();
+        let lengths: Vec<usize> = paths.iter().map(|(_, edges)| edges.len()).collect();
+        assert_eq!(lengths, vec![3, 3, 3, 4]);
+
+        // there are only five loopless paths from a to e
+        let paths = storage
+            .k_shortest_paths(&nodes[0].id, &nodes[4].id, 10)
+            .unwrap();
+        assert_eq!(paths.len(), 5);
+        let mut unique: Vec<Vec<String>> = paths
+            .iter()
+            .map(|(_, edges)| edges.iter().map(|e| e.id.clone()).collect())
+            .collect();
+        unique.sort();
+        unique.dedup();
+        assert_eq!(unique.len(), 5);
+        for (path_nodes, path_edges) in &paths {
+            assert_eq!(path_nodes.first().unwrap().id, nodes[0].id);
+            assert_eq!(path_nodes.last().unwrap().id, nodes[4].id);
+            for (i, edge) in path_edges.iter().enumerate() {
+                assert_eq!(edge.from_node, path_nodes[i].id);
+                assert_eq!(edge.to_node, path_nodes[i + 1].id);
+            }
+        }
+
+        assert!(storage
+            .k_shortest_paths(&nodes[0].id, &nodes[4].id, 0)
+            .unwrap()
+            .is_empty());
+        assert!(storage
+            .k_shortest_paths(&nodes[4].id, &nodes[0].id, 3)
+            .is_err());
+    }
+
+    fn setup_road_network(storage: &HelixGraphStorage) -> Vec<Node> {
+        // a -1-> b -1-> d is the cheapest route, a -> d directly is a single expensive hop
+        // and c can only be reached from a against the direction of its road
+        let nodes: Vec<Node> = ["a", "b", "c", "d"]
+            .iter()
+            .map(|name| {
+                storage
+                    .create_node("city", props! { "name" => *name })
+                    .unwrap()
+            })
+            .collect();
+        let road = |from: usize, to: usize, distance: f64| {
+            storage
+                .create_edge(
+                    "road",
+                    &nodes[from].id,
+                    &nodes[to].id,
+                    props! { "distance" => distance },
+                )
+                .unwrap();
+        };
+        road(0, 1, 1.0);
+        road(1, 3, 1.0);
+        road(0, 3, 5.0);
+        road(2, 0, 0.5);
+        road(2, 3, 0.5);
+        storage
+            .create_edge(
+                "ferry",
+                &nodes[0].id,
+                &nodes[3].id,
+                props! { "distance" => 0.1 },
+            )
+            .unwrap();
+        nodes
+    }
+
+    #[test]
+    fn test_dijkstra_shortest_path() {
+        let (storage, _temp_dir) = setup_temp_db();
+        let nodes = setup_road_network(&storage);
+        let roads = vec!["road".to_string()];
+
+        let (path_nodes, path_edges, cost) = storage
+            .dijkstra_shortest_path(
+                &nodes[0].id,
+                &nodes[3].id,
+                "distance",
+                EdgeDirection::Out,
+                &roads,
+            )
+            .unwrap();
+        let ids: Vec<&str> = path_nodes.iter().map(|n| n.id.as_str()).collect();
+        assert_eq!(
+            ids,
+            vec![
+                nodes[0].id.as_str(),
+                nodes[1].id.as_str(),
+                nodes[3].id.as_str()
+            ]
+        );
+        assert_eq!(path_edges.len(), 2);
+        assert_eq!(cost, 2.0);
+
+        // following roads in both directions allows the route through c
+        let (path_nodes, _, cost) = storage
+            .dijkstra_shortest_path(
+                &nodes[0].id,
+                &nodes[3].id,
+                "distance",
+                EdgeDirection::Both,
+                &roads,
+            )
+            .unwrap();
+        assert_eq!(path_nodes[1].id, nodes[2].id);
+        assert_eq!(cost, 1.0);
+
+        // without a label filter the ferry is the cheapest
+        let (_, path_edges, cost) = storage
+            .dijkstra_shortest_path(
+                &nodes[0].id,
+                &nodes[3].id,
+                "distance",
+                EdgeDirection::Out,
+                &[],
+            )
+            .unwrap();
+        assert_eq!(path_edges[0].label, "ferry");
+        assert_eq!(cost, 0.1);
+
+        assert!(storage
+            .dijkstra_shortest_path(
+                &nodes[3].id,
+                &nodes[0].id,
+                "distance",
+                EdgeDirection::Out,
+                &roads
+            )
+            .is_err());
+        assert!(storage
+            .dijkstra_shortest_path(
+                &nodes[0].id,
+                &nodes[3].id,
+                "missing",
+                EdgeDirection::Out,
+                &roads
+            )
+            .is_err());
+    }
+
+    #[test]
+    fn test_dijkstra_rejects_negative_weights() {
+        let (storage, _temp_dir) = setup_temp_db();
+        let a = storage.create_node("city", props!()).unwrap();
+        let b = storage.create_node("city", props!()).unwrap();
+        storage
+            .create_edge("road", &a.id, &b.id, props! { "distance" => -1 })
+            .unwrap();
+
+        assert!(matches!(
+            storage.dijkstra_shortest_path(&a.id, &b.id, "distance", EdgeDirection::Out, &[]),
+            Err(GraphError::TraversalError(_))
+        ));
+    }
+
+    #[test]
+    fn test_astar_shortest_path() {
+        let (storage, _temp_dir) = setup_temp_db();
+        // cities on a line, the target lies east of the start and a dead end runs west
+        let cities: Vec<Node> = [
+            ("w2", -2.0),
+            ("w1", -1.0),
+            ("a", 0.0),
+            ("e1", 1.0),
+            ("b", 2.0),
+        ]
+        .iter()
+        .map(|(name, x)| {
+            storage
+                .create_node("city", props! { "name" => *name, "x" => *x })
+                .unwrap()
+        })
+        .collect();
+        for pair in cities.windows(2) {
+            storage
+                .create_edge(
+                    "road",
+                    &pair[0].id,
+                    &pair[1].id,
+                    props! { "distance" => 1.0 },
+                )
+                .unwrap();
+        }
+        let roads = vec!["road".to_string()];
+        let (start, target) = (&cities[2], &cities[4]);
+
+        // straight line distance to the target, recording every city it is asked about
+        let estimated = RefCell::new(Vec::new());
+        let heuristic = |node: NodeRef<'_>| {
+            if let Some(ValueRef::String(name)) = node.check_property("name") {
+                estimated.borrow_mut().push(name.to_string());
+            }
+            let x = node.check_property("x").and_then(|x| x.as_f64()).unwrap();
+            (2.0 - x).abs()
+        };
+        let astar = storage
+            .astar_shortest_path(
+                &start.id,
+                &target.id,
+                "distance",
+                EdgeDirection::Both,
+                &roads,
+                heuristic,
+            )
+            .unwrap();
+        let dijkstra = storage
+            .dijkstra_shortest_path(
+                &start.id,
+                &target.id,
+                "distance",
+                EdgeDirection::Both,
+                &roads,
+            )
+            .unwrap();
+        assert_eq!(astar.2, 2.0);
+        assert_eq!(astar.2, dijkstra.2);
+        let ids = |nodes: &[Node]| nodes.iter().map(|n| n.id.clone()).collect::<Vec<_>>();
+        assert_eq!(ids(&astar.0), ids(&dijkstra.0));
+
+        // w1 is as close to the start as e1, so a search without a heuristic expands it and
+        // reaches w2, but a guided one finishes before w1 is expanded
+        let estimated = estimated.into_inner();
+        assert!(estimated.contains(&"w1".to_string()));
+        assert!(!estimated.contains(&"w2".to_string()));
+    }
 }
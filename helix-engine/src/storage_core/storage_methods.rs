@@ -1,5 +1,6 @@
 use std::borrow::Cow;
 
+use crate::storage_core::record::{EdgeRef, NodeRef};
 use crate::types::GraphError;
 use protocol::{Node, Edge, value::Value};
 
@@ -7,18 +8,39 @@ pub trait StorageMethods {
     /// Checks whether an entry with a given id exists.
     /// Works for nodes or edges.
     fn check_exists(&self, id: &str) -> Result<bool, GraphError>;
-    /// Gets a node object for a given node id without copying its underlying data. 
+    /// Gets a node object for a given node id, reading from pinned data.
     /// 
-    /// This should only used when fetched data is only needed temporarily
-    /// as underlying data is pinned.
+    /// This should only used when fetched data is only needed temporarily.
+    /// Use `with_temp_node` to read the node without copying it.
     fn get_temp_node(&self, id: &str) -> Result<Node, GraphError>;
 
-    /// Gets a edge object for a given edge id without copying its underlying data. 
+    /// Gets a edge object for a given edge id, reading from pinned data.
     /// 
-    /// This should only used when fetched data is only needed temporarily
-    /// as underlying data is pinned.
+    /// This should only used when fetched data is only needed temporarily.
+    /// Use `with_temp_edge` to read the edge without copying it.
     fn get_temp_edge(&self, id: &str) -> Result<Edge, GraphError>;
 
+    /// Calls `f` with a borrowed view of a node without copying its underlying data.
+    ///
+    /// The view is only valid inside `f` as the underlying data is pinned.
+    fn with_temp_node<R, F: FnOnce(NodeRef<'_>) -> R>(
+        &self,
+        id: &str,
+        f: F,
+    ) -> Result<R, GraphError>;
+
+    /// Calls `f` with a borrowed view of an edge without copying its underlying data.
+    ///
+    /// The view is only valid inside `f` as the underlying data is pinned.
+    fn with_temp_edge<R, F: FnOnce(EdgeRef<'_>) -> R>(
+        &self,
+        id: &str,
+        f: F,
+    ) -> Result<R, GraphError>;
+
+    /// Returns all nodes for which `f` returns true.
+    /// Only matching nodes are decoded, the predicate reads borrowed views of each record.
+    fn filter_all_nodes<F: Fn(NodeRef<'_>) -> bool>(&self, f: F) -> Result<Vec<Node>, GraphError>;
 
     /// Gets a node object for a given node id
     fn get_node(&self, id: &str) -> Result<Node, GraphError>;
@@ -35,12 +57,43 @@ pub trait StorageMethods {
     /// Returns a list of node objects of the incoming nodes from a given node
     fn get_in_nodes(&self, node_id: &str, edge_label: &str) -> Result<Vec<Node>, GraphError>;
 
+    /// Returns the outgoing nodes from a given node for which `f` returns true.
+    /// Only matching nodes are decoded, the predicate reads borrowed views of each record.
+    fn filter_out_nodes<F: Fn(NodeRef<'_>) -> bool>(
+        &self,
+        node_id: &str,
+        edge_label: &str,
+        f: F,
+    ) -> Result<Vec<Node>, GraphError>;
+    /// Returns the incoming nodes from a given node for which `f` returns true.
+    /// Only matching nodes are decoded, the predicate reads borrowed views of each record.
+    fn filter_in_nodes<F: Fn(NodeRef<'_>) -> bool>(
+        &self,
+        node_id: &str,
+        edge_label: &str,
+        f: F,
+    ) -> Result<Vec<Node>, GraphError>;
+
     /// Returns all nodes in the graph
     fn get_all_nodes(&self) -> Result<Vec<Node>, GraphError>;
     /// Returns all edges in the graph
     fn get_all_edges(&self) -> Result<Vec<Edge>, GraphError>;
 
     fn get_nodes_by_types(&self, labels:  &[String]) -> Result<Vec<Node>, GraphError>;
+    /// Returns the nodes of the types in `labels` for which `f` returns true.
+    /// Only matching nodes are decoded, the predicate reads borrowed views of each record.
+    fn filter_nodes_by_types<F: Fn(NodeRef<'_>) -> bool>(
+        &self,
+        labels: &[String],
+        f: F,
+    ) -> Result<Vec<Node>, GraphError>;
+
+    /// Returns at most `limit` nodes, only of the types in `labels` if any are given.
+    /// The scan stops as soon as enough nodes have been read.
+    fn get_nodes_limit(&self, labels: &[String], limit: usize) -> Result<Vec<Node>, GraphError>;
+    /// Returns at most `limit` edges, only of the types in `labels` if any are given.
+    /// The scan stops as soon as enough edges have been read.
+    fn get_edges_limit(&self, labels: &[String], limit: usize) -> Result<Vec<Edge>, GraphError>;
 
     /// Creates a node entry
     fn create_node(
@@ -65,8 +118,102 @@ pub trait StorageMethods {
     fn drop_edge(&self, id: &str)  -> Result<(), GraphError>;
 }
 
+/// Direction of the edges followed when searching for paths
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EdgeDirection {
+    /// Follow outgoing edges
+    #[default]
+    Out,
+    /// Follow incoming edges
+    In,
+    /// Follow edges in either direction
+    Both,
+}
+
+/// Options for unweighted path searches
+#[derive(Debug, Clone, Default)]
+pub struct PathOptions {
+    /// Direction of the edges followed from the start node
+    pub direction: EdgeDirection,
+    /// Labels of the edges that may be followed, any label if empty
+    pub edge_labels: Vec<String>,
+    /// Maximum number of edges in a path, unlimited if `None`
+    pub max_depth: Option<usize>,
+}
+
+impl EdgeDirection {
+    /// The direction that walks the same edges backwards
+    #[inline]
+    pub fn reverse(self) -> EdgeDirection {
+        match self {
+            EdgeDirection::Out => EdgeDirection::In,
+            EdgeDirection::In => EdgeDirection::Out,
+            EdgeDirection::Both => EdgeDirection::Both,
+        }
+    }
+}
 
 pub trait SearchMethods {
-    /// Find shortest path between two nodes
-    fn shortest_path(& self, from_id: &str, to_id: &str) -> Result<(Vec<Node>, Vec<Edge>), GraphError>;
+    /// Find shortest path between two nodes following outgoing edges of any label
+    fn shortest_path(
+        &self,
+        from_id: &str,
+        to_id: &str,
+    ) -> Result<(Vec<Node>, Vec<Edge>), GraphError>;
+
+    /// Finds the shortest path between two nodes with the given direction, edge labels and max depth.
+    ///
+    /// Uses a bidirectional BFS that expands the smaller frontier from either end until they meet,
+    /// which visits far fewer nodes than a one sided BFS on graphs with high degree nodes.
+    /// Returns the nodes and edges of the path in order.
+    fn shortest_path_with(
+        &self,
+        from_id: &str,
+        to_id: &str,
+        options: &PathOptions,
+    ) -> Result<(Vec<Node>, Vec<Edge>), GraphError>;
+
+    /// Finds the cheapest path between two nodes using Dijkstra's algorithm.
+    ///
+    /// Edge costs are read from the numeric `weight_property` of each edge and must not be negative.
+    /// Only edges with a label in `edge_labels` are followed, or every edge if it is empty.
+    /// Returns the nodes and edges of the path in order along with its total cost.
+    fn dijkstra_shortest_path(
+        &self,
+        from_id: &str,
+        to_id: &str,
+        weight_property: &str,
+        direction: EdgeDirection,
+        edge_labels: &[String],
+    ) -> Result<(Vec<Node>, Vec<Edge>, f64), GraphError>;
+
+    /// Finds the cheapest path between two nodes using A*.
+    ///
+    /// Same as `dijkstra_shortest_path`, with `heuristic` estimating the remaining cost from a node to `to_id`.
+    /// The heuristic must never overestimate the remaining cost for the returned path to be the cheapest.
+    fn astar_shortest_path<H: Fn(NodeRef<'_>) -> f64>(
+        &self,
+        from_id: &str,
+        to_id: &str,
+        weight_property: &str,
+        direction: EdgeDirection,
+        edge_labels: &[String],
+        heuristic: H,
+    ) -> Result<(Vec<Node>, Vec<Edge>, f64), GraphError>;
+
+    /// Finds every path between two nodes that has the minimum number of outgoing edges
+    fn all_shortest_paths(
+        &self,
+        from_id: &str,
+        to_id: &str,
+    ) -> Result<Vec<(Vec<Node>, Vec<Edge>)>, GraphError>;
+
+    /// Finds up to `k` loopless paths between two nodes following outgoing edges,
+    /// shortest first, using Yen's algorithm
+    fn k_shortest_paths(
+        &self,
+        from_id: &str,
+        to_id: &str,
+        k: usize,
+    ) -> Result<Vec<(Vec<Node>, Vec<Edge>)>, GraphError>;
 }
\ No newline at end of file
@@ -11,6 +11,9 @@ pub enum GraphError {
     StorageError(String),
     TraversalError(String),
     ConversionError(String),
+    EncryptionError(String),
+    MigrationError(String),
+    AlgorithmError(String),
     EdgeNotFound,
     NodeNotFound,
     Default,
@@ -31,6 +34,9 @@ impl fmt::Display for GraphError {
             GraphError::TraversalError(msg) => write!(f, "Traversal error: {}", msg),
             GraphError::StorageError(msg) => write!(f, "Storage error: {}", msg),
             GraphError::ConversionError(msg ) => write!(f, "Conversion error: {}", msg),
+            GraphError::EncryptionError(msg) => write!(f, "Encryption error: {}", msg),
+            GraphError::MigrationError(msg) => write!(f, "Migration error: {}", msg),
+            GraphError::AlgorithmError(msg) => write!(f, "Algorithm error: {}", msg),
             GraphError::EdgeNotFound => write!(f, "Edge not found"),
             GraphError::NodeNotFound => write!(f, "Node not found"),
             GraphError::New(msg) => write!(f, "Graph error: {}", msg),
@@ -64,6 +70,12 @@ impl From<FromUtf8Error> for GraphError {
     }
 }
 
+impl From<Utf8Error> for GraphError {
+    fn from(error: Utf8Error) -> Self {
+        GraphError::ConversionError(error.to_string())
+    }
+}
+
 impl From<&'static str> for GraphError {
     fn from(error: &'static str) -> Self {
         GraphError::ConversionError(error.to_string())
@@ -43,6 +43,11 @@ pub enum FieldType {
     Integer,
     Float,
     Boolean,
+    I64,
+    U64,
+    Timestamp,
+    Bytes,
+    Map,
 }
 
 #[derive(Debug, Clone)]
@@ -76,8 +81,14 @@ pub enum Expression {
     Identifier(String),
     StringLiteral(String),
     IntegerLiteral(i32),
+    I64Literal(i64),
+    U64Literal(u64),
     FloatLiteral(f64),
     BooleanLiteral(bool),
+    /// Milliseconds since the Unix epoch, written as `Timestamp("2024-01-31T12:00:00Z")`
+    TimestampLiteral(i64),
+    BytesLiteral(Vec<u8>),
+    NullLiteral,
     Exists(Box<Traversal>),
     AddVertex(AddVertex),
     AddEdge(AddEdge),
@@ -113,6 +124,148 @@ pub enum Step {
     BooleanOperation(BooleanOp),
     AddField(Vec<FieldAddition>),
     Count,
+    ShortestPath(ShortestPathStep),
+    Components(ComponentsStep),
+    Centrality(CentralityStep),
+    Similar(SimilarStep),
+    Repeat(RepeatStep),
+    /// Removes repeated nodes or edges, by id or by the given property
+    Dedup(Option<String>),
+    OrderBy(OrderByStep),
+    Limit(usize),
+    /// Groups the current nodes or edges by the given property
+    GroupBy(String),
+    /// Counts the current nodes or edges for each value of the given property
+    GroupCount(String),
+    Aggregate(AggregateStep),
+    /// Outputs of every anonymous traversal, run from each current node or edge
+    Union(Vec<Traversal>),
+    /// Output of the first anonymous traversal that outputs anything, for each current node or edge
+    Coalesce(Vec<Traversal>),
+    Choose(ChooseStep),
+    /// Output of the anonymous traversal, or the node or edge itself if it outputs nothing
+    Optional(Box<Traversal>),
+}
+
+/// Finds the shortest path from each current node to `to`.
+/// Edge costs are read from the `weight` property if given, otherwise every edge costs 1
+/// and paths longer than `max_depth` edges are not considered.
+#[derive(Debug, Clone)]
+pub struct ShortestPathStep {
+    pub to: IdType,
+    pub edge_types: Option<Vec<String>>,
+    pub weight: Option<String>,
+    pub direction: PathDirection,
+    pub max_depth: Option<usize>,
+}
+
+/// Labels each current node with the connected component it belongs to
+/// in the subgraph made of the current nodes and edges of `edge_types`.
+#[derive(Debug, Clone)]
+pub struct ComponentsStep {
+    pub kind: ComponentKind,
+    pub edge_types: Option<Vec<String>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ComponentKind {
+    /// Nodes connected by edges in either direction
+    Weak,
+    /// Nodes that can reach each other along edge directions
+    Strong,
+}
+
+/// Scores each current node with a centrality measure within the subgraph made of the current nodes
+/// and edges of `edge_types`, ordering them from the highest score and keeping the `top` highest if given.
+#[derive(Debug, Clone)]
+pub struct CentralityStep {
+    pub measure: CentralityMeasure,
+    pub edge_types: Option<Vec<String>>,
+    pub direction: PathDirection,
+    /// Number of sampled source nodes for betweenness
+    pub samples: Option<usize>,
+    pub top: Option<usize>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CentralityMeasure {
+    Degree,
+    Closeness,
+    Betweenness,
+}
+
+/// Replaces the current nodes with the `top` nodes most similar to them by `metric`,
+/// counting neighbours along edges of `edge_types`
+#[derive(Debug, Clone)]
+pub struct SimilarStep {
+    pub edge_types: Option<Vec<String>>,
+    pub metric: SimilarityMetric,
+    pub top: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SimilarityMetric {
+    CommonNeighbours,
+    Jaccard,
+    AdamicAdar,
+    PreferentialAttachment,
+    Cosine,
+}
+
+/// Follows `step` from the current nodes up to `max` times,
+/// returning the nodes first reached by each hop from the `min`th one onwards
+#[derive(Debug, Clone)]
+pub struct RepeatStep {
+    pub step: GraphStep,
+    pub min: usize,
+    pub max: usize,
+}
+
+/// Sorts the current nodes or edges by `property`
+#[derive(Debug, Clone)]
+pub struct OrderByStep {
+    pub property: String,
+    pub order: SortOrder,
+}
+
+/// Runs `then` from each current node or edge for which `predicate` holds and `otherwise` from the rest,
+/// keeping the rest as they are if there is no `otherwise`
+#[derive(Debug, Clone)]
+pub struct ChooseStep {
+    pub predicate: Box<Traversal>,
+    pub then: Box<Traversal>,
+    pub otherwise: Option<Box<Traversal>>,
+}
+
+/// Aggregates `property` over the current nodes or edges, or over each group after a grouping step
+#[derive(Debug, Clone)]
+pub struct AggregateStep {
+    pub function: AggregateFunction,
+    pub property: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AggregateFunction {
+    Sum,
+    Mean,
+    Min,
+    Max,
+    CollectSet,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SortOrder {
+    #[default]
+    Asc,
+    Desc,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PathDirection {
+    #[default]
+    Out,
+    In,
+    Both,
 }
 
 #[derive(Debug, Clone)]
@@ -252,6 +405,11 @@ impl HelixParser {
             "Integer" => FieldType::Integer,
             "Float" => FieldType::Float,
             "Boolean" => FieldType::Boolean,
+            "I64" => FieldType::I64,
+            "U64" => FieldType::U64,
+            "Timestamp" => FieldType::Timestamp,
+            "Bytes" => FieldType::Bytes,
+            "Map" => FieldType::Map,
             _ => unreachable!(),
         };
 
@@ -363,30 +521,7 @@ impl HelixParser {
                     .to_string();
 
                 let prop_val = match pairs.next() {
-                    Some(p) => {
-                        let value_pair = p
-                            .into_inner()
-                            .next()
-                            .ok_or_else(|| ParserError::from("Empty property value"))?;
-
-                        match value_pair.as_rule() {
-                            Rule::string_literal => {
-                                Ok(Value::from(value_pair.as_str().to_string()))
-                            }
-                            Rule::integer => value_pair
-                                .as_str()
-                                .parse()
-                                .map(Value::Integer)
-                                .map_err(|_| ParserError::from("Invalid integer value")),
-                            Rule::float => value_pair
-                                .as_str()
-                                .parse()
-                                .map(Value::Float)
-                                .map_err(|_| ParserError::from("Invalid float value")),
-                            Rule::boolean => Ok(Value::Boolean(value_pair.as_str() == "true")),
-                            _ => Err(ParserError::from("Invalid property value type")),
-                        }?
-                    }
+                    Some(p) => Self::parse_property_value(p)?,
                     None => Value::Empty,
                 };
 
@@ -395,6 +530,81 @@ impl HelixParser {
             .collect()
     }
 
+    fn parse_property_value(pair: Pair<Rule>) -> Result<Value, ParserError> {
+        let value_pair = pair
+            .into_inner()
+            .next()
+            .ok_or_else(|| ParserError::from("Empty property value"))?;
+
+        match value_pair.as_rule() {
+            Rule::string_literal => Ok(Value::from(value_pair.as_str().to_string())),
+            Rule::integer => Self::parse_integer_value(value_pair.as_str()),
+            Rule::float => value_pair
+                .as_str()
+                .parse()
+                .map(Value::Float)
+                .map_err(|_| ParserError::from("Invalid float value")),
+            Rule::boolean => Ok(Value::Boolean(value_pair.as_str() == "true")),
+            Rule::null => Ok(Value::Null),
+            Rule::timestamp => Self::parse_timestamp(value_pair).map(Value::Timestamp),
+            Rule::bytes => Self::parse_bytes(value_pair.as_str()).map(Value::Bytes),
+            Rule::map_literal => match value_pair.into_inner().next() {
+                Some(assignments) => Ok(Value::Map(
+                    Self::parse_property_assignments(assignments)?
+                        .into_iter()
+                        .collect(),
+                )),
+                None => Ok(Value::Map(HashMap::new())),
+            },
+            _ => Err(ParserError::from("Invalid property value type")),
+        }
+    }
+
+    /// Integer literals use the smallest integer type that fits: i32, then i64, then u64
+    fn parse_integer_value(literal: &str) -> Result<Value, ParserError> {
+        if let Ok(i) = literal.parse::<i32>() {
+            return Ok(Value::Integer(i));
+        }
+        if let Ok(i) = literal.parse::<i64>() {
+            return Ok(Value::I64(i));
+        }
+        literal
+            .parse::<u64>()
+            .map(Value::U64)
+            .map_err(|_| ParserError::from("Invalid integer value"))
+    }
+
+    /// Milliseconds since the Unix epoch of a `Timestamp("...")` literal
+    fn parse_timestamp(pair: Pair<Rule>) -> Result<i64, ParserError> {
+        let literal = Self::parse_string_literal(
+            pair.into_inner()
+                .next()
+                .ok_or_else(|| ParserError::from("Missing timestamp value"))?,
+        )?;
+        match Value::timestamp_from_rfc3339(&literal) {
+            Some(Value::Timestamp(millis)) => Ok(millis),
+            _ => Err(ParserError::from(format!(
+                "Invalid RFC 3339 timestamp: {}",
+                literal
+            ))),
+        }
+    }
+
+    /// Bytes of a `0x...` literal, two hex digits per byte
+    fn parse_bytes(literal: &str) -> Result<Vec<u8>, ParserError> {
+        let digits = literal.trim_start_matches("0x");
+        if digits.len() % 2 != 0 {
+            return Err(ParserError::from(
+                "Bytes literal must have an even number of hex digits",
+            ));
+        }
+        (0..digits.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&digits[i..i + 2], 16))
+            .collect::<Result<Vec<u8>, _>>()
+            .map_err(|_| ParserError::from("Invalid bytes literal"))
+    }
+
     fn parse_add_edge(pair: Pair<Rule>) -> Result<AddEdge, ParserError> {
         let mut edge_type = None;
         let mut fields = None;
@@ -437,9 +647,7 @@ impl HelixParser {
             .ok_or_else(|| ParserError::from("Missing ID"))?;
         match p.as_rule() {
             Rule::identifier => Ok(IdType::Identifier(p.as_str().to_string())),
-            Rule::string_literal | Rule::inner_string => {
-                Ok(IdType::from(p.as_str().to_string()))
-            }
+            Rule::string_literal | Rule::inner_string => Ok(IdType::from(p.as_str().to_string())),
             _ => unreachable!(),
         }
     }
@@ -512,12 +720,21 @@ impl HelixParser {
             Rule::string_literal => {
                 Ok(Expression::StringLiteral(Self::parse_string_literal(pair)?))
             }
-            Rule::integer => pair
+            Rule::integer => match Self::parse_integer_value(pair.as_str())? {
+                Value::Integer(i) => Ok(Expression::IntegerLiteral(i)),
+                Value::I64(i) => Ok(Expression::I64Literal(i)),
+                Value::U64(u) => Ok(Expression::U64Literal(u)),
+                _ => Err(ParserError::from("Invalid integer literal")),
+            },
+            Rule::float => pair
                 .as_str()
                 .parse()
-                .map(Expression::IntegerLiteral)
-                .map_err(|_| ParserError::from("Invalid integer literal")),
+                .map(Expression::FloatLiteral)
+                .map_err(|_| ParserError::from("Invalid float literal")),
             Rule::boolean => Ok(Expression::BooleanLiteral(pair.as_str() == "true")),
+            Rule::timestamp => Ok(Expression::TimestampLiteral(Self::parse_timestamp(pair)?)),
+            Rule::bytes => Ok(Expression::BytesLiteral(Self::parse_bytes(pair.as_str())?)),
+            Rule::null => Ok(Expression::NullLiteral),
             Rule::exists => {
                 let traversal = pair
                     .into_inner()
@@ -606,6 +823,27 @@ impl HelixParser {
             Rule::bool_operations => Ok(Step::BooleanOperation(Self::parse_bool_operation(inner)?)),
             Rule::addfield => Ok(Step::AddField(Self::parse_field_additions(inner)?)),
             Rule::count => Ok(Step::Count),
+            Rule::path_step => Ok(Step::ShortestPath(Self::parse_path_step(inner)?)),
+            Rule::components_step => Ok(Step::Components(Self::parse_components_step(inner))),
+            Rule::centrality_step => Ok(Step::Centrality(Self::parse_centrality_step(inner)?)),
+            Rule::similar_step => Ok(Step::Similar(Self::parse_similar_step(inner)?)),
+            Rule::repeat_step => Ok(Step::Repeat(Self::parse_repeat_step(inner)?)),
+            Rule::dedup_step => Ok(Step::Dedup(
+                inner.into_inner().next().map(|p| p.as_str().to_string()),
+            )),
+            Rule::order_by_step => Ok(Step::OrderBy(Self::parse_order_by_step(inner))),
+            Rule::group_step => Ok(Self::parse_group_step(inner)),
+            Rule::aggregate_step => Ok(Step::Aggregate(Self::parse_aggregate_step(inner))),
+            Rule::union_step => Ok(Step::Union(Self::parse_anon_traversals(inner)?)),
+            Rule::coalesce_step => Ok(Step::Coalesce(Self::parse_anon_traversals(inner)?)),
+            Rule::choose_step => Ok(Step::Choose(Self::parse_choose_step(inner)?)),
+            Rule::optional_step => Ok(Step::Optional(Box::new(Self::parse_anon_traversal(
+                inner.into_inner().next().unwrap(),
+            )?))),
+            Rule::limit_step => Ok(Step::Limit(Self::parse_count_arg(
+                "limit",
+                inner.into_inner().next().unwrap().as_str(),
+            )?)),
             _ => Err(ParserError::from("Unexpected step type")),
         }
     }
@@ -631,6 +869,238 @@ impl HelixParser {
         }
     }
 
+    fn parse_path_step(pair: Pair<Rule>) -> Result<ShortestPathStep, ParserError> {
+        let mut step = ShortestPathStep {
+            to: IdType::Literal(String::new()),
+            edge_types: None,
+            weight: None,
+            direction: PathDirection::Out,
+            max_depth: None,
+        };
+        for p in pair.into_inner() {
+            match p.as_rule() {
+                Rule::type_args => {
+                    step.edge_types = Some(p.into_inner().map(|t| t.as_str().to_string()).collect())
+                }
+                Rule::id_arg => step.to = Self::parse_id_args(p)?,
+                Rule::named_arg => {
+                    let (name, value) = Self::parse_named_arg(p);
+                    match name {
+                        "weight" => step.weight = Some(value.to_string()),
+                        "depth" => step.max_depth = Some(Self::parse_count_arg(name, value)?),
+                        "direction" => step.direction = Self::parse_direction_arg(value)?,
+                        _ => {
+                            return Err(ParserError::from(format!(
+                                "Unknown shortest path argument: {}",
+                                name
+                            )))
+                        }
+                    }
+                }
+                _ => return Err(ParserError::from("Unexpected shortest path argument")),
+            }
+        }
+        Ok(step)
+    }
+
+    fn parse_components_step(pair: Pair<Rule>) -> ComponentsStep {
+        let kind = match pair.as_str().starts_with("Strongly") {
+            true => ComponentKind::Strong,
+            false => ComponentKind::Weak,
+        };
+        let edge_types = pair
+            .into_inner()
+            .next()
+            .map(|p| p.into_inner().map(|t| t.as_str().to_string()).collect());
+        ComponentsStep { kind, edge_types }
+    }
+
+    fn parse_centrality_step(pair: Pair<Rule>) -> Result<CentralityStep, ParserError> {
+        let measure = match pair.as_str() {
+            s if s.starts_with("Degree") => CentralityMeasure::Degree,
+            s if s.starts_with("Closeness") => CentralityMeasure::Closeness,
+            _ => CentralityMeasure::Betweenness,
+        };
+        let mut step = CentralityStep {
+            measure,
+            edge_types: None,
+            direction: PathDirection::Out,
+            samples: None,
+            top: None,
+        };
+        for p in pair.into_inner() {
+            match p.as_rule() {
+                Rule::type_args => {
+                    step.edge_types = Some(p.into_inner().map(|t| t.as_str().to_string()).collect())
+                }
+                Rule::named_arg => {
+                    let (name, value) = Self::parse_named_arg(p);
+                    match name {
+                        "direction" => step.direction = Self::parse_direction_arg(value)?,
+                        "samples" => step.samples = Some(Self::parse_count_arg(name, value)?),
+                        "top" => step.top = Some(Self::parse_count_arg(name, value)?),
+                        _ => {
+                            return Err(ParserError::from(format!(
+                                "Unknown centrality argument: {}",
+                                name
+                            )))
+                        }
+                    }
+                }
+                _ => return Err(ParserError::from("Unexpected centrality argument")),
+            }
+        }
+        Ok(step)
+    }
+
+    fn parse_similar_step(pair: Pair<Rule>) -> Result<SimilarStep, ParserError> {
+        let mut edge_types = None;
+        let mut metric = None;
+        let mut top = None;
+        for p in pair.into_inner() {
+            match p.as_rule() {
+                Rule::type_args => {
+                    edge_types = Some(p.into_inner().map(|t| t.as_str().to_string()).collect())
+                }
+                Rule::named_arg => {
+                    let (name, value) = Self::parse_named_arg(p);
+                    match name {
+                        "metric" => {
+                            metric = Some(match value {
+                                "CommonNeighbours" => SimilarityMetric::CommonNeighbours,
+                                "Jaccard" => SimilarityMetric::Jaccard,
+                                "AdamicAdar" => SimilarityMetric::AdamicAdar,
+                                "PreferentialAttachment" => {
+                                    SimilarityMetric::PreferentialAttachment
+                                }
+                                "Cosine" => SimilarityMetric::Cosine,
+                                _ => {
+                                    return Err(ParserError::from(format!(
+                                        "Invalid similarity metric: {}",
+                                        value
+                                    )))
+                                }
+                            })
+                        }
+                        "top" => top = Some(Self::parse_count_arg(name, value)?),
+                        _ => {
+                            return Err(ParserError::from(format!(
+                                "Unknown similar argument: {}",
+                                name
+                            )))
+                        }
+                    }
+                }
+                _ => return Err(ParserError::from("Unexpected similar argument")),
+            }
+        }
+        Ok(SimilarStep {
+            edge_types,
+            metric: metric.ok_or_else(|| ParserError::from("Similar needs a metric"))?,
+            top: top.ok_or_else(|| ParserError::from("Similar needs a top count"))?,
+        })
+    }
+
+    fn parse_repeat_step(pair: Pair<Rule>) -> Result<RepeatStep, ParserError> {
+        let direction = pair.as_str();
+        let mut types = None;
+        let mut bounds = Vec::with_capacity(2);
+        for p in pair.into_inner() {
+            match p.as_rule() {
+                Rule::type_args => {
+                    types = Some(p.into_inner().map(|t| t.as_str().to_string()).collect())
+                }
+                Rule::integer => bounds.push(Self::parse_count_arg("repeat bound", p.as_str())?),
+                _ => return Err(ParserError::from("Unexpected repeat argument")),
+            }
+        }
+        let step = match direction {
+            s if s.starts_with("Out") => GraphStep::Out(types),
+            s if s.starts_with("In") => GraphStep::In(types),
+            _ => GraphStep::Both(types),
+        };
+        let (min, max) = match bounds[..] {
+            [exact] => (exact, exact),
+            [min, max] => (min, max),
+            _ => return Err(ParserError::from("Repeat needs one or two bounds")),
+        };
+        if min == 0 || min > max {
+            return Err(ParserError::from(format!(
+                "Invalid repeat bounds {{{},{}}}",
+                min, max
+            )));
+        }
+        Ok(RepeatStep { step, min, max })
+    }
+
+    fn parse_order_by_step(pair: Pair<Rule>) -> OrderByStep {
+        let mut inner = pair.into_inner();
+        let property = inner.next().unwrap().as_str().to_string();
+        let order = match inner.next().map(|p| p.as_str()) {
+            Some("DESC") => SortOrder::Desc,
+            _ => SortOrder::Asc,
+        };
+        OrderByStep { property, order }
+    }
+
+    fn parse_anon_traversals(pair: Pair<Rule>) -> Result<Vec<Traversal>, ParserError> {
+        pair.into_inner().map(Self::parse_anon_traversal).collect()
+    }
+
+    fn parse_choose_step(pair: Pair<Rule>) -> Result<ChooseStep, ParserError> {
+        let mut traversals = Self::parse_anon_traversals(pair)?.into_iter().map(Box::new);
+        let predicate = traversals.next().unwrap();
+        let then = traversals.next().unwrap();
+        Ok(ChooseStep {
+            predicate,
+            then,
+            otherwise: traversals.next(),
+        })
+    }
+
+    fn parse_group_step(pair: Pair<Rule>) -> Step {
+        let count = pair.as_str().starts_with("GROUP_COUNT");
+        let key = pair.into_inner().next().unwrap().as_str().to_string();
+        match count {
+            true => Step::GroupCount(key),
+            false => Step::GroupBy(key),
+        }
+    }
+
+    fn parse_aggregate_step(pair: Pair<Rule>) -> AggregateStep {
+        let function = match pair.as_str() {
+            s if s.starts_with("SUM") => AggregateFunction::Sum,
+            s if s.starts_with("MEAN") => AggregateFunction::Mean,
+            s if s.starts_with("MIN") => AggregateFunction::Min,
+            s if s.starts_with("MAX") => AggregateFunction::Max,
+            _ => AggregateFunction::CollectSet,
+        };
+        let property = pair.into_inner().next().unwrap().as_str().to_string();
+        AggregateStep { function, property }
+    }
+
+    fn parse_named_arg(pair: Pair<'_, Rule>) -> (&str, &str) {
+        let mut arg = pair.into_inner();
+        let name = arg.next().unwrap().as_str();
+        let value = arg.next().unwrap().as_str();
+        (name, value)
+    }
+
+    fn parse_direction_arg(value: &str) -> Result<PathDirection, ParserError> {
+        match value {
+            "Out" => Ok(PathDirection::Out),
+            "In" => Ok(PathDirection::In),
+            "Both" => Ok(PathDirection::Both),
+            _ => Err(ParserError::from(format!("Invalid direction: {}", value))),
+        }
+    }
+
+    fn parse_count_arg(name: &str, value: &str) -> Result<usize, ParserError> {
+        value
+            .parse()
+            .map_err(|_| ParserError::from(format!("Invalid {}: {}", name, value)))
+    }
+
     fn parse_props_step(pair: Pair<Rule>) -> Vec<String> {
         pair.into_inner().map(|p| p.as_str().to_string()).collect()
     }
@@ -675,7 +1145,7 @@ impl HelixParser {
         let value = match value_pair.as_rule() {
             Rule::evaluates_to_anything => Self::parse_expression(value_pair)?,
             Rule::anonymous_traversal => {
-                Expression::Traversal(Box::new(Self::parse_traversal(value_pair)?))
+                Expression::Traversal(Box::new(Self::parse_anon_traversal(value_pair)?))
             }
             _ => {
                 return Err(ParserError::from(format!(
@@ -1025,6 +1495,499 @@ mod tests {
         assert_eq!(query.statements.len(), 3);
     }
 
+    #[test]
+    fn test_extended_value_literals() {
+        let input = r#"
+    V::User {
+        Id: U64,
+        Balance: I64,
+        CreatedAt: Timestamp,
+        Avatar: Bytes,
+        Settings: Map
+    }
+
+    QUERY addUser() =>
+        user <- AddV<User>({Id: 18446744073709551615, Balance: -5000000000, Age: -3, Score: 1.5, CreatedAt: Timestamp("2024-01-31T12:00:00.250Z"), Avatar: 0x00ff, Settings: {Theme: "dark", Nested: {}}, Nickname: null})
+        RETURN user
+    "#;
+        let result = HelixParser::parse_source(input).unwrap();
+        let fields = &result.node_schemas[0].fields;
+        assert!(matches!(fields[0].field_type, FieldType::U64));
+        assert!(matches!(fields[1].field_type, FieldType::I64));
+        assert!(matches!(fields[2].field_type, FieldType::Timestamp));
+        assert!(matches!(fields[3].field_type, FieldType::Bytes));
+        assert!(matches!(fields[4].field_type, FieldType::Map));
+
+        let props: HashMap<String, Value> = match &result.queries[0].statements[0] {
+            Statement::Assignment(Assignment {
+                value: Expression::AddVertex(add_v),
+                ..
+            }) => add_v.fields.clone().unwrap().into_iter().collect(),
+            _ => panic!("Expected AddV assignment"),
+        };
+        assert_eq!(props["Id"], Value::U64(u64::MAX));
+        assert_eq!(props["Balance"], Value::I64(-5_000_000_000));
+        assert_eq!(props["Age"], Value::Integer(-3));
+        assert_eq!(props["Score"], Value::Float(1.5));
+        assert_eq!(props["CreatedAt"], Value::Timestamp(1_706_702_400_250));
+        assert_eq!(props["Avatar"], Value::Bytes(vec![0x00, 0xff]));
+        assert_eq!(props["Nickname"], Value::Null);
+        match &props["Settings"] {
+            Value::Map(settings) => {
+                assert_eq!(settings["Theme"], Value::String("dark".to_string()));
+                assert_eq!(settings["Nested"], Value::Map(HashMap::new()));
+            }
+            other => panic!("Expected map, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_extended_expression_literals() {
+        let input = r#"
+    QUERY filters() =>
+        big <- V<User>()::WHERE(_::Props(Balance)::GT(5000000000))
+        max <- V<User>()::WHERE(_::Props(Id)::EQ(18446744073709551615))
+        recent <- V<User>()::WHERE(_::Props(CreatedAt)::GTE(Timestamp("2024-01-31T12:00:00.250Z")))
+        unnamed <- V<User>()::WHERE(_::Props(Nickname)::EQ(null))
+        avatars <- V<User>()::WHERE(_::Props(Avatar)::NEQ(0x00ff))
+        scored <- V<User>()::WHERE(_::Props(Score)::LT(1.5))
+        RETURN big
+    "#;
+        let result = HelixParser::parse_source(input).unwrap();
+        let literals: Vec<&Expression> = result.queries[0]
+            .statements
+            .iter()
+            .map(|statement| match statement {
+                Statement::Assignment(Assignment {
+                    value: Expression::Traversal(tr),
+                    ..
+                }) => match &tr.steps[0] {
+                    Step::Where(expression) => match &**expression {
+                        Expression::Traversal(anon_tr) => match &anon_tr.steps[1] {
+                            Step::BooleanOperation(
+                                BooleanOp::GreaterThan(literal)
+                                | BooleanOp::GreaterThanOrEqual(literal)
+                                | BooleanOp::LessThan(literal)
+                                | BooleanOp::Equal(literal)
+                                | BooleanOp::NotEqual(literal),
+                            ) => &**literal,
+                            other => panic!("Expected comparison, got {:?}", other),
+                        },
+                        other => panic!("Expected anonymous traversal, got {:?}", other),
+                    },
+                    other => panic!("Expected WHERE, got {:?}", other),
+                },
+                other => panic!("Expected traversal assignment, got {:?}", other),
+            })
+            .collect();
+
+        assert!(matches!(literals[0], Expression::I64Literal(5_000_000_000)));
+        assert!(matches!(literals[1], Expression::U64Literal(u64::MAX)));
+        assert!(matches!(
+            literals[2],
+            Expression::TimestampLiteral(1_706_702_400_250)
+        ));
+        assert!(matches!(literals[3], Expression::NullLiteral));
+        assert!(matches!(literals[4], Expression::BytesLiteral(b) if b == &[0x00, 0xff]));
+        assert!(matches!(literals[5], Expression::FloatLiteral(f) if *f == 1.5));
+    }
+
+    #[test]
+    fn test_shortest_path_step() {
+        let input = r#"
+    QUERY route(target) =>
+        cheapest <- V("a")::ShortestPath<Road, Ferry>(target, weight: distance, direction: Both)
+        hops <- V("a")::ShortestPath<Knows>("b", direction: In, depth: 4)
+        RETURN cheapest, hops
+    "#;
+        let result = HelixParser::parse_source(input).unwrap();
+        let steps: Vec<&Step> = result.queries[0]
+            .statements
+            .iter()
+            .map(|stmt| match stmt {
+                Statement::Assignment(Assignment {
+                    value: Expression::Traversal(tr),
+                    ..
+                }) => &tr.steps[0],
+                _ => panic!("Expected traversal assignment"),
+            })
+            .collect();
+
+        match steps[0] {
+            Step::ShortestPath(step) => {
+                assert!(matches!(&step.to, IdType::Identifier(id) if id == "target"));
+                assert_eq!(
+                    step.edge_types,
+                    Some(vec!["Road".to_string(), "Ferry".to_string()])
+                );
+                assert_eq!(step.weight.as_deref(), Some("distance"));
+                assert_eq!(step.direction, PathDirection::Both);
+                assert!(step.max_depth.is_none());
+            }
+            _ => panic!("Expected shortest path step"),
+        }
+        match steps[1] {
+            Step::ShortestPath(step) => {
+                assert!(matches!(&step.to, IdType::Literal(id) if id == "b"));
+                assert_eq!(step.edge_types, Some(vec!["Knows".to_string()]));
+                assert!(step.weight.is_none());
+                assert_eq!(step.direction, PathDirection::In);
+                assert_eq!(step.max_depth, Some(4));
+            }
+            _ => panic!("Expected shortest path step"),
+        }
+
+        let invalid = r#"
+    QUERY route() =>
+        path <- V("a")::ShortestPath("b", direction: Sideways)
+        RETURN path
+    "#;
+        assert!(HelixParser::parse_source(invalid).is_err());
+
+        let invalid_depth = r#"
+    QUERY route() =>
+        path <- V("a")::ShortestPath("b", depth: far)
+        RETURN path
+    "#;
+        assert!(HelixParser::parse_source(invalid_depth).is_err());
+    }
+
+    #[test]
+    fn test_components_step() {
+        let input = r#"
+    QUERY clusters() =>
+        weak <- V<Package>::ConnectedComponents
+        cycles <- V<Package>::StronglyConnectedComponents<DependsOn>
+        RETURN weak, cycles
+    "#;
+        let result = HelixParser::parse_source(input).unwrap();
+        let steps: Vec<&Step> = result.queries[0]
+            .statements
+            .iter()
+            .map(|stmt| match stmt {
+                Statement::Assignment(Assignment {
+                    value: Expression::Traversal(tr),
+                    ..
+                }) => &tr.steps[0],
+                _ => panic!("Expected traversal assignment"),
+            })
+            .collect();
+
+        match steps[0] {
+            Step::Components(step) => {
+                assert_eq!(step.kind, ComponentKind::Weak);
+                assert!(step.edge_types.is_none());
+            }
+            _ => panic!("Expected components step"),
+        }
+        match steps[1] {
+            Step::Components(step) => {
+                assert_eq!(step.kind, ComponentKind::Strong);
+                assert_eq!(step.edge_types, Some(vec!["DependsOn".to_string()]));
+            }
+            _ => panic!("Expected components step"),
+        }
+    }
+
+    #[test]
+    fn test_centrality_step() {
+        let input = r#"
+    QUERY influencers() =>
+        popular <- V<User>::DegreeCentrality<Follows>(direction: In, top: 10)
+        bridges <- V<User>::BetweennessCentrality(samples: 100)
+        central <- V<User>::ClosenessCentrality
+        RETURN popular, bridges, central
+    "#;
+        let result = HelixParser::parse_source(input).unwrap();
+        let steps: Vec<&Step> = result.queries[0]
+            .statements
+            .iter()
+            .map(|stmt| match stmt {
+                Statement::Assignment(Assignment {
+                    value: Expression::Traversal(tr),
+                    ..
+                }) => &tr.steps[0],
+                _ => panic!("Expected traversal assignment"),
+            })
+            .collect();
+
+        match steps[0] {
+            Step::Centrality(step) => {
+                assert_eq!(step.measure, CentralityMeasure::Degree);
+                assert_eq!(step.edge_types, Some(vec!["Follows".to_string()]));
+                assert_eq!(step.direction, PathDirection::In);
+                assert_eq!(step.top, Some(10));
+                assert!(step.samples.is_none());
+            }
+            _ => panic!("Expected centrality step"),
+        }
+        match steps[1] {
+            Step::Centrality(step) => {
+                assert_eq!(step.measure, CentralityMeasure::Betweenness);
+                assert_eq!(step.samples, Some(100));
+                assert_eq!(step.direction, PathDirection::Out);
+            }
+            _ => panic!("Expected centrality step"),
+        }
+        match steps[2] {
+            Step::Centrality(step) => assert_eq!(step.measure, CentralityMeasure::Closeness),
+            _ => panic!("Expected centrality step"),
+        }
+
+        let invalid = r#"
+    QUERY influencers() =>
+        popular <- V<User>::DegreeCentrality(weight: score)
+        RETURN popular
+    "#;
+        assert!(HelixParser::parse_source(invalid).is_err());
+    }
+
+    #[test]
+    fn test_similar_step() {
+        let input = r#"
+    QUERY suggestions(userID) =>
+        suggested <- V(userID)::Similar<Knows>(metric: AdamicAdar, top: 5)
+        RETURN suggested
+    "#;
+        let result = HelixParser::parse_source(input).unwrap();
+        match &result.queries[0].statements[0] {
+            Statement::Assignment(Assignment {
+                value: Expression::Traversal(tr),
+                ..
+            }) => match &tr.steps[0] {
+                Step::Similar(step) => {
+                    assert_eq!(step.edge_types, Some(vec!["Knows".to_string()]));
+                    assert_eq!(step.metric, SimilarityMetric::AdamicAdar);
+                    assert_eq!(step.top, 5);
+                }
+                _ => panic!("Expected similar step"),
+            },
+            _ => panic!("Expected traversal assignment"),
+        }
+
+        for invalid in [
+            "Similar(top: 5)",
+            "Similar(metric: Jaccard)",
+            "Similar(metric: Euclidean, top: 5)",
+        ] {
+            let input = format!(
+                "QUERY suggestions() =>\n    suggested <- V<User>::{}\n    RETURN suggested",
+                invalid
+            );
+            assert!(HelixParser::parse_source(&input).is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_repeat_step() {
+        let input = r#"
+    QUERY reach(id) =>
+        nearby <- V(id)::Out<Follows>{1,3}
+        exact <- V(id)::Both{2}
+        edges <- V(id)::OutE<Follows>
+        RETURN nearby, exact, edges
+    "#;
+        let result = HelixParser::parse_source(input).unwrap();
+        let steps: Vec<&Step> = result.queries[0]
+            .statements
+            .iter()
+            .map(|stmt| match stmt {
+                Statement::Assignment(Assignment {
+                    value: Expression::Traversal(tr),
+                    ..
+                }) => &tr.steps[0],
+                _ => panic!("Expected traversal assignment"),
+            })
+            .collect();
+
+        match steps[0] {
+            Step::Repeat(step) => {
+                assert!(matches!(&step.step, GraphStep::Out(Some(types)) if types == &["Follows"]));
+                assert_eq!((step.min, step.max), (1, 3));
+            }
+            _ => panic!("Expected repeat step"),
+        }
+        match steps[1] {
+            Step::Repeat(step) => {
+                assert!(matches!(&step.step, GraphStep::Both(None)));
+                assert_eq!((step.min, step.max), (2, 2));
+            }
+            _ => panic!("Expected repeat step"),
+        }
+        assert!(matches!(steps[2], Step::Vertex(GraphStep::OutE(_))));
+
+        for invalid in ["Out{3,1}", "Out{0,2}", "OutE{1,2}"] {
+            let input = format!(
+                "QUERY reach(id) =>\n    nearby <- V(id)::{}\n    RETURN nearby",
+                invalid
+            );
+            assert!(HelixParser::parse_source(&input).is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_dedup_order_limit_steps() {
+        let input = r#"
+    QUERY oldest() =>
+        users <- V<User>::LIMIT(100)::Both<Follows>::DEDUP::DEDUP(name)::ORDER_BY(age, DESC)::ORDER_BY(name)
+        RETURN users
+    "#;
+        let result = HelixParser::parse_source(input).unwrap();
+        let steps = match &result.queries[0].statements[0] {
+            Statement::Assignment(Assignment {
+                value: Expression::Traversal(tr),
+                ..
+            }) => &tr.steps,
+            _ => panic!("Expected traversal assignment"),
+        };
+        assert!(matches!(steps[0], Step::Limit(100)));
+        assert!(matches!(steps[2], Step::Dedup(None)));
+        assert!(matches!(&steps[3], Step::Dedup(Some(property)) if property == "name"));
+        match (&steps[4], &steps[5]) {
+            (Step::OrderBy(desc), Step::OrderBy(asc)) => {
+                assert_eq!(
+                    (desc.property.as_str(), desc.order),
+                    ("age", SortOrder::Desc)
+                );
+                assert_eq!((asc.property.as_str(), asc.order), ("name", SortOrder::Asc));
+            }
+            _ => panic!("Expected order by steps"),
+        }
+
+        let input = "QUERY oldest() =>\n    users <- V::LIMIT(-1)\n    RETURN users";
+        assert!(HelixParser::parse_source(input).is_err());
+    }
+
+    #[test]
+    fn test_aggregation_steps() {
+        let input = r#"
+    QUERY stats() =>
+        by_city <- V<User>::GROUP_BY(city)::MEAN(age)
+        cities <- V<User>::GROUP_COUNT(city)
+        total <- V<User>::SUM(age)
+        names <- V<User>::COLLECT_SET(name)
+        RETURN by_city, cities, total, names
+    "#;
+        let result = HelixParser::parse_source(input).unwrap();
+        let steps: Vec<&Vec<Step>> = result.queries[0]
+            .statements
+            .iter()
+            .map(|stmt| match stmt {
+                Statement::Assignment(Assignment {
+                    value: Expression::Traversal(tr),
+                    ..
+                }) => &tr.steps,
+                _ => panic!("Expected traversal assignment"),
+            })
+            .collect();
+
+        assert!(matches!(&steps[0][0], Step::GroupBy(key) if key == "city"));
+        assert!(matches!(
+            &steps[0][1],
+            Step::Aggregate(AggregateStep { function: AggregateFunction::Mean, property }) if property == "age"
+        ));
+        assert!(matches!(&steps[1][0], Step::GroupCount(key) if key == "city"));
+        assert!(matches!(
+            &steps[2][0],
+            Step::Aggregate(AggregateStep {
+                function: AggregateFunction::Sum,
+                ..
+            })
+        ));
+        assert!(matches!(
+            &steps[3][0],
+            Step::Aggregate(AggregateStep { function: AggregateFunction::CollectSet, property }) if property == "name"
+        ));
+    }
+
+    #[test]
+    fn test_branching_steps() {
+        let input = r#"
+    QUERY suggestions(id) =>
+        people <- V(id)::COALESCE(_::Out<Follows>, _::Out<Suggested>)
+        reach <- V(id)::UNION(_::Out<Follows>, _::In<Follows>)::OPTIONAL(_::Out<Likes>)
+        picked <- V(id)::CHOOSE(_::Props(age)::GT(30), _::Out<Follows>)
+        RETURN people, reach, picked
+    "#;
+        let result = HelixParser::parse_source(input).unwrap();
+        let steps: Vec<&Vec<Step>> = result.queries[0]
+            .statements
+            .iter()
+            .map(|stmt| match stmt {
+                Statement::Assignment(Assignment {
+                    value: Expression::Traversal(tr),
+                    ..
+                }) => &tr.steps,
+                _ => panic!("Expected traversal assignment"),
+            })
+            .collect();
+
+        match &steps[0][0] {
+            Step::Coalesce(traversals) => {
+                assert_eq!(traversals.len(), 2);
+                assert!(matches!(traversals[0].start, StartNode::Anonymous));
+                assert!(matches!(
+                    &traversals[1].steps[0],
+                    Step::Vertex(GraphStep::Out(Some(types))) if types == &["Suggested"]
+                ));
+            }
+            _ => panic!("Expected coalesce step"),
+        }
+        assert!(matches!(&steps[1][0], Step::Union(traversals) if traversals.len() == 2));
+        assert!(matches!(&steps[1][1], Step::Optional(_)));
+        match &steps[2][0] {
+            Step::Choose(choose) => {
+                assert!(matches!(
+                    choose.predicate.steps[1],
+                    Step::BooleanOperation(_)
+                ));
+                assert!(choose.otherwise.is_none());
+            }
+            _ => panic!("Expected choose step"),
+        }
+
+        let input = "QUERY q(id) =>\n    p <- V(id)::CHOOSE(_::Out)\n    RETURN p";
+        assert!(HelixParser::parse_source(input).is_err());
+    }
+
+    #[test]
+    fn test_project_fields() {
+        let input = r#"
+    QUERY profiles() =>
+        users <- V<User>::{name: _::Props(name), followers: _::In<Follows>::COUNT}
+        RETURN users
+    "#;
+        let result = HelixParser::parse_source(input).unwrap();
+        let fields = match &result.queries[0].statements[0] {
+            Statement::Assignment(Assignment {
+                value: Expression::Traversal(tr),
+                ..
+            }) => match &tr.steps[0] {
+                Step::AddField(fields) => fields,
+                _ => panic!("Expected field additions"),
+            },
+            _ => panic!("Expected traversal assignment"),
+        };
+
+        assert_eq!(fields[0].name, "name");
+        match &fields[0].value {
+            Expression::Traversal(tr) => {
+                assert!(matches!(tr.start, StartNode::Anonymous));
+                assert!(matches!(&tr.steps[..], [Step::Props(props)] if props == &["name"]));
+            }
+            _ => panic!("Expected property traversal"),
+        }
+        assert_eq!(fields[1].name, "followers");
+        match &fields[1].value {
+            Expression::Traversal(tr) => {
+                assert!(matches!(tr.start, StartNode::Anonymous));
+                assert!(matches!(tr.steps[0], Step::Vertex(GraphStep::In(_))));
+                assert!(matches!(tr.steps[1], Step::Count));
+            }
+            _ => panic!("Expected anonymous traversal"),
+        }
+    }
+
     #[test]
     fn test_where_with_props() {
         let input = r#"
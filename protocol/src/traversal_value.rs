@@ -1,8 +1,12 @@
 use crate::{count::Count, Edge, filterable::Filterable, Node, value::Value};
-use serde::Serializer;
+use serde::{
+    de::{MapAccess, Visitor},
+    Deserializer, Serializer,
+};
 use sonic_rs::{Deserialize, Serialize};
-use std::borrow::Cow;
+use std::{borrow::Cow, fmt};
 
+/// Deserialised by trying each variant in order, so `Value`, which accepts any JSON, comes last
 #[derive(Deserialize, Clone)]
 #[serde(untagged)]
 pub enum TraversalValue {
@@ -11,15 +15,145 @@ pub enum TraversalValue {
     NodeArray(Vec<Node>),
     EdgeArray(Vec<Edge>),
     ValueArray(Vec<(String, Value)>),
-    Paths(Vec<(Vec<Node>, Vec<Edge>)>),
+    /// The nodes and edges along each path, with its total cost when found by a weighted search.
+    /// Serialised as `[nodes, edges]`, or `[nodes, edges, cost]` when the path has a cost.
+    Paths(
+        #[serde(deserialize_with = "deserialize_paths")] Vec<(Vec<Node>, Vec<Edge>, Option<f64>)>,
+    ),
+    /// The result of a step for each group of nodes or edges, ordered by group key.
+    /// Serialised as a JSON array of `{"key": ..., "value": ...}` objects,
+    /// so keys of any type stay distinct.
+    Groups(#[serde(deserialize_with = "deserialize_groups")] Vec<(Value, TraversalValue)>),
+    /// One row of named fields per traverser
+    Rows(Vec<Row>),
+    /// A single aggregated value, such as a sum or a mean
+    Value(Value),
 }
 
+/// A single group of a `TraversalValue::Groups` as it is written to JSON
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Group<K, V> {
+    key: K,
+    value: V,
+}
+
+fn deserialize_groups<'de, D>(deserializer: D) -> Result<Vec<(Value, TraversalValue)>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let groups = Vec::<Group<Value, TraversalValue>>::deserialize(deserializer)?;
+    Ok(groups
+        .into_iter()
+        .map(|group| (group.key, group.value))
+        .collect())
+}
+
+/// A single path of a `TraversalValue::Paths` as it is written to JSON
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum Path<N, E> {
+    Weighted(N, E, f64),
+    Unweighted(N, E),
+}
+
+/// The nodes, edges and cost of a path as it is held in `TraversalValue::Paths`
+type PathEntry = (Vec<Node>, Vec<Edge>, Option<f64>);
+
+fn deserialize_paths<'de, D>(deserializer: D) -> Result<Vec<PathEntry>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let paths = Vec::<Path<Vec<Node>, Vec<Edge>>>::deserialize(deserializer)?;
+    Ok(paths
+        .into_iter()
+        .map(|path| match path {
+            Path::Weighted(nodes, edges, cost) => (nodes, edges, Some(cost)),
+            Path::Unweighted(nodes, edges) => (nodes, edges, None),
+        })
+        .collect())
+}
+
+/// Named fields computed for a single traverser by `project` or `select`,
+/// in the order they were asked for. Serialised as a JSON object.
+#[derive(Clone, Default)]
+pub struct Row(pub Vec<(String, TraversalValue)>);
+
+impl Row {
+    /// Returns the value of the field called `name`
+    pub fn get(&self, name: &str) -> Option<&TraversalValue> {
+        self.0
+            .iter()
+            .find(|(field, _)| field == name)
+            .map(|(_, value)| value)
+    }
+}
+
+impl std::fmt::Debug for Row {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_map()
+            .entries(self.0.iter().map(|(k, v)| (k, v)))
+            .finish()
+    }
+}
+
+impl Serialize for Row {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        use serde::ser::SerializeMap;
+        let mut map = serializer.serialize_map(Some(self.0.len()))?;
+        for (name, value) in &self.0 {
+            map.serialize_entry(name, value)?;
+        }
+        map.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for Row {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        /// Reads the fields of a JSON object in the order they are written
+        struct RowVisitor;
+
+        impl<'de> Visitor<'de> for RowVisitor {
+            type Value = Row;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("an object of named fields")
+            }
+
+            fn visit_map<M>(self, mut map: M) -> Result<Row, M::Error>
+            where
+                M: MapAccess<'de>,
+            {
+                let mut fields = Vec::with_capacity(map.size_hint().unwrap_or(0));
+                while let Some(field) = map.next_entry()? {
+                    fields.push(field);
+                }
+                Ok(Row(fields))
+            }
+        }
+
+        deserializer.deserialize_map(RowVisitor)
+    }
+}
+
+/// Joins the items of the first kind present, in the order nodes, edges, values, paths,
+/// groups, rows and then single values, which are gathered into a `Value::Array` when there
+/// is more than one.
 impl FromIterator<TraversalValue> for TraversalValue {
     fn from_iter<T: IntoIterator<Item = TraversalValue>>(iter: T) -> Self {
         let mut nodes = Vec::with_capacity(10);
         let mut edges = Vec::with_capacity(10);
         let mut values = Vec::with_capacity(10);
         let mut paths = Vec::with_capacity(10);
+        let mut groups = Vec::new();
+        let mut rows = Vec::new();
+        let mut scalars = Vec::new();
 
         for value in iter {
             match value {
@@ -28,6 +162,9 @@ impl FromIterator<TraversalValue> for TraversalValue {
                 TraversalValue::EdgeArray(mut edge_vec) => edges.append(&mut edge_vec),
                 TraversalValue::ValueArray(mut value_vec) => values.append(&mut value_vec),
                 TraversalValue::Paths(mut path_vecs) => paths.append(&mut path_vecs),
+                TraversalValue::Value(value) => scalars.push(value),
+                TraversalValue::Groups(mut group_vec) => groups.append(&mut group_vec),
+                TraversalValue::Rows(mut row_vec) => rows.append(&mut row_vec),
                 TraversalValue::Empty => (),
             }
         }
@@ -38,6 +175,16 @@ impl FromIterator<TraversalValue> for TraversalValue {
             TraversalValue::EdgeArray(edges)
         } else if !values.is_empty() {
             TraversalValue::ValueArray(values)
+        } else if !paths.is_empty() {
+            TraversalValue::Paths(paths)
+        } else if !groups.is_empty() {
+            TraversalValue::Groups(groups)
+        } else if !rows.is_empty() {
+            TraversalValue::Rows(rows)
+        } else if scalars.len() > 1 {
+            TraversalValue::Value(Value::Array(scalars))
+        } else if let Some(value) = scalars.pop() {
+            TraversalValue::Value(value)
         } else {
             TraversalValue::Empty
         }
@@ -86,6 +233,9 @@ impl std::fmt::Debug for TraversalValue {
             TraversalValue::EdgeArray(edges) => edges.fmt(f),
             TraversalValue::ValueArray(values) => values.fmt(f),
             TraversalValue::Paths(paths) => paths.fmt(f),
+            TraversalValue::Value(value) => value.fmt(f),
+            TraversalValue::Groups(groups) => groups.fmt(f),
+            TraversalValue::Rows(rows) => rows.fmt(f),
         }
     }
 }
@@ -101,7 +251,45 @@ impl Serialize for TraversalValue {
             TraversalValue::NodeArray(nodes) => nodes.serialize(serializer),
             TraversalValue::EdgeArray(edges) => edges.serialize(serializer),
             TraversalValue::ValueArray(values) => values.serialize(serializer),
-            TraversalValue::Paths(paths) => paths.serialize(serializer),
+            TraversalValue::Paths(paths) => {
+                serializer.collect_seq(paths.iter().map(|(nodes, edges, cost)| match cost {
+                    Some(cost) => Path::Weighted(nodes, edges, *cost),
+                    None => Path::Unweighted(nodes, edges),
+                }))
+            }
+            TraversalValue::Groups(groups) => {
+                serializer.collect_seq(groups.iter().map(|(key, value)| Group { key, value }))
+            }
+            TraversalValue::Rows(rows) => rows.serialize(serializer),
+            TraversalValue::Value(value) => value.serialize(serializer),
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_collect_keeps_single_values() {
+        let collected: TraversalValue = vec![
+            TraversalValue::Value(Value::from(1)),
+            TraversalValue::Empty,
+            TraversalValue::Value(Value::from(2)),
+        ]
+        .into_iter()
+        .collect();
+        assert!(matches!(
+            collected,
+            TraversalValue::Value(Value::Array(values)) if values.len() == 2
+        ));
+
+        let collected: TraversalValue = vec![TraversalValue::Value(Value::from(1))]
+            .into_iter()
+            .collect();
+        assert!(matches!(
+            collected,
+            TraversalValue::Value(Value::Integer(1))
+        ));
+    }
+}
@@ -3,11 +3,22 @@ use serde::{
     Deserializer, Serializer,
 };
 use sonic_rs::{Deserialize, Serialize};
-use std::{collections::HashMap, fmt};
+use std::{
+    cmp::Ordering,
+    collections::HashMap,
+    fmt,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
 
 /// A flexible value type that can represent various property values in nodes and edges.
 /// Handles both JSON and binary serialisation formats via custom implementaions of the Serialize and Deserialize traits.
-#[derive(Clone, PartialEq, Debug)]
+///
+/// `Empty` represents a missing value (e.g. a property that does not exist on a node),
+/// whereas `Null` is an explicitly stored null.
+///
+/// Values have a total ordering across types, see the `Ord` implementation below.
+/// Strings are ordered by their UTF-8 bytes rather than by a locale's collation.
+#[derive(Clone, Debug)]
 pub enum Value {
     String(String),
     Float(f64),
@@ -15,6 +26,156 @@ pub enum Value {
     Boolean(bool),
     Array(Vec<Value>),
     Empty,
+    I64(i64),
+    U64(u64),
+    /// Milliseconds since the Unix epoch
+    Timestamp(i64),
+    Bytes(Vec<u8>),
+    Map(HashMap<String, Value>),
+    Null,
+}
+
+impl Value {
+    /// Creates a timestamp value for the current time
+    pub fn timestamp_now() -> Value {
+        Value::from(SystemTime::now())
+    }
+
+    /// Parses an RFC 3339 date-time such as `2024-01-31T12:00:00Z` or
+    /// `2024-01-31T12:00:00.250+01:00` into a timestamp value
+    pub fn timestamp_from_rfc3339(s: &str) -> Option<Value> {
+        parse_rfc3339_millis(s).map(Value::Timestamp)
+    }
+
+    /// Returns the value as an i64 if it is an integer type that fits
+    #[inline]
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            Value::Integer(i) => Some(*i as i64),
+            Value::I64(i) | Value::Timestamp(i) => Some(*i),
+            Value::U64(u) => i64::try_from(*u).ok(),
+            _ => None,
+        }
+    }
+
+    /// Returns the value as an f64 if it is numeric
+    #[inline]
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Value::Float(f) => Some(*f),
+            Value::Integer(i) => Some(*i as f64),
+            Value::I64(i) | Value::Timestamp(i) => Some(*i as f64),
+            Value::U64(u) => Some(*u as f64),
+            _ => None,
+        }
+    }
+
+    /// Returns true for `Null` and `Empty`
+    #[inline]
+    pub fn is_null(&self) -> bool {
+        matches!(self, Value::Null | Value::Empty)
+    }
+}
+
+/// Parses an RFC 3339 date-time into milliseconds since the Unix epoch
+fn parse_rfc3339_millis(s: &str) -> Option<i64> {
+    let b = s.trim().as_bytes();
+    let num = |range: std::ops::Range<usize>| -> Option<i64> {
+        let digits = b.get(range)?;
+        if digits.is_empty() || !digits.iter().all(u8::is_ascii_digit) {
+            return None;
+        }
+        std::str::from_utf8(digits).ok()?.parse().ok()
+    };
+    if b.len() < 20 || b[4] != b'-' || b[7] != b'-' || !matches!(b[10], b'T' | b't' | b' ') {
+        return None;
+    }
+    if b[13] != b':' || b[16] != b':' {
+        return None;
+    }
+    let (year, month, day) = (num(0..4)?, num(5..7)?, num(8..10)?);
+    let (hour, minute, second) = (num(11..13)?, num(14..16)?, num(17..19)?);
+    if !(1..=12).contains(&month)
+        || !(1..=31).contains(&day)
+        || hour > 23
+        || minute > 59
+        || second > 60
+    {
+        return None;
+    }
+
+    let mut i = 19;
+    let mut millis = 0;
+    if b.get(i) == Some(&b'.') {
+        let start = i + 1;
+        i = start;
+        while b.get(i).is_some_and(u8::is_ascii_digit) {
+            i += 1;
+        }
+        if i == start {
+            return None;
+        }
+        // only millisecond precision is kept
+        let frac = &s.trim()[start..i.min(start + 3)];
+        millis = frac.parse::<i64>().ok()? * 10_i64.pow(3 - frac.len() as u32);
+    }
+
+    let offset_minutes = match b.get(i)? {
+        b'Z' | b'z' if i + 1 == b.len() => 0,
+        sign @ (b'+' | b'-') if i + 6 == b.len() && b[i + 3] == b':' => {
+            let offset = num(i + 1..i + 3)? * 60 + num(i + 4..i + 6)?;
+            if *sign == b'+' {
+                offset
+            } else {
+                -offset
+            }
+        }
+        _ => return None,
+    };
+
+    // days from civil date (proleptic Gregorian calendar)
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let mp = (month + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    let days = era * 146097 + doe - 719468;
+
+    let seconds = days * 86400 + hour * 3600 + minute * 60 + second - offset_minutes * 60;
+    Some(seconds * 1000 + millis)
+}
+
+/// Formats milliseconds since the Unix epoch as an RFC 3339 date-time in UTC,
+/// e.g. `2024-01-31T12:00:00.250Z`. Returns `None` for years outside 0000-9999.
+fn format_rfc3339_millis(millis: i64) -> Option<String> {
+    let days = millis.div_euclid(86_400_000);
+    let ms_of_day = millis.rem_euclid(86_400_000);
+
+    // civil date from days (proleptic Gregorian calendar)
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    if !(0..=9999).contains(&year) {
+        return None;
+    }
+
+    Some(format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+        year,
+        month,
+        day,
+        ms_of_day / 3_600_000,
+        ms_of_day / 60_000 % 60,
+        ms_of_day / 1000 % 60,
+        ms_of_day % 1000
+    ))
 }
 
 /// Custom serialisation implementation for Value that removes enum variant names in JSON
@@ -25,6 +186,7 @@ impl Serialize for Value {
         S: Serializer,
     {
         if serializer.is_human_readable() {
+            use serde::ser::SerializeMap;
             match self {
                 Value::String(s) => s.serialize(serializer),
                 Value::Float(f) => f.serialize(serializer),
@@ -39,6 +201,22 @@ impl Serialize for Value {
                     seq.end()
                 }
                 Value::Empty => serializer.serialize_none(),
+                Value::I64(i) => json_tags::serialize(serializer, json_tags::I64, &i.to_string()),
+                Value::U64(u) => json_tags::serialize(serializer, json_tags::U64, &u.to_string()),
+                Value::Timestamp(t) => match format_rfc3339_millis(*t) {
+                    Some(s) => json_tags::serialize(serializer, json_tags::TIMESTAMP, &s),
+                    None => json_tags::serialize(serializer, json_tags::TIMESTAMP, t),
+                },
+                Value::Bytes(b) => {
+                    json_tags::serialize(serializer, json_tags::BYTES, &base64::encode(b))
+                }
+                Value::Map(map) if json_tags::is_ambiguous(map) => {
+                    let mut outer = serializer.serialize_map(Some(1))?;
+                    outer.serialize_entry(json_tags::MAP, &PropertiesRef(map))?;
+                    outer.end()
+                }
+                Value::Map(map) => properties_format::serialize(map, serializer),
+                Value::Null => json_tags::serialize(serializer, json_tags::NULL, &()),
             }
         } else {
             match self {
@@ -48,6 +226,14 @@ impl Serialize for Value {
                 Value::Boolean(b) => serializer.serialize_newtype_variant("Value", 3, "Boolean", b),
                 Value::Array(a) => serializer.serialize_newtype_variant("Value", 4, "Array", a),
                 Value::Empty => serializer.serialize_unit_variant("Value", 5, "Empty"),
+                Value::I64(i) => serializer.serialize_newtype_variant("Value", 6, "I64", i),
+                Value::U64(u) => serializer.serialize_newtype_variant("Value", 7, "U64", u),
+                Value::Timestamp(t) => {
+                    serializer.serialize_newtype_variant("Value", 8, "Timestamp", t)
+                }
+                Value::Bytes(b) => serializer.serialize_newtype_variant("Value", 9, "Bytes", b),
+                Value::Map(m) => serializer.serialize_newtype_variant("Value", 10, "Map", m),
+                Value::Null => serializer.serialize_unit_variant("Value", 11, "Null"),
             }
         }
     }
@@ -70,7 +256,9 @@ impl<'de> Deserialize<'de> for Value {
 
             #[inline]
             fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
-                formatter.write_str("a string, number, boolean, array, null, or Value enum")
+                formatter.write_str(
+                    "a string, number, boolean, array, object, bytes, null, or Value enum",
+                )
             }
 
             #[inline]
@@ -97,6 +285,48 @@ impl<'de> Deserialize<'de> for Value {
                 Ok(Value::Integer(value))
             }
 
+            /// Integers that fit in an i32 stay as `Integer` so existing data keeps its type
+            #[inline]
+            fn visit_i64<E>(self, value: i64) -> Result<Value, E>
+            where
+                E: serde::de::Error,
+            {
+                Ok(match i32::try_from(value) {
+                    Ok(i) => Value::Integer(i),
+                    Err(_) => Value::I64(value),
+                })
+            }
+
+            /// Plain JSON numbers are read as the narrowest integer type that fits.
+            /// `I64` and `U64` values written by `Serialize` are tagged so they keep their type.
+            #[inline]
+            fn visit_u64<E>(self, value: u64) -> Result<Value, E>
+            where
+                E: serde::de::Error,
+            {
+                Ok(match (i32::try_from(value), i64::try_from(value)) {
+                    (Ok(i), _) => Value::Integer(i),
+                    (_, Ok(i)) => Value::I64(i),
+                    _ => Value::U64(value),
+                })
+            }
+
+            #[inline]
+            fn visit_bytes<E>(self, value: &[u8]) -> Result<Value, E>
+            where
+                E: serde::de::Error,
+            {
+                Ok(Value::Bytes(value.to_vec()))
+            }
+
+            #[inline]
+            fn visit_byte_buf<E>(self, value: Vec<u8>) -> Result<Value, E>
+            where
+                E: serde::de::Error,
+            {
+                Ok(Value::Bytes(value))
+            }
+
             #[inline]
             fn visit_f64<E>(self, value: f64) -> Result<Value, E>
             where
@@ -121,6 +351,41 @@ impl<'de> Deserialize<'de> for Value {
                 Ok(Value::Empty)
             }
 
+            #[inline]
+            fn visit_unit<E>(self) -> Result<Value, E>
+            where
+                E: serde::de::Error,
+            {
+                Ok(Value::Empty)
+            }
+
+            /// Handles JSON objects by recursively deserialising each entry.
+            /// Objects with a single `$`-prefixed tag key are read back as the tagged variant.
+            fn visit_map<A>(self, mut map: A) -> Result<Value, A::Error>
+            where
+                A: serde::de::MapAccess<'de>,
+            {
+                let mut values = HashMap::with_capacity(map.size_hint().unwrap_or(0));
+                let Some(first) = map.next_key::<String>()? else {
+                    return Ok(Value::Map(values));
+                };
+                if json_tags::is_tag(&first) {
+                    let value = json_tags::deserialize(&first, &mut map)?;
+                    if map.next_key::<String>()?.is_some() {
+                        return Err(serde::de::Error::custom(format!(
+                            "unexpected key after {}",
+                            first
+                        )));
+                    }
+                    return Ok(value);
+                }
+                values.insert(first, map.next_value()?);
+                while let Some((key, value)) = map.next_entry()? {
+                    values.insert(key, value);
+                }
+                Ok(Value::Map(values))
+            }
+
             /// Handles array values by recursively deserialising each element
             fn visit_seq<A>(self, mut seq: A) -> Result<Value, A::Error>
             where
@@ -134,7 +399,7 @@ impl<'de> Deserialize<'de> for Value {
             }
 
             /// Handles binary format deserialisation using numeric indices to identify variants
-            /// Maps indices 0-5 to corresponding Value enum variants
+            /// Maps indices 0-11 to corresponding Value enum variants
             fn visit_enum<A>(self, data: A) -> Result<Self::Value, A::Error>
             where
                 A: serde::de::EnumAccess<'de>,
@@ -150,9 +415,18 @@ impl<'de> Deserialize<'de> for Value {
                         variant_data.unit_variant()?;
                         Ok(Value::Empty)
                     }
+                    6 => Ok(Value::I64(variant_data.newtype_variant()?)),
+                    7 => Ok(Value::U64(variant_data.newtype_variant()?)),
+                    8 => Ok(Value::Timestamp(variant_data.newtype_variant()?)),
+                    9 => Ok(Value::Bytes(variant_data.newtype_variant()?)),
+                    10 => Ok(Value::Map(variant_data.newtype_variant()?)),
+                    11 => {
+                        variant_data.unit_variant()?;
+                        Ok(Value::Null)
+                    }
                     _ => Err(serde::de::Error::invalid_value(
                         serde::de::Unexpected::Unsigned(variant_idx as u64),
-                        &"variant index 0 through 5",
+                        &"variant index 0 through 11",
                     )),
                 }
             }
@@ -196,13 +470,169 @@ impl<'de> Deserialize<'de> for Value {
             // For binary, use enum variant indices
             deserializer.deserialize_enum(
                 "Value",
-                &["String", "Float", "Integer", "Boolean", "Array", "Empty"],
+                &[
+                    "String",
+                    "Float",
+                    "Integer",
+                    "Boolean",
+                    "Array",
+                    "Empty",
+                    "I64",
+                    "U64",
+                    "Timestamp",
+                    "Bytes",
+                    "Map",
+                    "Null",
+                ],
                 ValueVisitor,
             )
         }
     }
 }
 
+/// Tagged JSON objects used for the variants that have no native JSON type,
+/// so that every variant reads back as itself.
+///
+/// - `I64` / `U64`: `{"$i64": "-5"}` / `{"$u64": "5"}`, as strings so no precision is lost
+/// - `Timestamp`: `{"$timestamp": "2024-01-31T12:00:00.250Z"}` (RFC 3339, UTC), or the
+///   milliseconds since the Unix epoch for years outside 0000-9999
+/// - `Bytes`: `{"$bytes": "AP8="}` (standard base64 with padding)
+/// - `Null`: `{"$null": null}`, a bare `null` is `Empty`
+/// - `Map`: a plain object, or `{"$map": {...}}` if one of its keys is a tag
+mod json_tags {
+    use super::*;
+    use serde::de::{Error, MapAccess};
+    use serde::ser::SerializeMap;
+
+    pub const I64: &str = "$i64";
+    pub const U64: &str = "$u64";
+    pub const TIMESTAMP: &str = "$timestamp";
+    pub const BYTES: &str = "$bytes";
+    pub const NULL: &str = "$null";
+    pub const MAP: &str = "$map";
+
+    const TAGS: [&str; 6] = [I64, U64, TIMESTAMP, BYTES, NULL, MAP];
+
+    #[inline]
+    pub fn is_tag(key: &str) -> bool {
+        TAGS.contains(&key)
+    }
+
+    /// Maps containing a tag key are wrapped so they are not read back as a tagged value
+    #[inline]
+    pub fn is_ambiguous(map: &HashMap<String, Value>) -> bool {
+        map.keys().any(|k| is_tag(k))
+    }
+
+    pub fn serialize<S, T>(serializer: S, tag: &str, value: &T) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+        T: Serialize + ?Sized,
+    {
+        let mut map = serializer.serialize_map(Some(1))?;
+        map.serialize_entry(tag, value)?;
+        map.end()
+    }
+
+    /// Reads the value of a tag key, the tag itself has already been read
+    pub fn deserialize<'de, A>(tag: &str, map: &mut A) -> Result<Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let invalid = |what: &str| A::Error::custom(format!("invalid {} value: {}", tag, what));
+        match tag {
+            I64 => {
+                let s = map.next_value::<String>()?;
+                s.parse().map(Value::I64).map_err(|_| invalid(&s))
+            }
+            U64 => {
+                let s = map.next_value::<String>()?;
+                s.parse().map(Value::U64).map_err(|_| invalid(&s))
+            }
+            TIMESTAMP => match map.next_value::<Value>()? {
+                Value::String(s) => parse_rfc3339_millis(&s)
+                    .map(Value::Timestamp)
+                    .ok_or_else(|| invalid(&s)),
+                Value::Integer(i) => Ok(Value::Timestamp(i as i64)),
+                Value::I64(i) => Ok(Value::Timestamp(i)),
+                _ => Err(invalid("expected a string or integer")),
+            },
+            BYTES => {
+                let s = map.next_value::<String>()?;
+                base64::decode(&s)
+                    .map(Value::Bytes)
+                    .ok_or_else(|| invalid(&s))
+            }
+            NULL => {
+                map.next_value::<Value>()?;
+                Ok(Value::Null)
+            }
+            MAP => Ok(Value::Map(map.next_value()?)),
+            _ => Err(invalid("unknown tag")),
+        }
+    }
+}
+
+/// Standard base64 with padding, used for `Bytes` in JSON
+mod base64 {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    pub fn encode(bytes: &[u8]) -> String {
+        let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+        for chunk in bytes.chunks(3) {
+            let b = [
+                chunk[0],
+                chunk.get(1).copied().unwrap_or(0),
+                chunk.get(2).copied().unwrap_or(0),
+            ];
+            let n = (b[0] as u32) << 16 | (b[1] as u32) << 8 | b[2] as u32;
+            for i in 0..4 {
+                match i <= chunk.len() {
+                    true => out.push(ALPHABET[(n >> (18 - 6 * i) & 0x3f) as usize] as char),
+                    false => out.push('='),
+                }
+            }
+        }
+        out
+    }
+
+    pub fn decode(s: &str) -> Option<Vec<u8>> {
+        let s = s.as_bytes();
+        if !s.len().is_multiple_of(4) {
+            return None;
+        }
+        let mut out = Vec::with_capacity(s.len() / 4 * 3);
+        for (i, chunk) in s.chunks(4).enumerate() {
+            let last = i + 1 == s.len() / 4;
+            let padding = chunk.iter().rev().take_while(|&&c| c == b'=').count();
+            if padding > 2 || (padding > 0 && !last) {
+                return None;
+            }
+            let mut n = 0u32;
+            for &c in &chunk[..4 - padding] {
+                let v = ALPHABET.iter().position(|&a| a == c)? as u32;
+                n = n << 6 | v;
+            }
+            n <<= 6 * padding as u32;
+            out.extend_from_slice(&n.to_be_bytes()[1..4 - padding]);
+        }
+        Some(out)
+    }
+}
+
+/// Borrowed property map serialised with `properties_format`
+struct PropertiesRef<'a>(&'a HashMap<String, Value>);
+
+impl Serialize for PropertiesRef<'_> {
+    #[inline]
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        properties_format::serialize(self.0, serializer)
+    }
+}
+
 /// Module for custom serialisation of property hashmaps
 /// Ensures consistent handling of Value enum serialisation within property maps
 pub mod properties_format {
@@ -275,6 +705,74 @@ impl From<Vec<Value>> for Value {
     }
 }
 
+impl From<i64> for Value {
+    #[inline]
+    fn from(i: i64) -> Self {
+        Value::I64(i)
+    }
+}
+
+impl From<u64> for Value {
+    #[inline]
+    fn from(u: u64) -> Self {
+        Value::U64(u)
+    }
+}
+
+impl From<Vec<u8>> for Value {
+    #[inline]
+    fn from(b: Vec<u8>) -> Self {
+        Value::Bytes(b)
+    }
+}
+
+impl From<&[u8]> for Value {
+    #[inline]
+    fn from(b: &[u8]) -> Self {
+        Value::Bytes(b.to_vec())
+    }
+}
+
+impl From<HashMap<String, Value>> for Value {
+    #[inline]
+    fn from(m: HashMap<String, Value>) -> Self {
+        Value::Map(m)
+    }
+}
+
+impl From<SystemTime> for Value {
+    #[inline]
+    fn from(t: SystemTime) -> Self {
+        match t.duration_since(UNIX_EPOCH) {
+            Ok(d) => Value::Timestamp(d.as_millis() as i64),
+            Err(e) => Value::Timestamp(-(e.duration().as_millis() as i64)),
+        }
+    }
+}
+
+impl<T: Into<Value>> From<Option<T>> for Value {
+    #[inline]
+    fn from(v: Option<T>) -> Self {
+        match v {
+            Some(v) => v.into(),
+            None => Value::Null,
+        }
+    }
+}
+
+impl TryFrom<&Value> for SystemTime {
+    type Error = &'static str;
+
+    #[inline]
+    fn try_from(v: &Value) -> Result<Self, Self::Error> {
+        match v {
+            Value::Timestamp(t) if *t >= 0 => Ok(UNIX_EPOCH + Duration::from_millis(*t as u64)),
+            Value::Timestamp(t) => Ok(UNIX_EPOCH - Duration::from_millis(t.unsigned_abs())),
+            _ => Err("Value is not a timestamp"),
+        }
+    }
+}
+
 impl From<Value> for String {
     #[inline]
     fn from(v: Value) -> Self {
@@ -284,3 +782,424 @@ impl From<Value> for String {
         }
     }
 }
+
+/// Type ranks used to order values of different types.
+/// Numbers of any width share a rank so they are compared by their numeric value.
+#[inline]
+fn type_rank(v: &Value) -> u8 {
+    match v {
+        Value::Empty | Value::Null => 0,
+        Value::Boolean(_) => 1,
+        Value::Integer(_) | Value::I64(_) | Value::U64(_) | Value::Float(_) => 2,
+        Value::Timestamp(_) => 3,
+        Value::String(_) => 4,
+        Value::Bytes(_) => 5,
+        Value::Array(_) => 6,
+        Value::Map(_) => 7,
+    }
+}
+
+/// A number widened so that every integer type is exact
+#[derive(Clone, Copy)]
+enum Number {
+    Int(i128),
+    Float(f64),
+}
+
+impl Number {
+    #[inline]
+    fn of(v: &Value) -> Option<Number> {
+        match v {
+            Value::Integer(i) => Some(Number::Int(*i as i128)),
+            Value::I64(i) => Some(Number::Int(*i as i128)),
+            Value::U64(u) => Some(Number::Int(*u as i128)),
+            Value::Float(f) => Some(Number::Float(*f)),
+            _ => None,
+        }
+    }
+
+    #[inline]
+    fn cmp(self, other: Number) -> Ordering {
+        match (self, other) {
+            (Number::Int(a), Number::Int(b)) => a.cmp(&b),
+            (Number::Float(a), Number::Float(b)) => cmp_floats(a, b),
+            (Number::Int(a), Number::Float(b)) => cmp_int_float(a, b),
+            (Number::Float(a), Number::Int(b)) => cmp_int_float(b, a).reverse(),
+        }
+    }
+}
+
+/// Orders floats numerically with `-0.0 == 0.0` and NaN greater than every other number
+#[inline]
+fn cmp_floats(a: f64, b: f64) -> Ordering {
+    match (a.is_nan(), b.is_nan()) {
+        (true, true) => Ordering::Equal,
+        (true, false) => Ordering::Greater,
+        (false, true) => Ordering::Less,
+        (false, false) => a.partial_cmp(&b).unwrap_or(Ordering::Equal),
+    }
+}
+
+/// Compares an integer with a float exactly, without rounding the integer to an f64
+fn cmp_int_float(i: i128, f: f64) -> Ordering {
+    const I128_BOUND: f64 = 1.7014118346046923e38; // 2^127
+    if f.is_nan() || f >= I128_BOUND {
+        return Ordering::Less;
+    }
+    if f < -I128_BOUND {
+        return Ordering::Greater;
+    }
+    let whole = f.trunc();
+    match i.cmp(&(whole as i128)) {
+        Ordering::Equal => 0.0.partial_cmp(&(f - whole)).unwrap_or(Ordering::Equal),
+        ord => ord,
+    }
+}
+
+/// Map entries sorted by key so maps can be compared independently of hash order
+#[inline]
+fn sorted_entries(map: &HashMap<String, Value>) -> Vec<(&String, &Value)> {
+    let mut entries: Vec<_> = map.iter().collect();
+    entries.sort_unstable_by(|a, b| a.0.cmp(b.0));
+    entries
+}
+
+/// Total ordering across all value types.
+///
+/// - Types are ordered `Empty < Null < Boolean < numbers < Timestamp < String < Bytes < Array < Map`
+/// - `Integer`, `I64`, `U64` and `Float` are compared by numeric value, so `Integer(1) == Float(1.0)`.
+///   NaN is equal to itself and greater than every other number.
+/// - Strings are compared by their UTF-8 bytes (equivalently, by Unicode code point).
+///   There is no locale-aware collation or case folding, so `"B" < "a" < "é"`.
+/// - Arrays are compared element by element, and maps by their entries sorted by key
+impl Ord for Value {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let rank = type_rank(self).cmp(&type_rank(other));
+        if rank != Ordering::Equal {
+            return rank;
+        }
+        match (self, other) {
+            (Value::Empty, Value::Empty) | (Value::Null, Value::Null) => Ordering::Equal,
+            (Value::Empty, Value::Null) => Ordering::Less,
+            (Value::Null, Value::Empty) => Ordering::Greater,
+            (Value::Boolean(a), Value::Boolean(b)) => a.cmp(b),
+            (Value::Timestamp(a), Value::Timestamp(b)) => a.cmp(b),
+            (Value::String(a), Value::String(b)) => a.cmp(b),
+            (Value::Bytes(a), Value::Bytes(b)) => a.cmp(b),
+            (Value::Array(a), Value::Array(b)) => a.cmp(b),
+            (Value::Map(a), Value::Map(b)) => sorted_entries(a).cmp(&sorted_entries(b)),
+            (a, b) => match (Number::of(a), Number::of(b)) {
+                (Some(a), Some(b)) => a.cmp(b),
+                _ => unreachable!("values with the same type rank"),
+            },
+        }
+    }
+}
+
+impl PartialOrd for Value {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Equality is defined by the total ordering so that it is consistent with `Ord`
+impl PartialEq for Value {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Value {}
+
+/// Order-preserving binary encoding of values.
+///
+/// For any two values `a.cmp(&b) == a.to_ordered_bytes().cmp(&b.to_ordered_bytes())`,
+/// so the encoding can be used directly as (part of) a storage key for range indexes.
+/// Encodings are self-delimiting, so several values can be concatenated into a composite key.
+pub mod ordered_encoding {
+    use super::*;
+
+    const TAG_EMPTY: u8 = 0x01;
+    const TAG_NULL: u8 = 0x02;
+    const TAG_BOOLEAN: u8 = 0x10;
+    const TAG_NUMBER: u8 = 0x20;
+    const TAG_TIMESTAMP: u8 = 0x30;
+    const TAG_STRING: u8 = 0x40;
+    const TAG_BYTES: u8 = 0x50;
+    const TAG_ARRAY: u8 = 0x60;
+    const TAG_MAP: u8 = 0x70;
+
+    const END: u8 = 0x00;
+    const MAP_ENTRY: u8 = 0x01;
+
+    impl Value {
+        /// Encodes the value into bytes that sort in the same order as the values
+        pub fn to_ordered_bytes(&self) -> Vec<u8> {
+            let mut out = Vec::with_capacity(16);
+            self.encode_ordered(&mut out);
+            out
+        }
+
+        /// Appends the order-preserving encoding of the value to `out`
+        pub fn encode_ordered(&self, out: &mut Vec<u8>) {
+            match self {
+                Value::Empty => out.push(TAG_EMPTY),
+                Value::Null => out.push(TAG_NULL),
+                Value::Boolean(b) => {
+                    out.push(TAG_BOOLEAN);
+                    out.push(*b as u8);
+                }
+                Value::Integer(_) | Value::I64(_) | Value::U64(_) | Value::Float(_) => {
+                    out.push(TAG_NUMBER);
+                    match Number::of(self) {
+                        Some(Number::Int(i)) => encode_int(i, out),
+                        Some(Number::Float(f)) => encode_float(f, out),
+                        None => unreachable!(),
+                    }
+                }
+                Value::Timestamp(t) => {
+                    out.push(TAG_TIMESTAMP);
+                    out.extend_from_slice(&((*t as u64) ^ (1 << 63)).to_be_bytes());
+                }
+                Value::String(s) => {
+                    out.push(TAG_STRING);
+                    encode_escaped(s.as_bytes(), out);
+                }
+                Value::Bytes(b) => {
+                    out.push(TAG_BYTES);
+                    encode_escaped(b, out);
+                }
+                Value::Array(arr) => {
+                    out.push(TAG_ARRAY);
+                    for v in arr {
+                        v.encode_ordered(out);
+                    }
+                    out.push(END);
+                }
+                Value::Map(map) => {
+                    out.push(TAG_MAP);
+                    for (k, v) in sorted_entries(map) {
+                        out.push(MAP_ENTRY);
+                        encode_escaped(k.as_bytes(), out);
+                        v.encode_ordered(out);
+                    }
+                    out.push(END);
+                }
+            }
+        }
+    }
+
+    /// Encodes a float as sortable bits of its value followed by a zero correction term
+    fn encode_float(f: f64, out: &mut Vec<u8>) {
+        let f = if f.is_nan() {
+            f64::NAN
+        } else if f == 0.0 {
+            0.0 // normalise -0.0
+        } else {
+            f
+        };
+        out.extend_from_slice(&sortable_f64_bits(f).to_be_bytes());
+        out.extend_from_slice(&(1u64 << 63).to_be_bytes());
+    }
+
+    /// Encodes an integer as the sortable bits of the nearest f64 followed by the
+    /// exact difference between the integer and that f64.
+    /// Rounding is monotonic, so this orders correctly against floats and other integers.
+    fn encode_int(i: i128, out: &mut Vec<u8>) {
+        let approx = i as f64;
+        let correction = (i - approx as i128) as i64;
+        out.extend_from_slice(&sortable_f64_bits(approx).to_be_bytes());
+        out.extend_from_slice(&((correction as u64) ^ (1 << 63)).to_be_bytes());
+    }
+
+    #[inline]
+    fn sortable_f64_bits(f: f64) -> u64 {
+        let bits = f.to_bits();
+        if bits & (1 << 63) != 0 {
+            !bits
+        } else {
+            bits | (1 << 63)
+        }
+    }
+
+    /// Escapes `0x00` as `0x00 0xFF` and terminates with `0x00 0x01`
+    /// so shorter strings sort before longer strings with the same prefix
+    fn encode_escaped(bytes: &[u8], out: &mut Vec<u8>) {
+        for &b in bytes {
+            out.push(b);
+            if b == 0x00 {
+                out.push(0xFF);
+            }
+        }
+        out.push(0x00);
+        out.push(0x01);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn json_roundtrip(value: &Value) -> Value {
+        let json = serde_json::to_string(value).unwrap();
+        serde_json::from_str(&json).unwrap()
+    }
+
+    fn bincode_roundtrip(value: &Value) -> Value {
+        bincode::deserialize(&bincode::serialize(value).unwrap()).unwrap()
+    }
+
+    /// Compares variants as well as values, as `PartialEq` treats equal numbers as equal
+    fn identical(a: &Value, b: &Value) -> bool {
+        match (a, b) {
+            (Value::Array(a), Value::Array(b)) => {
+                a.len() == b.len() && a.iter().zip(b).all(|(a, b)| identical(a, b))
+            }
+            (Value::Map(a), Value::Map(b)) => {
+                a.len() == b.len()
+                    && a.iter()
+                        .all(|(k, v)| b.get(k).is_some_and(|w| identical(v, w)))
+            }
+            _ => std::mem::discriminant(a) == std::mem::discriminant(b) && a == b,
+        }
+    }
+
+    fn assert_identical(a: &Value, b: &Value) {
+        assert!(identical(a, b), "{:?} != {:?}", a, b);
+    }
+
+    fn sample_values() -> Vec<Value> {
+        vec![
+            Value::String("hello".to_string()),
+            Value::String("2024-01-31T12:00:00Z".to_string()),
+            Value::Float(1.5),
+            Value::Integer(-7),
+            Value::Boolean(true),
+            Value::Array(vec![Value::Integer(1), Value::String("a".to_string())]),
+            Value::Empty,
+            Value::Null,
+            Value::I64(5),
+            Value::I64(i64::MIN),
+            Value::U64(5),
+            Value::U64(u64::MAX),
+            Value::Timestamp(1_706_702_400_250),
+            Value::Timestamp(-1),
+            Value::Timestamp(i64::MAX),
+            Value::Bytes(vec![]),
+            Value::Bytes(vec![0x00, 0xff]),
+            Value::Bytes(b"hello world".to_vec()),
+            Value::Map(HashMap::from([
+                ("name".to_string(), Value::String("Alice".to_string())),
+                ("id".to_string(), Value::U64(42)),
+            ])),
+            Value::Map(HashMap::from([("$i64".to_string(), Value::Integer(1))])),
+            Value::Map(HashMap::new()),
+        ]
+    }
+
+    #[test]
+    fn test_json_roundtrip() {
+        for value in sample_values() {
+            assert_identical(&json_roundtrip(&value), &value);
+        }
+    }
+
+    #[test]
+    fn test_bincode_roundtrip() {
+        for value in sample_values() {
+            assert_identical(&bincode_roundtrip(&value), &value);
+        }
+    }
+
+    #[test]
+    fn test_json_encoding() {
+        let json = |v: Value| serde_json::to_string(&v).unwrap();
+        assert_eq!(json(Value::Empty), "null");
+        assert_eq!(json(Value::Null), r#"{"$null":null}"#);
+        assert_eq!(json(Value::I64(-5)), r#"{"$i64":"-5"}"#);
+        assert_eq!(json(Value::U64(5)), r#"{"$u64":"5"}"#);
+        assert_eq!(
+            json(Value::Timestamp(1_706_702_400_250)),
+            r#"{"$timestamp":"2024-01-31T12:00:00.250Z"}"#
+        );
+        assert_eq!(json(Value::Bytes(vec![0x00, 0xff])), r#"{"$bytes":"AP8="}"#);
+    }
+
+    #[test]
+    fn test_json_plain_input() {
+        let parse = |s: &str| serde_json::from_str::<Value>(s).unwrap();
+        assert_identical(&parse("null"), &Value::Empty);
+        assert_identical(&parse("7"), &Value::Integer(7));
+        assert_identical(&parse("5000000000"), &Value::I64(5_000_000_000));
+        assert_identical(&parse("18446744073709551615"), &Value::U64(u64::MAX));
+        assert_identical(
+            &parse(r#"{"$timestamp":"2024-01-31T13:00:00.250+01:00"}"#),
+            &Value::Timestamp(1_706_702_400_250),
+        );
+        assert!(serde_json::from_str::<Value>(r#"{"$bytes":"AP8"}"#).is_err());
+        assert!(serde_json::from_str::<Value>(r#"{"$i64":"1","a":2}"#).is_err());
+    }
+
+    #[test]
+    fn test_value_ordering_matches_ordered_encoding() {
+        let values = vec![
+            Value::Empty,
+            Value::Null,
+            Value::Boolean(false),
+            Value::Boolean(true),
+            Value::Float(f64::NEG_INFINITY),
+            Value::I64(i64::MIN),
+            Value::Integer(-3),
+            Value::Float(-2.5),
+            Value::Float(0.0),
+            Value::Float(0.5),
+            Value::Integer(1),
+            Value::I64(9_007_199_254_740_993), // 2^53 + 1, not representable as f64
+            Value::U64(u64::MAX),
+            Value::Float(f64::INFINITY),
+            Value::Float(f64::NAN),
+            Value::Timestamp(-1),
+            Value::Timestamp(1_706_702_400_250),
+            Value::from(""),
+            Value::from("B"),
+            Value::from("a"),
+            Value::from("a\0"),
+            Value::from("ab"),
+            Value::from("b"),
+            Value::from("\u{e9}"), // byte order, so "é" sorts after "b"
+            Value::Bytes(vec![0]),
+            Value::Bytes(vec![0, 0]),
+            Value::Array(vec![]),
+            Value::Array(vec![Value::from(1), Value::from("z")]),
+            Value::Array(vec![Value::from(2)]),
+            Value::Map(HashMap::new()),
+            Value::Map(HashMap::from([("a".to_string(), Value::from(1))])),
+        ];
+
+        for (i, a) in values.iter().enumerate() {
+            for (j, b) in values.iter().enumerate() {
+                assert_eq!(a.cmp(b), i.cmp(&j), "{:?} vs {:?}", a, b);
+                assert_eq!(
+                    a.to_ordered_bytes().cmp(&b.to_ordered_bytes()),
+                    i.cmp(&j),
+                    "encoding of {:?} vs {:?}",
+                    a,
+                    b
+                );
+            }
+        }
+
+        // numeric promotion makes different widths of the same number equal
+        assert_eq!(Value::Integer(1), Value::Float(1.0));
+        assert_eq!(Value::I64(7), Value::U64(7));
+        assert_eq!(Value::Float(-0.0), Value::Integer(0));
+        assert_eq!(
+            Value::Integer(1).to_ordered_bytes(),
+            Value::Float(1.0).to_ordered_bytes()
+        );
+        assert_ne!(Value::Timestamp(1), Value::Integer(1));
+        assert!(Value::I64(9_007_199_254_740_993) > Value::Float(9_007_199_254_740_992.0));
+    }
+}